@@ -5,8 +5,8 @@
 /// The allow list of VA structures and enum values.
 const ALLOW_LIST_TYPE: &str =
     ".*ExternalBuffers.*|.*PRIME.*|.*MPEG2.*|.*VP8.*|.*VP9.*|.*H264.*|.*HEVC.*|\
-    .*JPEG.*|VACodedBufferSegment|.*AV1.*|VAEncMisc.*|VASurfaceDecodeMBErrors|\
-    VADecodeErrorType|.*VAProc.*|\
+    .*JPEG.*|VACodedBufferSegment|.*AV1.*|VAEncMisc.*|VAEncPackedHeader.*|VASurfaceDecodeMBErrors|\
+    VADecodeErrorType|.*VAProc.*|VADRMFormatModifierList|\
     VACenc.*|VA_TEE_.*|VAEncryption.*|VA_PROTECTED_.*";
 
 // The common bindgen builder for VA-API.
@@ -19,4 +19,8 @@ pub fn vaapi_gen_builder(builder: bindgen::Builder) -> bindgen::Builder {
         .allowlist_var("VA.*")
         .allowlist_function("va.*")
         .allowlist_type(ALLOW_LIST_TYPE)
+        // Xlib's `Display` would clash with the crate's own `Display`, so `vaGetDisplay` is
+        // declared by hand with an opaque pointer.
+        .blocklist_type("Display|_XDisplay")
+        .blocklist_function("vaGetDisplay")
 }
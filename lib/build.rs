@@ -112,6 +112,15 @@ fn main() {
     if va_check_version(1, 16) {
         println!("cargo::rustc-cfg=libva_1_16_or_higher")
     }
+    if va_check_version(1, 11) {
+        println!("cargo::rustc-cfg=libva_1_11_or_higher")
+    }
+    if va_check_version(1, 10) {
+        println!("cargo::rustc-cfg=libva_1_10_or_higher")
+    }
+    if va_check_version(1, 9) {
+        println!("cargo::rustc-cfg=libva_1_9_or_higher")
+    }
 
     if !va_lib_path.is_empty() {
         assert!(
@@ -125,12 +134,23 @@ fn main() {
     // Tell cargo to link va and va-drm objects dynamically.
     println!("cargo:rustc-link-lib=dylib=va");
     println!("cargo:rustc-link-lib=dylib=va-drm"); // for the vaGetDisplayDRM entrypoint
+    if std::env::var("CARGO_FEATURE_X11").is_ok() {
+        println!("cargo:rustc-link-lib=dylib=va-x11"); // for the vaGetDisplay entrypoint
+        println!("cargo:rustc-link-lib=dylib=X11");
+    }
+    if std::env::var("CARGO_FEATURE_GBM").is_ok() {
+        println!("cargo:rustc-link-lib=dylib=gbm"); // for gbm_bo_* used by GbmBoDescriptor
+    }
 
     let mut bindings_builder = vaapi_gen_builder(bindgen::builder()).header(WRAPPER_PATH);
     if !va_h_path.is_empty() {
         bindings_builder = bindings_builder.clang_arg(format!("-I{}", va_h_path));
     }
 
+    if std::env::var("CARGO_FEATURE_X11").is_ok() {
+        bindings_builder = bindings_builder.clang_arg("-DCROS_LIBVA_X11");
+    }
+
     if std::env::var("CARGO_FEATURE_INTEL_PROTECTED_CONTENT_HEADERS").is_ok() {
         println!("cargo:warning=Building with intel protected content headers!");
         bindings_builder = bindings_builder.clang_arg("-DINTEL_PROTECTED_CONTENT_HEADERS");
@@ -6,6 +6,10 @@
 #include <va/va_drm.h>
 #include <va/va_drmcommon.h>
 
+#if defined(CROS_LIBVA_X11)
+#include <va/va_x11.h>
+#endif  // defined(CROS_LIBVA_X11)
+
 #if defined(INTEL_PROTECTED_CONTENT_HEADERS)
 #include <va_protected_content.h>
 #endif  // defined(INTEL_PROTECTED_CONTENT_HEADERS)
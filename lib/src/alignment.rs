@@ -0,0 +1,221 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to compute the coded size and plane layout the driver expects for a surface.
+
+use crate::bindings;
+use crate::DriverQuirks;
+
+/// Rounds `value` up to the next multiple of `alignment`, which must be a power of two. Returns
+/// `None` if the result does not fit in a `u32`.
+fn align_up(value: u32, alignment: u32) -> Option<u32> {
+    debug_assert!(alignment.is_power_of_two());
+    Some(value.checked_add(alignment - 1)? & !(alignment - 1))
+}
+
+/// Layout of a single plane within a surface buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaneLayout {
+    /// Offset of the plane from the start of the buffer, in bytes.
+    pub offset: u32,
+    /// Number of bytes between two consecutive lines of the plane.
+    pub pitch: u32,
+    /// Number of lines of the plane.
+    pub height: u32,
+}
+
+/// Alignment constraints, in pixels, that the driver applies to the coded size of surfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SurfaceAlignment {
+    /// Alignment of the coded width.
+    pub width: u32,
+    /// Alignment of the coded height.
+    pub height: u32,
+}
+
+impl SurfaceAlignment {
+    /// Returns the alignment required by the block structure of the codec used by `profile`.
+    pub fn for_profile(profile: bindings::VAProfile::Type) -> Self {
+        let block_size = match profile {
+            bindings::VAProfile::VAProfileHEVCMain
+            | bindings::VAProfile::VAProfileHEVCMain10
+            | bindings::VAProfile::VAProfileHEVCMain12
+            | bindings::VAProfile::VAProfileHEVCMain422_10
+            | bindings::VAProfile::VAProfileHEVCMain422_12
+            | bindings::VAProfile::VAProfileHEVCMain444
+            | bindings::VAProfile::VAProfileHEVCMain444_10
+            | bindings::VAProfile::VAProfileHEVCMain444_12
+            | bindings::VAProfile::VAProfileHEVCSccMain
+            | bindings::VAProfile::VAProfileHEVCSccMain10
+            | bindings::VAProfile::VAProfileHEVCSccMain444
+            | bindings::VAProfile::VAProfileHEVCSccMain444_10
+            | bindings::VAProfile::VAProfileVP9Profile0
+            | bindings::VAProfile::VAProfileVP9Profile1
+            | bindings::VAProfile::VAProfileVP9Profile2
+            | bindings::VAProfile::VAProfileVP9Profile3 => 64,
+            // AV1 superblocks can be up to 128x128.
+            bindings::VAProfile::VAProfileAV1Profile0
+            | bindings::VAProfile::VAProfileAV1Profile1 => 128,
+            // Macroblock-based codecs (MPEG-2, H.264, VP8, JPEG) and video processing.
+            _ => 16,
+        };
+
+        Self {
+            width: block_size,
+            height: block_size,
+        }
+    }
+
+    /// Parses the value of the `VASurfaceAttribAlignmentSize` surface attribute, which stores the
+    /// log2 of the width alignment in bits 0-3 and the log2 of the height alignment in bits 4-7.
+    pub fn from_attribute_value(value: u32) -> Self {
+        Self {
+            width: 1 << (value & 0xf),
+            height: 1 << ((value >> 4) & 0xf),
+        }
+    }
+
+    /// Returns this alignment adjusted for the `quirks` of the driver, as returned by
+    /// [`crate::DriverInfo::quirks`] or known to the caller.
+    pub fn with_quirks(self, quirks: DriverQuirks) -> Self {
+        let mut alignment = self;
+
+        if quirks.contains(DriverQuirks::FIELD_ALIGNED_SURFACES) {
+            // Each field is aligned to the macroblock size.
+            alignment.height = alignment.height.max(32);
+        }
+
+        alignment
+    }
+
+    /// Returns an alignment satisfying the constraints of both `self` and `other`.
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            width: self.width.max(other.width),
+            height: self.height.max(other.height),
+        }
+    }
+
+    /// Returns the coded size of a surface with a visible size of `width`x`height`, or `None` if
+    /// it overflows.
+    pub fn coded_size(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        Some((align_up(width, self.width)?, align_up(height, self.height)?))
+    }
+
+    /// Returns the layout of the planes of a `fourcc` surface with a visible size of
+    /// `width`x`height`, with each pitch aligned to `pitch_alignment` bytes.
+    ///
+    /// This can be used to allocate external buffers that the driver will accept on import.
+    /// Returns `None` if `fourcc` is not supported by this helper, or if the layout overflows.
+    pub fn plane_layouts(
+        &self,
+        fourcc: u32,
+        width: u32,
+        height: u32,
+        pitch_alignment: u32,
+    ) -> Option<Vec<PlaneLayout>> {
+        let (width, height) = self.coded_size(width, height)?;
+        let chroma_height = height.div_ceil(2);
+        let width_x2 = width.checked_mul(2)?;
+        let width_x4 = width.checked_mul(4)?;
+
+        // Width in bytes and height in lines of each plane.
+        let planes = match fourcc {
+            bindings::VA_FOURCC_NV12 => vec![(width, height), (width, chroma_height)],
+            bindings::VA_FOURCC_P010 | bindings::VA_FOURCC_P012 | bindings::VA_FOURCC_P016 => {
+                vec![(width_x2, height), (width_x2, chroma_height)]
+            }
+            bindings::VA_FOURCC_I420 | bindings::VA_FOURCC_YV12 => vec![
+                (width, height),
+                (width.div_ceil(2), chroma_height),
+                (width.div_ceil(2), chroma_height),
+            ],
+            bindings::VA_FOURCC_YUY2 => vec![(width_x2, height)],
+            bindings::VA_FOURCC_444P => vec![(width, height), (width, height), (width, height)],
+            bindings::VA_FOURCC_ARGB
+            | bindings::VA_FOURCC_ABGR
+            | bindings::VA_FOURCC_XRGB
+            | bindings::VA_FOURCC_XBGR
+            | bindings::VA_FOURCC_RGBA
+            | bindings::VA_FOURCC_BGRA
+            | bindings::VA_FOURCC_RGBX
+            | bindings::VA_FOURCC_BGRX => vec![(width_x4, height)],
+            _ => return None,
+        };
+
+        let mut offset: u32 = 0;
+        planes
+            .into_iter()
+            .map(|(bytes_per_line, lines)| {
+                let pitch = align_up(bytes_per_line, pitch_alignment)?;
+                let layout = PlaneLayout {
+                    offset,
+                    pitch,
+                    height: lines,
+                };
+                offset = offset.checked_add(pitch.checked_mul(lines)?)?;
+                Some(layout)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coded_size_and_layout() {
+        let h264 = SurfaceAlignment::for_profile(bindings::VAProfile::VAProfileH264Main);
+        assert_eq!(h264.coded_size(1920, 1080), Some((1920, 1088)));
+        assert_eq!(h264.coded_size(u32::MAX, 1080), None);
+
+        let hevc = SurfaceAlignment::for_profile(bindings::VAProfile::VAProfileHEVCMain10);
+        assert_eq!(hevc.coded_size(1920, 1080), Some((1920, 1088)));
+        assert_eq!(hevc.coded_size(1000, 1000), Some((1024, 1024)));
+
+        let fields = h264.with_quirks(DriverQuirks::FIELD_ALIGNED_SURFACES);
+        assert_eq!(fields.coded_size(1920, 1080), Some((1920, 1088)));
+        assert_eq!(fields.coded_size(1280, 720), Some((1280, 736)));
+        assert_eq!(h264.with_quirks(DriverQuirks::empty()), h264);
+
+        // 128x16 alignment as reported by a driver.
+        let driver = SurfaceAlignment::from_attribute_value(0x47);
+        assert_eq!(
+            driver,
+            SurfaceAlignment {
+                width: 128,
+                height: 16
+            }
+        );
+        assert_eq!(
+            h264.combine(driver).coded_size(1920, 1080),
+            Some((1920, 1088))
+        );
+
+        let planes = h264
+            .plane_layouts(bindings::VA_FOURCC_NV12, 100, 50, 64)
+            .unwrap();
+        assert_eq!(
+            planes,
+            vec![
+                PlaneLayout {
+                    offset: 0,
+                    pitch: 128,
+                    height: 64
+                },
+                PlaneLayout {
+                    offset: 128 * 64,
+                    pitch: 128,
+                    height: 32
+                },
+            ]
+        );
+
+        assert!(h264.plane_layouts(0, 100, 50, 64).is_none());
+        assert!(h264
+            .plane_layouts(bindings::VA_FOURCC_ARGB, 0x4000_0000, 16, 64)
+            .is_none());
+    }
+}
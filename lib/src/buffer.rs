@@ -5,40 +5,96 @@
 //! Wrappers and helpers around `VABuffer`s.
 
 mod av1;
+mod coded_output;
 mod enc_jpeg;
 mod enc_misc;
+mod enc_packed_header;
 mod h264;
 mod hevc;
 mod jpeg_baseline;
 mod mpeg2;
+mod proc_filter;
 mod proc_pipeline;
+mod slice_data;
 mod vp8;
 mod vp9;
 
 pub use av1::*;
+pub use coded_output::*;
 pub use enc_jpeg::*;
 pub use enc_misc::*;
+pub use enc_packed_header::*;
 pub use h264::*;
 pub use hevc::*;
 pub use jpeg_baseline::*;
 pub use mpeg2::*;
+pub use proc_filter::*;
 pub use proc_pipeline::*;
+pub use slice_data::*;
 pub use vp8::*;
 pub use vp9::*;
 
+use std::any::Any;
+use std::num::NonZeroI32;
 use std::rc::Rc;
 
+use bitflags::bitflags;
 use log::error;
 
 use crate::bindings;
+use crate::copy;
+use crate::copy::CopyObject;
 use crate::va_check;
 use crate::Context;
+use crate::CopyMode;
 use crate::VaError;
+use crate::VaObjectKind;
+
+bitflags! {
+    /// Intended accesses to a mapped buffer, aka `VA_MAPBUFFER_FLAG_*`.
+    ///
+    /// These let the driver skip unneeded cache operations, e.g. when reading back a coded
+    /// buffer. They are only passed to the driver with libva 1.21 or higher, which introduced
+    /// `vaMapBuffer2`, and ignored otherwise.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct MapFlags: u32 {
+        /// The mapping will be read from.
+        const READ = 1;
+        /// The mapping will be written to.
+        const WRITE = 2;
+    }
+}
+
+/// Maps buffer `id` of `display` with `flags`, using `vaMapBuffer2` when available.
+pub(crate) fn map_buffer(
+    display: bindings::VADisplay,
+    id: bindings::VABufferID,
+    flags: MapFlags,
+) -> Result<*mut std::ffi::c_void, VaError> {
+    let mut addr = std::ptr::null_mut();
+
+    // Safe because the caller guarantees that `display` and `id` are valid.
+    #[cfg(libva_1_21_or_higher)]
+    va_check(unsafe { bindings::vaMapBuffer2(display, id, &mut addr, flags.bits()) })?;
+    #[cfg(not(libva_1_21_or_higher))]
+    {
+        let _ = flags;
+        // Safe because the caller guarantees that `display` and `id` are valid.
+        va_check(unsafe { bindings::vaMapBuffer(display, id, &mut addr) })?;
+    }
+
+    Ok(addr)
+}
 
 /// Wrapper type representing a buffer created with `vaCreateBuffer`.
 pub struct Buffer {
     context: Rc<Context>,
     id: bindings::VABufferID,
+    /// Packed header described by this buffer, if it is a packed header parameter buffer.
+    packed_header: PackedHeaders,
+    /// Owns the data pointed to by the parameters of this buffer. `vaCreateBuffer` only copies the
+    /// parameters themselves, and the driver follows their pointers when the picture is rendered.
+    _keep_alive: Option<Box<dyn Any>>,
 }
 
 impl Buffer {
@@ -47,6 +103,11 @@ impl Buffer {
     pub(crate) fn new(context: Rc<Context>, mut type_: BufferType) -> Result<Self, VaError> {
         let mut buffer_id = 0;
 
+        let packed_header = match type_ {
+            BufferType::EncPackedHeaderParameter(ref param) => param.packed_header(),
+            _ => PackedHeaders::empty(),
+        };
+
         /* we send all slices parameters as a single array in H264, AV1 */
         let nb_elements = match type_ {
             BufferType::SliceParameter(SliceParameter::H264(ref mut params)) => {
@@ -283,17 +344,54 @@ impl Buffer {
                     wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
                     std::mem::size_of_val(wrapper.inner_mut()),
                 ),
+                EncMiscParameter::CustomRoundingControl(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
+                EncMiscParameter::ROI(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
+                EncMiscParameter::TemporalLayerStructure(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
+                EncMiscParameter::RIR(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
             },
             BufferType::ProcPipelineParameter(ref mut proc_pipeline_param) => (
                 proc_pipeline_param.inner_mut() as *mut _ as *mut std::ffi::c_void,
                 std::mem::size_of_val(proc_pipeline_param.inner_mut()),
             ),
+            BufferType::ProcFilterParameter(ref mut proc_filter_param) => match proc_filter_param {
+                ProcFilterParameter::HVSNoiseReduction(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
+                ProcFilterParameter::Deinterlacing(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
+            },
             BufferType::QMatrix(ref mut q_matrix) => match q_matrix {
                 QMatrix::JPEG(ref mut wrapper) => (
                     wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
                     std::mem::size_of_val(wrapper.inner_mut()),
                 ),
+                QMatrix::VP8(ref mut wrapper) => (
+                    wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                    std::mem::size_of_val(wrapper.inner_mut()),
+                ),
             },
+            BufferType::EncPackedHeaderParameter(ref mut wrapper) => (
+                wrapper.inner_mut() as *mut _ as *mut std::ffi::c_void,
+                std::mem::size_of_val(wrapper.inner_mut()),
+            ),
+            BufferType::EncPackedHeaderData(ref mut data) => {
+                (data.as_mut_ptr() as *mut std::ffi::c_void, data.len())
+            }
         };
 
         // Safe because `self` represents a valid `VAContext`. `ptr` and `size` are also ensured to
@@ -311,12 +409,106 @@ impl Buffer {
             )
         })?;
 
+        context
+            .display()
+            .register_object(VaObjectKind::Buffer, buffer_id);
+
         Ok(Self {
             context,
             id: buffer_id,
+            packed_header,
+            _keep_alive: type_.into_keep_alive(),
+        })
+    }
+
+    /// Returns the packed header described by this buffer, or an empty set if it is not a
+    /// packed header parameter buffer.
+    pub fn packed_header(&self) -> PackedHeaders {
+        self.packed_header
+    }
+
+    /// Returns the type of this buffer and its size in bytes. Wrapper over `vaBufferInfo`.
+    fn info(&self) -> Result<(bindings::VABufferType::Type, usize), VaError> {
+        let mut type_ = 0;
+        let mut size = 0;
+        let mut num_elements = 0;
+
+        // Safe because `self` represents a valid buffer.
+        va_check(unsafe {
+            bindings::vaBufferInfo(
+                self.context.display().handle(),
+                self.id,
+                &mut type_,
+                &mut size,
+                &mut num_elements,
+            )
+        })?;
+
+        // A size that cannot be addressed can only come from a misbehaving driver.
+        let size = (size as usize)
+            .checked_mul(num_elements as usize)
+            .ok_or(VaError(
+                NonZeroI32::new(bindings::VA_STATUS_ERROR_INVALID_BUFFER as i32).unwrap(),
+            ))?;
+
+        Ok((type_, size))
+    }
+
+    /// Changes the number of elements of this buffer, e.g. to reuse it for a frame with fewer
+    /// slices than the one it was created for. Wrapper over `vaBufferSetNumElements`.
+    ///
+    /// `num_elements` cannot exceed the number of elements the buffer was created with, and the
+    /// buffer must not be mapped.
+    pub fn set_num_elements(&mut self, num_elements: u32) -> Result<(), VaError> {
+        // Safe because `self` represents a valid buffer, which cannot be mapped as mapping
+        // borrows it mutably.
+        va_check(unsafe {
+            bindings::vaBufferSetNumElements(self.context.display().handle(), self.id, num_elements)
+        })
+    }
+
+    /// Maps this buffer for the accesses described by `flags`. Wrapper over `vaMapBuffer2`, or
+    /// `vaMapBuffer` with libva versions older than 1.21.
+    pub fn map(&mut self, flags: MapFlags) -> Result<MappedBuffer<'_>, VaError> {
+        let (_, size) = self.info()?;
+        let addr = map_buffer(self.context.display().handle(), self.id, flags)?;
+
+        Ok(MappedBuffer {
+            buffer: self,
+            addr: addr as *mut u8,
+            size,
         })
     }
 
+    /// Returns the type of this buffer and a copy of its contents. Wrapper over `vaBufferInfo` and
+    /// `vaMapBuffer`, mostly useful for diagnostics.
+    pub fn read_contents(&self) -> Result<(bindings::VABufferType::Type, Vec<u8>), VaError> {
+        let display = self.context.display().handle();
+        let (type_, size) = self.info()?;
+        let addr = map_buffer(display, self.id, MapFlags::READ)?;
+
+        // Safe because the mapping is valid until `vaUnmapBuffer` is called, and is `size` bytes
+        // long as reported by `vaBufferInfo`.
+        let contents = unsafe { std::slice::from_raw_parts(addr as *const u8, size) }.to_vec();
+
+        // Safe because `self` represents a valid buffer that has been mapped above.
+        va_check(unsafe { bindings::vaUnmapBuffer(display, self.id) })?;
+
+        Ok((type_, contents))
+    }
+
+    /// Copies the contents of this buffer into `dst` on the GPU using `vaCopy`, waiting for the
+    /// copy to complete before returning. Fails with `VA_STATUS_ERROR_UNIMPLEMENTED` before
+    /// VA-API 1.10.
+    pub fn copy_to(&self, dst: &Buffer, mode: CopyMode) -> Result<(), VaError> {
+        copy::copy(
+            self.context.display(),
+            CopyObject::Buffer(dst.id),
+            CopyObject::Buffer(self.id),
+            mode,
+        )
+    }
+
     /// Convenience function to return a `VABufferID` vector from a slice of `Buffer`s in order to
     /// easily interface with the C API where a buffer array might be needed.
     pub fn as_id_vec(buffers: &[Self]) -> Vec<bindings::VABufferID> {
@@ -331,6 +523,9 @@ impl Drop for Buffer {
         let status = va_check(unsafe {
             bindings::vaDestroyBuffer(self.context.display().handle(), self.id)
         });
+        self.context
+            .display()
+            .unregister_object(VaObjectKind::Buffer, self.id);
 
         if status.is_err() {
             error!("vaDestroyBuffer failed: {}", status.unwrap_err());
@@ -338,6 +533,42 @@ impl Drop for Buffer {
     }
 }
 
+/// A mapping of a [`Buffer`], unmapped when dropped.
+pub struct MappedBuffer<'a> {
+    buffer: &'a mut Buffer,
+    addr: *mut u8,
+    size: usize,
+}
+
+impl<'a> MappedBuffer<'a> {
+    /// Returns the contents of the buffer.
+    pub fn as_slice(&self) -> &[u8] {
+        // Safe because the mapping is valid until `vaUnmapBuffer` is called in `drop`, and is
+        // `size` bytes long as reported by `vaBufferInfo`.
+        unsafe { std::slice::from_raw_parts(self.addr, self.size) }
+    }
+
+    /// Returns the contents of the buffer for modification.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // Safe because the mapping is valid until `vaUnmapBuffer` is called in `drop`, is `size`
+        // bytes long as reported by `vaBufferInfo`, and we hold the only reference to the buffer.
+        unsafe { std::slice::from_raw_parts_mut(self.addr, self.size) }
+    }
+}
+
+impl<'a> Drop for MappedBuffer<'a> {
+    fn drop(&mut self) {
+        // Safe because `self.buffer` represents a valid buffer that has been mapped.
+        let status = va_check(unsafe {
+            bindings::vaUnmapBuffer(self.buffer.context.display().handle(), self.buffer.id)
+        });
+
+        if status.is_err() {
+            error!("vaUnmapBuffer failed: {}", status.unwrap_err());
+        }
+    }
+}
+
 /// Abstraction over `VABufferType`s.
 pub enum BufferType {
     /// Abstraction over `VAPictureParameterBufferType`. Needed for MPEG2, VP8, VP9, H264, JPEGBaseline.
@@ -366,11 +597,68 @@ pub enum BufferType {
     EncMiscParameter(EncMiscParameter),
     /// Abstraction over `VAProcPipelineParameterBuffer`.
     ProcPipelineParameter(proc_pipeline::ProcPipelineParameterBuffer),
+    /// Abstraction over `VAProcFilterParameterBufferType`. The IDs of these buffers are passed to
+    /// the `filters` of a `ProcPipelineParameter` buffer.
+    ProcFilterParameter(ProcFilterParameter),
     /// Abstraction over `VAQMatrixBufferType`.
     QMatrix(QMatrix),
+    /// Abstraction over `VAEncPackedHeaderParameterBufferType`.
+    EncPackedHeaderParameter(EncPackedHeaderParameterBuffer),
+    /// Abstraction over `VAEncPackedHeaderDataBufferType`. Contains the header data described by
+    /// the preceding `EncPackedHeaderParameter` buffer.
+    EncPackedHeaderData(Vec<u8>),
+}
+
+/// Kind of operation a buffer can be submitted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Decode,
+    Encode,
+    Proc,
 }
 
 impl BufferType {
+    /// Returns whether this buffer can be submitted with a picture performing an operation of
+    /// kind `kind`.
+    pub fn supports(&self, kind: OperationKind) -> bool {
+        match self {
+            BufferType::PictureParameter(PictureParameter::EncJPEG(_))
+            | BufferType::SliceParameter(SliceParameter::EncJpeg(_)) => {
+                kind == OperationKind::Encode
+            }
+            BufferType::PictureParameter(_)
+            | BufferType::SliceParameter(_)
+            | BufferType::IQMatrix(_)
+            | BufferType::Probability(_)
+            | BufferType::SliceData(_) => kind == OperationKind::Decode,
+            // Huffman tables are used by both JPEG decode and encode.
+            BufferType::HuffmanTable(_) => kind != OperationKind::Proc,
+            BufferType::EncSequenceParameter(_)
+            | BufferType::EncPictureParameter(_)
+            | BufferType::EncSliceParameter(_)
+            | BufferType::EncMacroblockParameterBuffer(_)
+            | BufferType::EncCodedBuffer(_)
+            | BufferType::EncMiscParameter(_)
+            | BufferType::QMatrix(_)
+            | BufferType::EncPackedHeaderParameter(_)
+            | BufferType::EncPackedHeaderData(_) => kind == OperationKind::Encode,
+            BufferType::ProcPipelineParameter(_) | BufferType::ProcFilterParameter(_) => {
+                kind == OperationKind::Proc
+            }
+        }
+    }
+
+    /// Returns the part of this buffer type that owns data pointed to by its parameters, and thus
+    /// must outlive the [`Buffer`] created from it.
+    fn into_keep_alive(self) -> Option<Box<dyn Any>> {
+        match self {
+            BufferType::PictureParameter(PictureParameter::AV1(wrapper)) => Some(Box::new(wrapper)),
+            BufferType::ProcPipelineParameter(wrapper) => Some(Box::new(wrapper)),
+            BufferType::EncMiscParameter(EncMiscParameter::ROI(wrapper)) => Some(Box::new(wrapper)),
+            _ => None,
+        }
+    }
+
     /// Returns the inner FFI buffer type.
     pub(crate) fn inner(&self) -> bindings::VABufferType::Type {
         match self {
@@ -404,7 +692,16 @@ impl BufferType {
             BufferType::ProcPipelineParameter(_) => {
                 bindings::VABufferType::VAProcPipelineParameterBufferType
             }
+            BufferType::ProcFilterParameter(_) => {
+                bindings::VABufferType::VAProcFilterParameterBufferType
+            }
             BufferType::QMatrix(_) => bindings::VABufferType::VAQMatrixBufferType,
+            BufferType::EncPackedHeaderParameter(_) => {
+                bindings::VABufferType::VAEncPackedHeaderParameterBufferType
+            }
+            BufferType::EncPackedHeaderData(_) => {
+                bindings::VABufferType::VAEncPackedHeaderDataBufferType
+            }
         }
     }
 }
@@ -456,16 +753,24 @@ pub enum SliceParameter {
 }
 
 /// Abstraction over the `IQMatrixBuffer` types we support.
+///
+/// The order in which the coefficients of the matrices are expected differs between codecs, and
+/// does not always match the order in which they appear in the bitstream.
 pub enum IQMatrix {
-    /// Abstraction over `VAIQMatrixBufferMPEG2`
+    /// Abstraction over `VAIQMatrixBufferMPEG2`. Matrices are in zig-zag scan order, as in the
+    /// bitstream.
     MPEG2(mpeg2::IQMatrixBufferMPEG2),
-    /// Abstraction over `VAIQMatrixBufferVP8`
+    /// Abstraction over `VAIQMatrixBufferVP8`. Holds the quantization indices of each segment
+    /// rather than matrices.
     VP8(vp8::IQMatrixBufferVP8),
-    /// Abstraction over `VAIQMatrixBufferH264`
+    /// Abstraction over `VAIQMatrixBufferH264`. Scaling lists are in raster scan order, i.e. they
+    /// must be converted from the zig-zag order of the bitstream.
     H264(h264::IQMatrixBufferH264),
-    /// Abstraction over `VAIQMatrixBufferHEVC`
+    /// Abstraction over `VAIQMatrixBufferHEVC`. Scaling lists are in raster scan order, i.e. they
+    /// must be converted from the up-right diagonal order of the bitstream.
     HEVC(hevc::IQMatrixBufferHEVC),
-    /// Abstraction over `VAIQMatrixBufferJPEGBaseline``
+    /// Abstraction over `VAIQMatrixBufferJPEGBaseline`. Quantization tables are in zig-zag scan
+    /// order, as in the DQT segments of the bitstream.
     JPEGBaseline(jpeg_baseline::IQMatrixBufferJPEGBaseline),
 }
 
@@ -479,6 +784,8 @@ pub enum HuffmanTable {
 pub enum QMatrix {
     /// Abstraction over `VAQMatrixBufferJPEG`
     JPEG(enc_jpeg::QMatrixBufferJPEG),
+    /// Abstraction over `VAQMatrixBufferVP8`
+    VP8(vp8::QMatrixBufferVP8),
 }
 
 /// Abstraction over the `EncSequenceParameter` types we support.
@@ -540,6 +847,50 @@ impl EncCodedBuffer {
     pub fn id(&self) -> bindings::VABufferID {
         self.0.id
     }
+
+    /// Wrapper around `vaSyncBuffer` that blocks until the encode operation producing this buffer
+    /// has completed, or until `timeout_ns` nanoseconds have elapsed.
+    ///
+    /// Returns `Ok(false)` if the timeout expired before the buffer became ready.
+    ///
+    /// `vaSyncBuffer` is only available from VA-API 1.9, and some drivers do not implement it
+    /// (see [`crate::DriverQuirks::NO_SYNC_BUFFER`]). In both cases this falls back to mapping the
+    /// buffer, which blocks until the encode operation has completed regardless of `timeout_ns`.
+    #[cfg(libva_1_9_or_higher)]
+    pub fn sync(&self, timeout_ns: u64) -> Result<bool, VaError> {
+        let display = self.0.context.display();
+        // Safe because `self` represents a valid `VABuffer`.
+        match display.check_device_lost(va_check(unsafe {
+            bindings::vaSyncBuffer(display.handle(), self.id(), timeout_ns)
+        })) {
+            Ok(()) => Ok(true),
+            Err(e) if e.va_status() == bindings::VA_STATUS_ERROR_TIMEDOUT as i32 => Ok(false),
+            Err(e) if e.va_status() == bindings::VA_STATUS_ERROR_UNIMPLEMENTED as i32 => {
+                self.map().map(|_| true)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fallback for [`Self::sync`] without `vaSyncBuffer`: mapping a coded buffer blocks until
+    /// the encode operation producing it has completed.
+    #[cfg(not(libva_1_9_or_higher))]
+    pub fn sync(&self, _timeout_ns: u64) -> Result<bool, VaError> {
+        self.map().map(|_| true)
+    }
+
+    /// Returns whether the encode operation producing this buffer has completed, without
+    /// blocking. Blocks until completion before VA-API 1.9, see [`Self::sync`].
+    pub fn is_ready(&self) -> Result<bool, VaError> {
+        self.sync(0)
+    }
+
+    /// Maps the buffer for reading, giving access to the segments of coded data it contains.
+    ///
+    /// This blocks until the encode operation producing this buffer has completed.
+    pub fn map(&self) -> Result<MappedCodedBuffer<'_>, VaError> {
+        MappedCodedBuffer::new_with_flags(self, MapFlags::READ)
+    }
 }
 
 /// Helper to access a single segment of mapped coded buffer
@@ -549,6 +900,18 @@ pub struct MappedCodedSegment<'s> {
     pub buf: &'s [u8],
 }
 
+impl MappedCodedSegment<'_> {
+    /// Returns whether a slice of this segment exceeded the maximum slice size.
+    pub fn slice_overflow(&self) -> bool {
+        self.status & bindings::VA_CODED_BUF_STATUS_SLICE_OVERFLOW_MASK != 0
+    }
+
+    /// Returns whether this segment holds a single NAL unit.
+    pub fn single_nalu(&self) -> bool {
+        self.status & bindings::VA_CODED_BUF_STATUS_SINGLE_NALU != 0
+    }
+}
+
 /// Helper to access segments of mapped coded buffer
 pub struct MappedCodedBuffer<'p> {
     segments: Vec<MappedCodedSegment<'p>>,
@@ -558,12 +921,14 @@ pub struct MappedCodedBuffer<'p> {
 impl<'p> MappedCodedBuffer<'p> {
     /// Map a 'VAEncCodedBufferType' buffer.
     pub fn new(buffer: &'p EncCodedBuffer) -> Result<Self, VaError> {
-        let mut addr = std::ptr::null_mut();
-        let mut segments = Vec::new();
+        Self::new_with_flags(buffer, MapFlags::empty())
+    }
 
-        va_check(unsafe {
-            bindings::vaMapBuffer(buffer.0.context.display().handle(), buffer.id(), &mut addr)
-        })?;
+    /// Map a 'VAEncCodedBufferType' buffer for the accesses described by `flags`. Passing
+    /// [`MapFlags::READ`] lets the driver optimize the readback of the coded data.
+    pub fn new_with_flags(buffer: &'p EncCodedBuffer, flags: MapFlags) -> Result<Self, VaError> {
+        let mut addr = map_buffer(buffer.0.context.display().handle(), buffer.id(), flags)?;
+        let mut segments = Vec::new();
 
         while !addr.is_null() {
             let segment: &bindings::VACodedBufferSegment =
@@ -595,6 +960,60 @@ impl<'p> MappedCodedBuffer<'p> {
     pub fn segments(&self) -> &Vec<MappedCodedSegment<'p>> {
         &self.segments
     }
+
+    /// Returns the statistics of the encoded frame, as reported in the segments.
+    pub fn stats(&self) -> FrameStats {
+        FrameStats::from_segments(&self.segments)
+    }
+}
+
+/// Statistics of an encoded frame, parsed from the `status` of its coded buffer segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// Size of the encoded frame, in bytes.
+    pub size: usize,
+    /// Average QP of the frame, if reported by the driver.
+    pub average_qp: u8,
+    /// Whether the frame has been skipped, i.e. no data has been produced.
+    pub skipped: bool,
+    /// Whether a slice exceeded the size limit.
+    pub slice_overflow: bool,
+    /// Whether the frame exceeded the bitrate budget of the rate control.
+    pub bitrate_overflow: bool,
+    /// Whether the frame exceeded the maximum frame size.
+    pub frame_size_overflow: bool,
+}
+
+impl FrameStats {
+    /// Parses the statistics of a frame from its coded buffer `segments`.
+    pub fn from_segments(segments: &[MappedCodedSegment]) -> Self {
+        let size = segments.iter().map(|segment| segment.buf.len()).sum();
+        let status = segments
+            .iter()
+            .fold(0, |status, segment| status | segment.status);
+        let average_qp = segments
+            .first()
+            .map(|segment| segment.status & bindings::VA_CODED_BUF_STATUS_PICTURE_AVE_QP_MASK)
+            .unwrap_or(0) as u8;
+
+        Self {
+            size,
+            average_qp,
+            skipped: size == 0,
+            slice_overflow: status & bindings::VA_CODED_BUF_STATUS_SLICE_OVERFLOW_MASK != 0,
+            bitrate_overflow: status & bindings::VA_CODED_BUF_STATUS_BITRATE_OVERFLOW != 0,
+            frame_size_overflow: status & bindings::VA_CODED_BUF_STATUS_FRAME_SIZE_OVERFLOW != 0,
+        }
+    }
+}
+
+impl<'a, 'p> IntoIterator for &'a MappedCodedBuffer<'p> {
+    type Item = &'a MappedCodedSegment<'p>;
+    type IntoIter = std::slice::Iter<'a, MappedCodedSegment<'p>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
 }
 
 impl<'p> Drop for MappedCodedBuffer<'p> {
@@ -627,4 +1046,48 @@ pub enum EncMiscParameter {
     QualityLevel(EncMiscParameterBufferQualityLevel),
     /// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterQuantization`.
     Quantization(EncMiscParameterQuantization),
+    /// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterCustomRoundingControl`.
+    CustomRoundingControl(EncMiscParameterCustomRoundingControl),
+    /// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterBufferROI`.
+    ROI(EncMiscParameterBufferROI),
+    /// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterTemporalLayerStructure`.
+    TemporalLayerStructure(EncMiscParameterTemporalLayerStructure),
+    /// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterRIR`.
+    RIR(EncMiscParameterRIR),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RoiRegion;
+
+    #[test]
+    fn roi_outlives_buffer_type() {
+        let region = RoiRegion {
+            x: 0,
+            y: 16,
+            width: 32,
+            height: 48,
+            delta_qp: -4,
+        };
+        let roi = EncMiscParameterBufferROI::new(&[region], 10, -10, true);
+        let roi_ptr = roi.inner().value.roi;
+
+        // `Buffer::new` consumes the buffer type after `vaCreateBuffer`, which only copies the
+        // `VAEncMiscParameterBufferROI` itself. The region array it points to must survive.
+        let keep_alive = BufferType::EncMiscParameter(EncMiscParameter::ROI(roi))
+            .into_keep_alive()
+            .unwrap();
+        let roi = keep_alive
+            .downcast_ref::<EncMiscParameterBufferROI>()
+            .unwrap();
+
+        assert_eq!(roi.inner().value.roi, roi_ptr);
+        assert_eq!(roi.rois().as_ptr(), roi_ptr as *const _);
+        assert_eq!(roi.rois()[0].roi_value, -4);
+
+        assert!(BufferType::SliceData(vec![0; 4])
+            .into_keep_alive()
+            .is_none());
+    }
 }
@@ -4,6 +4,8 @@
 
 //! Wrappers around AV1 `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
 /// Wrapper over the `seq_fields` bindgen field in `VADecPictureParameterBufferAV1`.
@@ -382,8 +384,96 @@ impl AV1QMatrixFields {
     }
 }
 
+/// Maximum loop filter level of an AV1 frame.
+pub const AV1_MAX_LOOP_FILTER: u8 = 63;
+
+/// Maximum number of tile columns or rows of an AV1 frame.
+pub const AV1_MAX_TILE_COLS_ROWS: u8 = 64;
+
+/// Error type for the `validate` methods of the AV1 parameter buffers.
+#[derive(Debug, Error)]
+pub enum AV1ParameterError {
+    #[error("loop filter level {0} is out of range 0..=63")]
+    InvalidLoopFilterLevel(u8),
+    #[error("loop filter delta {0} is out of range -64..=63")]
+    InvalidLoopFilterDelta(i8),
+    #[error("quantizer delta {0} is out of range -64..=63")]
+    InvalidDeltaQ(i8),
+    #[error("{0}x{1} tiles are out of range 1..=64")]
+    InvalidTiles(u8, u8),
+    #[error("CDEF damping {0} or bits {1} is out of range")]
+    InvalidCdef(u8, u8),
+    #[error("quantizer index range {0}..={1} is empty")]
+    InvalidQIndexRange(u8, u8),
+}
+
+/// The fields shared by the decode and encode picture parameters that `validate` checks.
+struct AV1FrameFields {
+    filter_levels: [u8; 4],
+    loop_filter_deltas: [i8; 10],
+    delta_q: [i8; 5],
+    tile_cols: u8,
+    tile_rows: u8,
+    cdef_damping_minus_3: u8,
+    cdef_bits: u8,
+}
+
+impl AV1FrameFields {
+    fn validate(&self) -> Result<(), AV1ParameterError> {
+        for level in self.filter_levels {
+            if level > AV1_MAX_LOOP_FILTER {
+                return Err(AV1ParameterError::InvalidLoopFilterLevel(level));
+            }
+        }
+        for delta in self.loop_filter_deltas {
+            if !(-64..=63).contains(&delta) {
+                return Err(AV1ParameterError::InvalidLoopFilterDelta(delta));
+            }
+        }
+        for delta in self.delta_q {
+            if !(-64..=63).contains(&delta) {
+                return Err(AV1ParameterError::InvalidDeltaQ(delta));
+            }
+        }
+        if !(1..=AV1_MAX_TILE_COLS_ROWS).contains(&self.tile_cols)
+            || !(1..=AV1_MAX_TILE_COLS_ROWS).contains(&self.tile_rows)
+        {
+            return Err(AV1ParameterError::InvalidTiles(
+                self.tile_cols,
+                self.tile_rows,
+            ));
+        }
+        if self.cdef_damping_minus_3 > 3 || self.cdef_bits > 3 {
+            return Err(AV1ParameterError::InvalidCdef(
+                self.cdef_damping_minus_3 + 3,
+                self.cdef_bits,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Concatenates the reference and mode loop filter deltas.
+fn av1_loop_filter_deltas(ref_deltas: [i8; 8], mode_deltas: [i8; 2]) -> [i8; 10] {
+    let mut deltas = [0; 10];
+    deltas[..8].copy_from_slice(&ref_deltas);
+    deltas[8..].copy_from_slice(&mode_deltas);
+    deltas
+}
+
 /// A wrapper over `VADecPictureParameterBufferAV1` FFI type
-pub struct PictureParameterBufferAV1(Box<bindings::VADecPictureParameterBufferAV1>);
+///
+/// For large-scale tile decoding (`large_scale_tile` set in `pic_info_fields`), the anchor frames
+/// are passed as `anchor_frames_list`. The driver reads that list when the picture is rendered, so
+/// the [`crate::Buffer`] created from this wrapper keeps it alive until it is dropped. The tiles of
+/// the tile list are then submitted using [`SliceParameterBufferAV1::from_tile_list`].
+pub struct PictureParameterBufferAV1 {
+    c_params: Box<bindings::VADecPictureParameterBufferAV1>,
+
+    // Owns the data pointed to by `c_params.anchor_frames_list`.
+    anchor_frames_list: Vec<bindings::VASurfaceID>,
+}
 
 impl PictureParameterBufferAV1 {
     /// Creates the wrapper
@@ -445,7 +535,13 @@ impl PictureParameterBufferAV1 {
         let mode_control_fields = mode_control_fields.0;
 
         let anchor_frames_num = anchor_frames_list.len() as u8;
-        let anchor_frames_list = anchor_frames_list.as_mut_ptr();
+        // The pointer remains valid after `anchor_frames_list` is moved into `Self`, as moving a
+        // `Vec` does not move its heap storage.
+        let anchor_frames_list_ptr = if anchor_frames_list.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            anchor_frames_list.as_mut_ptr()
+        };
 
         let film_grain_info = film_grain_info.0;
         let loop_restoration_fields = loop_restoration_fields.0;
@@ -458,7 +554,7 @@ impl PictureParameterBufferAV1 {
             .try_into()
             .unwrap();
 
-        Self(Box::new(bindings::VADecPictureParameterBufferAV1 {
+        let c_params = Box::new(bindings::VADecPictureParameterBufferAV1 {
             profile,
             order_hint_bits_minus_1,
             bit_depth_idx,
@@ -467,7 +563,7 @@ impl PictureParameterBufferAV1 {
             current_frame,
             current_display_picture,
             anchor_frames_num,
-            anchor_frames_list,
+            anchor_frames_list: anchor_frames_list_ptr,
             frame_width_minus1,
             frame_height_minus1,
             output_frame_width_in_tiles_minus_1,
@@ -508,14 +604,85 @@ impl PictureParameterBufferAV1 {
             loop_restoration_fields,
             wm,
             va_reserved: Default::default(),
-        }))
+        });
+
+        Self {
+            c_params,
+            anchor_frames_list,
+        }
+    }
+
+    /// Returns the anchor frames used for large-scale tile decoding.
+    pub fn anchor_frames(&self) -> &[bindings::VASurfaceID] {
+        &self.anchor_frames_list
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), AV1ParameterError> {
+        let p = self.c_params.as_ref();
+
+        AV1FrameFields {
+            filter_levels: [
+                p.filter_level[0],
+                p.filter_level[1],
+                p.filter_level_u,
+                p.filter_level_v,
+            ],
+            loop_filter_deltas: av1_loop_filter_deltas(p.ref_deltas, p.mode_deltas),
+            delta_q: [
+                p.y_dc_delta_q,
+                p.u_dc_delta_q,
+                p.u_ac_delta_q,
+                p.v_dc_delta_q,
+                p.v_ac_delta_q,
+            ],
+            tile_cols: p.tile_cols,
+            tile_rows: p.tile_rows,
+            cdef_damping_minus_3: p.cdef_damping_minus_3,
+            cdef_bits: p.cdef_bits,
+        }
+        .validate()
     }
 
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VADecPictureParameterBufferAV1 {
-        self.0.as_mut()
+        self.c_params.as_mut()
+    }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VADecPictureParameterBufferAV1 {
+        self.c_params.as_ref()
     }
 }
 
+/// Maximum number of tiles in the tile list of a large-scale tile frame.
+pub const MAX_AV1_TILE_LIST_ENTRIES: usize = 512;
+
+/// A tile of the tile list (`tile_list_obu`) of a large-scale tile frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AV1TileListEntry {
+    /// Index of the anchor frame the tile references in the anchor frames list.
+    pub anchor_frame_idx: u8,
+    pub tile_row: u16,
+    pub tile_column: u16,
+    /// Size of the tile data in the slice data buffer.
+    pub data_size: u32,
+    /// Offset of the tile data in the slice data buffer.
+    pub data_offset: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum AV1TileListError {
+    #[error("invalid number of tile list entries {0}")]
+    InvalidEntryCount(usize),
+    #[error("tile list entry {entry} references anchor frame {anchor_frame_idx} out of {anchor_frames_num}")]
+    InvalidAnchorFrame {
+        entry: usize,
+        anchor_frame_idx: u8,
+        anchor_frames_num: usize,
+    },
+}
+
 /// A wrapper over an array of the `VASliceParameterBufferAV1` FFI type. This
 /// allows for passing all tile parameters in a single call if multiple tiles
 /// are present in the tile group.
@@ -556,6 +723,71 @@ impl SliceParameterBufferAV1 {
         });
     }
 
+    /// Creates the slice parameters of the tile list of a large-scale tile frame, whose anchor
+    /// frames are `anchor_frames` (see [`PictureParameterBufferAV1::anchor_frames`]).
+    ///
+    /// The entries are submitted in tile list order, which determines where each tile is written
+    /// in the output frame.
+    pub fn from_tile_list(
+        anchor_frames: &[bindings::VASurfaceID],
+        entries: &[AV1TileListEntry],
+    ) -> Result<Self, AV1TileListError> {
+        if entries.is_empty() || entries.len() > MAX_AV1_TILE_LIST_ENTRIES {
+            return Err(AV1TileListError::InvalidEntryCount(entries.len()));
+        }
+
+        let mut params = Self::new();
+        for (i, entry) in entries.iter().enumerate() {
+            if usize::from(entry.anchor_frame_idx) >= anchor_frames.len() {
+                return Err(AV1TileListError::InvalidAnchorFrame {
+                    entry: i,
+                    anchor_frame_idx: entry.anchor_frame_idx,
+                    anchor_frames_num: anchor_frames.len(),
+                });
+            }
+
+            // Will not truncate, as the number of entries has been checked above.
+            params.add_tile_list_entry(
+                entry.data_size,
+                entry.data_offset,
+                entry.tile_row,
+                entry.tile_column,
+                entry.anchor_frame_idx,
+                i as u16,
+            );
+        }
+
+        Ok(params)
+    }
+
+    /// Adds the parameters of a tile from the tile list of a large-scale tile frame.
+    ///
+    /// `anchor_frame_idx` is the index into the anchor frames list of the picture parameters of
+    /// the frame the tile references, and `tile_idx_in_tile_list` the position of the tile in the
+    /// tile list, which determines where it is written in the output frame. Prefer
+    /// [`Self::from_tile_list`], which validates these indices.
+    pub fn add_tile_list_entry(
+        &mut self,
+        slice_data_size: u32,
+        slice_data_offset: u32,
+        tile_row: u16,
+        tile_column: u16,
+        anchor_frame_idx: u8,
+        tile_idx_in_tile_list: u16,
+    ) {
+        self.add_slice_parameter(
+            slice_data_size,
+            slice_data_offset,
+            bindings::VA_SLICE_DATA_FLAG_ALL,
+            tile_row,
+            tile_column,
+            0,
+            0,
+            anchor_frame_idx,
+            tile_idx_in_tile_list,
+        );
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut Vec<bindings::VASliceParameterBufferAV1> {
         self.0.as_mut()
     }
@@ -1134,6 +1366,42 @@ impl EncPictureParameterBufferAV1 {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), AV1ParameterError> {
+        let p = self.0.as_ref();
+
+        AV1FrameFields {
+            filter_levels: [
+                p.filter_level[0],
+                p.filter_level[1],
+                p.filter_level_u,
+                p.filter_level_v,
+            ],
+            loop_filter_deltas: av1_loop_filter_deltas(p.ref_deltas, p.mode_deltas),
+            delta_q: [
+                p.y_dc_delta_q,
+                p.u_dc_delta_q,
+                p.u_ac_delta_q,
+                p.v_dc_delta_q,
+                p.v_ac_delta_q,
+            ],
+            tile_cols: p.tile_cols,
+            tile_rows: p.tile_rows,
+            cdef_damping_minus_3: p.cdef_damping_minus_3,
+            cdef_bits: p.cdef_bits,
+        }
+        .validate()?;
+        if p.min_base_qindex > p.max_base_qindex {
+            return Err(AV1ParameterError::InvalidQIndexRange(
+                p.min_base_qindex,
+                p.max_base_qindex,
+            ));
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferAV1 {
         &mut self.0
     }
@@ -1154,3 +1422,79 @@ impl EncTileGroupBufferAV1 {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(anchor_frame_idx: u8, data_offset: u32) -> AV1TileListEntry {
+        AV1TileListEntry {
+            anchor_frame_idx,
+            tile_row: 0,
+            tile_column: 1,
+            data_size: 16,
+            data_offset,
+        }
+    }
+
+    #[test]
+    fn tile_list() {
+        let mut params =
+            SliceParameterBufferAV1::from_tile_list(&[3, 4], &[entry(1, 0), entry(0, 16)]).unwrap();
+        let params = params.inner_mut();
+
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].anchor_frame_idx, 1);
+        assert_eq!(params[0].tile_idx_in_tile_list, 0);
+        assert_eq!(params[1].slice_data_offset, 16);
+        assert_eq!(params[1].tile_idx_in_tile_list, 1);
+        assert_eq!(params[1].slice_data_flag, bindings::VA_SLICE_DATA_FLAG_ALL);
+    }
+
+    #[test]
+    fn tile_list_invalid() {
+        assert!(matches!(
+            SliceParameterBufferAV1::from_tile_list(&[3], &[]),
+            Err(AV1TileListError::InvalidEntryCount(0))
+        ));
+        assert!(matches!(
+            SliceParameterBufferAV1::from_tile_list(&[3], &[entry(0, 0), entry(1, 16)]),
+            Err(AV1TileListError::InvalidAnchorFrame {
+                entry: 1,
+                anchor_frame_idx: 1,
+                anchor_frames_num: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn frame_fields_ranges() {
+        let fields = AV1FrameFields {
+            filter_levels: [63, 0, 10, 10],
+            loop_filter_deltas: [-64, 63, 0, 0, 0, 0, 0, 0, 0, 0],
+            delta_q: [0; 5],
+            tile_cols: 64,
+            tile_rows: 1,
+            cdef_damping_minus_3: 3,
+            cdef_bits: 3,
+        };
+        assert!(fields.validate().is_ok());
+
+        assert!(matches!(
+            AV1FrameFields {
+                filter_levels: [64, 0, 0, 0],
+                ..fields
+            }
+            .validate(),
+            Err(AV1ParameterError::InvalidLoopFilterLevel(64))
+        ));
+        assert!(matches!(
+            AV1FrameFields {
+                tile_cols: 0,
+                ..fields
+            }
+            .validate(),
+            Err(AV1ParameterError::InvalidTiles(0, 1))
+        ));
+    }
+}
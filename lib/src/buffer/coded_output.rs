@@ -0,0 +1,85 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Pull-based retrieval of encoded output.
+
+use std::collections::VecDeque;
+
+use crate::EncCodedBuffer;
+use crate::VaError;
+
+/// FIFO of coded buffers whose encode operations have been submitted but not yet retrieved.
+///
+/// This decouples submission from retrieval: the caller pushes each coded buffer after submitting
+/// the picture encoding into it, along with some data `T` that must be kept alive until the
+/// operation has completed (e.g. the picture itself, or a timestamp), and later polls for
+/// completed outputs. Outputs are returned in submission order.
+pub struct CodedOutputQueue<T> {
+    pending: VecDeque<(EncCodedBuffer, T)>,
+}
+
+impl<T> Default for CodedOutputQueue<T> {
+    fn default() -> Self {
+        Self {
+            pending: Default::default(),
+        }
+    }
+}
+
+impl<T> CodedOutputQueue<T> {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `coded_buffer`, into which an encode operation has been submitted, to the queue.
+    pub fn push(&mut self, coded_buffer: EncCodedBuffer, data: T) {
+        self.pending.push_back((coded_buffer, data));
+    }
+
+    /// Returns the number of operations in flight.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no operation is in flight.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Returns the oldest output if its encode operation has completed, without blocking.
+    pub fn poll_output(&mut self) -> Result<Option<(EncCodedBuffer, T)>, VaError> {
+        self.wait_output(0)
+    }
+
+    /// Returns the oldest output, waiting at most `timeout_ns` nanoseconds for its encode
+    /// operation to complete. Returns `Ok(None)` if the queue is empty or the timeout expired.
+    pub fn wait_output(&mut self, timeout_ns: u64) -> Result<Option<(EncCodedBuffer, T)>, VaError> {
+        match self.pending.front() {
+            Some((coded_buffer, _)) if coded_buffer.sync(timeout_ns)? => {
+                Ok(self.pending.pop_front())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns an iterator over the outputs that have completed, in submission order. The
+    /// iteration stops at the first output that is not ready yet.
+    pub fn ready_outputs(&mut self) -> ReadyOutputs<'_, T> {
+        ReadyOutputs { queue: self }
+    }
+}
+
+/// Iterator returned by [`CodedOutputQueue::ready_outputs`].
+pub struct ReadyOutputs<'a, T> {
+    queue: &'a mut CodedOutputQueue<T>,
+}
+
+impl<'a, T> Iterator for ReadyOutputs<'a, T> {
+    type Item = Result<(EncCodedBuffer, T), VaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.poll_output().transpose()
+    }
+}
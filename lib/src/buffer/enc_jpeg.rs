@@ -5,6 +5,9 @@
 //! Wrappers around JPEG-Encoding `VABuffer` types.
 
 use crate::bindings;
+use crate::buffer::jpeg_baseline::validate_jpeg_huffman_table_selectors;
+use crate::buffer::jpeg_baseline::validate_jpeg_num_components;
+use crate::buffer::JpegParameterError;
 
 /// Wrapper over the `pic_flags` bindgen field in `VAEncPictureParameterBufferJPEG`
 pub struct PicFlags(bindings::_VAEncPictureParameterBufferJPEG__bindgen_ty_1);
@@ -69,6 +72,23 @@ impl EncPictureParameterBufferJPEG {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), JpegParameterError> {
+        let num_components = validate_jpeg_num_components(self.0.num_components)?;
+
+        for &selector in &self.0.quantiser_table_selector[..num_components] {
+            if selector > 3 {
+                return Err(JpegParameterError::InvalidQuantTableSelector(selector));
+            }
+        }
+        if !(1..=100).contains(&self.0.quality) {
+            return Err(JpegParameterError::InvalidQuality(self.0.quality));
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferJPEG {
         self.0.as_mut()
     }
@@ -118,6 +138,53 @@ impl EncSliceParameterBufferJPEG {
         }))
     }
 
+    /// Creates the interleaved scan of a baseline YUV picture with component IDs 1, 2 and 3, the
+    /// luma component using Huffman tables 0 and the chroma components Huffman tables 1.
+    pub fn new_yuv(restart_interval: u16) -> Self {
+        Self::new(
+            restart_interval,
+            3,
+            [
+                EncSliceParameterBufferJPEGComponent::new(1, 0, 0),
+                EncSliceParameterBufferJPEGComponent::new(2, 1, 1),
+                EncSliceParameterBufferJPEGComponent::new(3, 1, 1),
+                EncSliceParameterBufferJPEGComponent::new(0, 0, 0),
+            ],
+        )
+    }
+
+    /// Returns the start of scan (SOS) marker segment describing this scan, e.g. to be passed as
+    /// packed raw data when the driver does not generate the JPEG headers itself.
+    pub fn scan_header(&self) -> Vec<u8> {
+        let components = &self.0.components[..(self.0.num_components as usize).min(4)];
+        let length = 6 + 2 * components.len() as u16;
+
+        let mut header = vec![0xff, 0xda];
+        header.extend_from_slice(&length.to_be_bytes());
+        header.push(components.len() as u8);
+        for component in components {
+            header.push(component.component_selector);
+            header.push((component.dc_table_selector << 4) | (component.ac_table_selector & 0xf));
+        }
+        // Spectral selection start and end, and successive approximation, are fixed for
+        // baseline sequential JPEG.
+        header.extend_from_slice(&[0, 63, 0]);
+
+        header
+    }
+
+    /// Checks the ranges of the fields of this scan, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), JpegParameterError> {
+        let num_components = validate_jpeg_num_components(self.0.num_components)?;
+
+        validate_jpeg_huffman_table_selectors(
+            self.0.components[..num_components]
+                .iter()
+                .flat_map(|c| [c.dc_table_selector, c.ac_table_selector]),
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncSliceParameterBufferJPEG {
         self.0.as_mut()
     }
@@ -157,3 +224,30 @@ impl QMatrixBufferJPEG {
         self.0.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_header() {
+        assert_eq!(
+            EncSliceParameterBufferJPEG::new_yuv(0).scan_header(),
+            [0xff, 0xda, 0x00, 0x0c, 0x03, 0x01, 0x00, 0x02, 0x11, 0x03, 0x11, 0x00, 0x3f, 0x00]
+        );
+    }
+
+    #[test]
+    fn scan_validate() {
+        assert!(EncSliceParameterBufferJPEG::new_yuv(0).validate().is_ok());
+        assert!(matches!(
+            EncSliceParameterBufferJPEG::new(
+                0,
+                0,
+                [0, 1, 2, 3].map(|i| EncSliceParameterBufferJPEGComponent::new(i, 0, 0)),
+            )
+            .validate(),
+            Err(JpegParameterError::InvalidNumComponents(0))
+        ));
+    }
+}
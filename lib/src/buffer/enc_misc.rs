@@ -4,7 +4,13 @@
 
 //! Wrappers around `VAEncMisc*` types.
 
+use bitflags::bitflags;
+use thiserror::Error;
+
 use crate::bindings;
+use crate::EncMiscParameter;
+use crate::EncRoiCapabilities;
+use crate::RoiRegion;
 
 #[repr(C)]
 #[derive(Default)]
@@ -66,6 +72,56 @@ impl EncMiscParameterFrameRate {
     ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterFrameRate> {
         &mut self.0
     }
+
+    /// Creates the wrapper for a frame rate of `numerator / denominator` frames per second,
+    /// packed as expected by VA-API: the numerator in the low 16 bits of `framerate` and the
+    /// denominator in the high 16 bits.
+    pub fn new_fractional(
+        numerator: u32,
+        denominator: u32,
+        temporal_id: u32,
+    ) -> Result<Self, FrameRateError> {
+        if numerator == 0 || denominator == 0 {
+            return Err(FrameRateError::Zero);
+        }
+        if numerator > u32::from(u16::MAX) || denominator > u32::from(u16::MAX) {
+            return Err(FrameRateError::OutOfRange {
+                numerator,
+                denominator,
+            });
+        }
+
+        Ok(Self::new(numerator | (denominator << 16), temporal_id))
+    }
+
+    /// Returns the frame rate as a `(numerator, denominator)` pair, unpacking the fractional
+    /// encoding if needed.
+    pub fn fraction(&self) -> (u32, u32) {
+        unpack_framerate(self.0.value.framerate)
+    }
+
+    /// Returns the temporal layer the frame rate applies to.
+    pub fn temporal_id(&self) -> u32 {
+        // Safe because all the members of the union are plain integer types.
+        unsafe { self.0.value.framerate_flags.bits.temporal_id() }
+    }
+}
+
+/// Unpacks a frame rate in the format of `VAEncMiscParameterFrameRate::framerate` into a
+/// `(numerator, denominator)` pair.
+fn unpack_framerate(framerate: u32) -> (u32, u32) {
+    match framerate >> 16 {
+        0 => (framerate, 1),
+        denominator => (framerate & 0xffff, denominator),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FrameRateError {
+    #[error("the frame rate numerator and denominator must be non-zero")]
+    Zero,
+    #[error("frame rate {numerator}/{denominator} does not fit in 16 bits")]
+    OutOfRange { numerator: u32, denominator: u32 },
 }
 
 #[derive(Default)]
@@ -155,6 +211,235 @@ impl EncMiscParameterRateControl {
     }
 }
 
+/// Rate control modes, aka `VA_RC_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateControlMode {
+    /// Constant bitrate.
+    Cbr,
+    /// Variable bitrate.
+    Vbr,
+    /// Constant QP.
+    Cqp,
+    /// Intelligent constant quality.
+    Icq,
+    /// Quality-defined variable bitrate.
+    Qvbr,
+}
+
+impl RateControlMode {
+    /// Returns the `VA_RC_*` value of the mode, as used with `VAConfigAttribRateControl`.
+    pub fn va_rc(&self) -> u32 {
+        match self {
+            RateControlMode::Cbr => bindings::VA_RC_CBR,
+            RateControlMode::Vbr => bindings::VA_RC_VBR,
+            RateControlMode::Cqp => bindings::VA_RC_CQP,
+            RateControlMode::Icq => bindings::VA_RC_ICQ,
+            RateControlMode::Qvbr => bindings::VA_RC_QVBR,
+        }
+    }
+
+    /// Returns whether the mode is part of `rc_modes`, the value of the
+    /// `VAConfigAttribRateControl` attribute of a config.
+    pub fn is_supported(&self, rc_modes: u32) -> bool {
+        rc_modes & self.va_rc() != 0
+    }
+}
+
+/// Maximum quality factor of ICQ and QVBR.
+pub const MAX_RATE_CONTROL_QUALITY_FACTOR: u32 = 51;
+
+/// Returns the maximum QP of the codec of `profile`, as accepted by
+/// [`EncMiscParameterRateControlBuilder`].
+pub fn max_qp_for_profile(profile: bindings::VAProfile::Type) -> u32 {
+    match profile {
+        bindings::VAProfile::VAProfileMPEG2Simple | bindings::VAProfile::VAProfileMPEG2Main => 31,
+        bindings::VAProfile::VAProfileVP8Version0_3 => 127,
+        bindings::VAProfile::VAProfileVP9Profile0
+        | bindings::VAProfile::VAProfileVP9Profile1
+        | bindings::VAProfile::VAProfileVP9Profile2
+        | bindings::VAProfile::VAProfileVP9Profile3
+        | bindings::VAProfile::VAProfileAV1Profile0
+        | bindings::VAProfile::VAProfileAV1Profile1 => 255,
+        // H.264 and HEVC.
+        _ => 51,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RateControlError {
+    #[error("rate control mode {0:?} requires a non-zero bitrate")]
+    MissingBitrate(RateControlMode),
+    #[error("invalid target percentage {0}")]
+    InvalidTargetPercentage(u32),
+    #[error("target percentage {0} conflicts with CBR, which always targets 100")]
+    CbrTargetPercentage(u32),
+    #[error("invalid QP range {min}..={max}")]
+    InvalidQpRange { min: u32, max: u32 },
+    #[error("initial QP {qp} is outside of the QP range {min}..={max}")]
+    InvalidInitialQp { qp: u32, min: u32, max: u32 },
+    #[error("invalid quality factor {0}")]
+    InvalidQualityFactor(u32),
+}
+
+/// Builder for [`EncMiscParameterRateControl`] that only exposes the fields relevant to the
+/// selected [`RateControlMode`].
+#[derive(Debug, Clone)]
+pub struct EncMiscParameterRateControlBuilder {
+    mode: RateControlMode,
+    max_qp_limit: u32,
+    bits_per_second: u32,
+    target_percentage: u32,
+    window_size: u32,
+    initial_qp: u32,
+    min_qp: u32,
+    max_qp: u32,
+    quality_factor: u32,
+    temporal_id: u32,
+    reset: bool,
+}
+
+impl EncMiscParameterRateControlBuilder {
+    /// Creates a builder for `mode`, to encode with `profile`. The target percentage defaults to
+    /// 100 and the QP range is left to the driver.
+    pub fn new(mode: RateControlMode, profile: bindings::VAProfile::Type) -> Self {
+        Self {
+            mode,
+            max_qp_limit: max_qp_for_profile(profile),
+            bits_per_second: 0,
+            target_percentage: 100,
+            window_size: 0,
+            initial_qp: 0,
+            min_qp: 0,
+            max_qp: 0,
+            quality_factor: 0,
+            temporal_id: 0,
+            reset: false,
+        }
+    }
+
+    /// Sets the maximum bitrate, or the target bitrate for CBR.
+    pub fn bits_per_second(mut self, bits_per_second: u32) -> Self {
+        self.bits_per_second = bits_per_second;
+        self
+    }
+
+    /// Sets the target bitrate as a percentage of the maximum bitrate, for VBR and QVBR. CBR only
+    /// accepts 100.
+    pub fn target_percentage(mut self, target_percentage: u32) -> Self {
+        self.target_percentage = target_percentage;
+        self
+    }
+
+    /// Sets the rate control window size, in milliseconds.
+    pub fn window_size(mut self, window_size: u32) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    /// Sets the QP of the first frame, which must be within the QP range. Zero leaves it to the
+    /// driver.
+    pub fn initial_qp(mut self, initial_qp: u32) -> Self {
+        self.initial_qp = initial_qp;
+        self
+    }
+
+    /// Sets the range of QP the driver may use, up to the maximum QP of the codec (see
+    /// [`max_qp_for_profile`]). Zero leaves the bound to the driver.
+    pub fn qp_range(mut self, min_qp: u32, max_qp: u32) -> Self {
+        self.min_qp = min_qp;
+        self.max_qp = max_qp;
+        self
+    }
+
+    /// Sets the quality factor of ICQ and QVBR, from 1 (best) to 51.
+    pub fn quality_factor(mut self, quality_factor: u32) -> Self {
+        self.quality_factor = quality_factor;
+        self
+    }
+
+    /// Sets the temporal layer the parameters apply to.
+    pub fn temporal_id(mut self, temporal_id: u32) -> Self {
+        self.temporal_id = temporal_id;
+        self
+    }
+
+    /// Requests the driver to reset its rate control state.
+    pub fn reset(mut self, reset: bool) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    /// Validates the parameters and builds the misc parameter.
+    pub fn build(self) -> Result<EncMiscParameterRateControl, RateControlError> {
+        let needs_bitrate = matches!(
+            self.mode,
+            RateControlMode::Cbr | RateControlMode::Vbr | RateControlMode::Qvbr
+        );
+        if needs_bitrate && self.bits_per_second == 0 {
+            return Err(RateControlError::MissingBitrate(self.mode));
+        }
+
+        if self.target_percentage > 100 {
+            return Err(RateControlError::InvalidTargetPercentage(
+                self.target_percentage,
+            ));
+        }
+
+        if self.mode == RateControlMode::Cbr && self.target_percentage != 100 {
+            return Err(RateControlError::CbrTargetPercentage(
+                self.target_percentage,
+            ));
+        }
+
+        // A zero maximum QP leaves the bound to the driver, so the codec limit applies instead.
+        let max_qp = if self.max_qp != 0 {
+            self.max_qp
+        } else {
+            self.max_qp_limit
+        };
+        if self.max_qp > self.max_qp_limit || self.min_qp > max_qp {
+            return Err(RateControlError::InvalidQpRange {
+                min: self.min_qp,
+                max: self.max_qp,
+            });
+        }
+
+        if self.initial_qp != 0 && !(self.min_qp..=max_qp).contains(&self.initial_qp) {
+            return Err(RateControlError::InvalidInitialQp {
+                qp: self.initial_qp,
+                min: self.min_qp,
+                max: max_qp,
+            });
+        }
+
+        let needs_quality = matches!(self.mode, RateControlMode::Icq | RateControlMode::Qvbr);
+        if needs_quality && !(1..=MAX_RATE_CONTROL_QUALITY_FACTOR).contains(&self.quality_factor) {
+            return Err(RateControlError::InvalidQualityFactor(self.quality_factor));
+        }
+
+        let (icq_quality_factor, quality_factor) = match self.mode {
+            RateControlMode::Icq => (self.quality_factor, 0),
+            RateControlMode::Qvbr => (0, self.quality_factor),
+            _ => (0, 0),
+        };
+        let rc_flags = RcFlags::new(self.reset as u32, 0, 0, 0, self.temporal_id, 0, 0, 0, 0);
+
+        Ok(EncMiscParameterRateControl::new(
+            self.bits_per_second,
+            self.target_percentage,
+            self.window_size,
+            self.initial_qp,
+            self.min_qp,
+            0,
+            rc_flags,
+            icq_quality_factor,
+            self.max_qp,
+            quality_factor,
+            0,
+        ))
+    }
+}
+
 #[derive(Default)]
 pub struct EncMiscParameterMaxSliceSize(
     Box<MiscEncParamBuffer<bindings::VAEncMiscParameterMaxSliceSize>>,
@@ -163,7 +448,7 @@ pub struct EncMiscParameterMaxSliceSize(
 impl EncMiscParameterMaxSliceSize {
     pub fn new(max_slice_size: u32) -> Self {
         Self(MiscEncParamBuffer::new_boxed(
-            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeMaxFrameSize,
+            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeMaxSliceSize,
             bindings::VAEncMiscParameterMaxSliceSize {
                 max_slice_size,
                 ..Default::default()
@@ -180,6 +465,45 @@ impl EncMiscParameterMaxSliceSize {
     ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterMaxSliceSize> {
         &mut self.0
     }
+
+    /// Creates the wrapper after checking that `max_slice_size` is valid and that the driver
+    /// supports limiting the size of slices according to its `slice_structure`, as returned by
+    /// [`crate::Display::query_enc_slice_structure`].
+    pub fn new_checked(
+        max_slice_size: u32,
+        slice_structure: EncSliceStructure,
+    ) -> Result<Self, MaxSliceSizeError> {
+        if max_slice_size == 0 {
+            return Err(MaxSliceSizeError::InvalidSize);
+        }
+        if !slice_structure.contains(EncSliceStructure::MAX_SLICE_SIZE) {
+            return Err(MaxSliceSizeError::NotSupported);
+        }
+
+        Ok(Self::new(max_slice_size))
+    }
+}
+
+bitflags! {
+    /// Slice structures supported by an encoder, aka `VA_ENC_SLICE_STRUCTURE_*`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct EncSliceStructure: u32 {
+        const POWER_OF_TWO_ROWS = bindings::VA_ENC_SLICE_STRUCTURE_POWER_OF_TWO_ROWS;
+        const ARBITRARY_MACROBLOCKS = bindings::VA_ENC_SLICE_STRUCTURE_ARBITRARY_MACROBLOCKS;
+        const EQUAL_ROWS = bindings::VA_ENC_SLICE_STRUCTURE_EQUAL_ROWS;
+        /// Slices can be limited in size with [`EncMiscParameterMaxSliceSize`].
+        const MAX_SLICE_SIZE = bindings::VA_ENC_SLICE_STRUCTURE_MAX_SLICE_SIZE;
+        const ARBITRARY_ROWS = bindings::VA_ENC_SLICE_STRUCTURE_ARBITRARY_ROWS;
+        const EQUAL_MULTI_ROWS = bindings::VA_ENC_SLICE_STRUCTURE_EQUAL_MULTI_ROWS;
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MaxSliceSizeError {
+    #[error("the maximum slice size must be non-zero")]
+    InvalidSize,
+    #[error("the driver does not support limiting the size of slices")]
+    NotSupported,
 }
 
 #[derive(Default)]
@@ -237,6 +561,39 @@ impl EncMiscParameterSkipFrame {
     ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterSkipFrame> {
         &mut self.0
     }
+
+    /// Creates the wrapper telling the encoder that `num_skip_frames` frames were dropped by the
+    /// application before the current frame, which is encoded as normal. `size_skip_frames` is
+    /// the size of the dropped frames in bits, to be accounted for by the rate control.
+    pub fn frames_dropped(num_skip_frames: u8, size_skip_frames: u32) -> Self {
+        Self::new(
+            SkipFrameMode::PrecedingSkipped as u8,
+            num_skip_frames,
+            size_skip_frames,
+        )
+    }
+
+    /// Returns the skip mode of this buffer, or `None` if the flag has an unknown value.
+    pub fn mode(&self) -> Option<SkipFrameMode> {
+        match self.0.value.skip_frame_flag {
+            0 => Some(SkipFrameMode::Normal),
+            1 => Some(SkipFrameMode::PrecedingSkipped),
+            2 => Some(SkipFrameMode::CurrentSkipped),
+            _ => None,
+        }
+    }
+}
+
+/// Values of the `skip_frame_flag` of `VAEncMiscParameterSkipFrame`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SkipFrameMode {
+    /// No frame was skipped, encode as normal.
+    Normal = 0,
+    /// One or more frames were skipped before the current frame, which is encoded as normal.
+    PrecedingSkipped = 1,
+    /// The current frame is skipped: the encoder generates a skipped frame instead.
+    CurrentSkipped = 2,
 }
 
 #[derive(Default)]
@@ -261,6 +618,50 @@ impl EncMiscParameterHRD {
     pub(crate) fn inner_mut(&mut self) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterHRD> {
         &mut self.0
     }
+
+    /// Creates the wrapper after checking that the buffer is not empty and that its initial
+    /// fullness does not exceed its size. Both values are in bits.
+    pub fn new_checked(initial_buffer_fullness: u32, buffer_size: u32) -> Result<Self, HRDError> {
+        if buffer_size == 0 {
+            return Err(HRDError::EmptyBuffer);
+        }
+        if initial_buffer_fullness > buffer_size {
+            return Err(HRDError::FullnessExceedsSize {
+                initial_buffer_fullness,
+                buffer_size,
+            });
+        }
+
+        Ok(Self::new(initial_buffer_fullness, buffer_size))
+    }
+
+    /// Creates the wrapper for a stream of `bits_per_second`, with a buffer holding `buffer_ms`
+    /// milliseconds of data, initially filled at `initial_fullness_percent`.
+    pub fn for_bitrate(
+        bits_per_second: u32,
+        buffer_ms: u32,
+        initial_fullness_percent: u32,
+    ) -> Result<Self, HRDError> {
+        let buffer_size = u64::from(bits_per_second) * u64::from(buffer_ms) / 1000;
+        let buffer_size = u32::try_from(buffer_size).map_err(|_| HRDError::BufferTooLarge)?;
+        let initial_buffer_fullness =
+            (u64::from(buffer_size) * u64::from(initial_fullness_percent) / 100) as u32;
+
+        Self::new_checked(initial_buffer_fullness, buffer_size)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HRDError {
+    #[error("the HRD buffer size must be non-zero")]
+    EmptyBuffer,
+    #[error("the HRD buffer size does not fit in 32 bits")]
+    BufferTooLarge,
+    #[error("initial buffer fullness {initial_buffer_fullness} exceeds buffer size {buffer_size}")]
+    FullnessExceedsSize {
+        initial_buffer_fullness: u32,
+        buffer_size: u32,
+    },
 }
 
 #[derive(Default)]
@@ -288,6 +689,35 @@ impl EncMiscParameterBufferQualityLevel {
     ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterBufferQualityLevel> {
         &mut self.0
     }
+
+    /// Creates the wrapper after checking that `quality_level` is within `1..=quality_range`,
+    /// `quality_range` being the value returned by [`crate::Display::query_enc_quality_range`].
+    ///
+    /// Level 1 is the best quality, `quality_range` the fastest.
+    pub fn new_checked(quality_level: u32, quality_range: u32) -> Result<Self, QualityLevelError> {
+        if quality_range == 0 {
+            return Err(QualityLevelError::NotSupported);
+        }
+        if !(1..=quality_range).contains(&quality_level) {
+            return Err(QualityLevelError::OutOfRange {
+                quality_level,
+                quality_range,
+            });
+        }
+
+        Ok(Self::new(quality_level))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QualityLevelError {
+    #[error("the driver does not support quality levels")]
+    NotSupported,
+    #[error("quality level {quality_level} is not within 1..={quality_range}")]
+    OutOfRange {
+        quality_level: u32,
+        quality_range: u32,
+    },
 }
 
 #[derive(Default)]
@@ -335,3 +765,839 @@ impl EncMiscParameterQuantization {
         &mut self.0
     }
 }
+
+#[derive(Default)]
+pub struct EncMiscParameterCustomRoundingControl(
+    Box<MiscEncParamBuffer<bindings::VAEncMiscParameterCustomRoundingControl>>,
+);
+
+impl EncMiscParameterCustomRoundingControl {
+    /// Creates the wrapper. `rounding_offset_intra` and `rounding_offset_inter` are the 7-bit
+    /// rounding offsets to use for intra and inter blocks respectively, `None` keeping the
+    /// driver's default rounding.
+    pub fn new(rounding_offset_intra: Option<u8>, rounding_offset_inter: Option<u8>) -> Self {
+        use bindings::_VAEncMiscParameterCustomRoundingControl__bindgen_ty_1 as RoundingOffsetSetting;
+        use bindings::_VAEncMiscParameterCustomRoundingControl__bindgen_ty_1__bindgen_ty_1 as RoundingOffsetBits;
+
+        let _bitfield_1 = RoundingOffsetBits::new_bitfield_1(
+            rounding_offset_intra.is_some() as u32,
+            rounding_offset_intra.unwrap_or(0) as u32 & 0x7f,
+            rounding_offset_inter.is_some() as u32,
+            rounding_offset_inter.unwrap_or(0) as u32 & 0x7f,
+            Default::default(),
+        );
+
+        Self(MiscEncParamBuffer::new_boxed(
+            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeCustomRoundingControl,
+            bindings::VAEncMiscParameterCustomRoundingControl {
+                rounding_offset_setting: RoundingOffsetSetting {
+                    bits: RoundingOffsetBits {
+                        _bitfield_align_1: Default::default(),
+                        _bitfield_1,
+                    },
+                },
+            },
+        ))
+    }
+
+    pub fn inner(&self) -> &MiscEncParamBuffer<bindings::VAEncMiscParameterCustomRoundingControl> {
+        &self.0
+    }
+
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterCustomRoundingControl> {
+        &mut self.0
+    }
+}
+
+/// Direction in which rolling intra refresh moves across the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntraRefreshDirection {
+    /// Intra columns moving from left to right, aka `VA_ENC_INTRA_REFRESH_ROLLING_COLUMN`.
+    Column,
+    /// Intra rows moving from top to bottom, aka `VA_ENC_INTRA_REFRESH_ROLLING_ROW`.
+    Row,
+}
+
+impl IntraRefreshDirection {
+    /// Returns whether the direction is part of `intra_refresh`, the value of the
+    /// `VAConfigAttribEncIntraRefresh` attribute of a config.
+    pub fn is_supported(&self, intra_refresh: u32) -> bool {
+        if intra_refresh == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return false;
+        }
+
+        let flag = match self {
+            Self::Column => bindings::VA_ENC_INTRA_REFRESH_ROLLING_COLUMN,
+            Self::Row => bindings::VA_ENC_INTRA_REFRESH_ROLLING_ROW,
+        };
+
+        intra_refresh & flag != 0
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum IntraRefreshError {
+    #[error("the driver does not support rolling intra refresh by {0:?}")]
+    NotSupported(IntraRefreshDirection),
+    #[error("the intra refresh size must be non-zero")]
+    InvalidSize,
+    #[error(
+        "intra refresh of {size} blocks at {location} exceeds the {blocks} blocks of the frame"
+    )]
+    OutOfFrame {
+        location: u16,
+        size: u16,
+        blocks: u16,
+    },
+}
+
+/// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterRIR`.
+#[derive(Default)]
+pub struct EncMiscParameterRIR(Box<MiscEncParamBuffer<bindings::VAEncMiscParameterRIR>>);
+
+impl EncMiscParameterRIR {
+    /// Creates the wrapper. `intra_insertion_location` and `intra_insert_size` are the position and
+    /// size of the intra-coded area of the frame, in macroblocks or CTUs along `direction`.
+    pub fn new(
+        direction: IntraRefreshDirection,
+        intra_insertion_location: u16,
+        intra_insert_size: u16,
+        qp_delta_for_inserted_intra: u8,
+    ) -> Self {
+        let rir_flags = match direction {
+            IntraRefreshDirection::Column => 1,
+            IntraRefreshDirection::Row => 1 << 1,
+        };
+
+        Self(MiscEncParamBuffer::new_boxed(
+            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeRIR,
+            bindings::VAEncMiscParameterRIR {
+                rir_flags: bindings::_VAEncMiscParameterRIR__bindgen_ty_1 { value: rir_flags },
+                intra_insertion_location,
+                intra_insert_size,
+                qp_delta_for_inserted_intra,
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Creates the wrapper after checking that the driver supports `direction` according to
+    /// `intra_refresh`, the value of the `VAConfigAttribEncIntraRefresh` attribute, and that the
+    /// intra-coded area fits within the `blocks` macroblocks or CTUs of the frame along
+    /// `direction`.
+    pub fn new_checked(
+        direction: IntraRefreshDirection,
+        intra_insertion_location: u16,
+        intra_insert_size: u16,
+        qp_delta_for_inserted_intra: u8,
+        blocks: u16,
+        intra_refresh: u32,
+    ) -> Result<Self, IntraRefreshError> {
+        if !direction.is_supported(intra_refresh) {
+            return Err(IntraRefreshError::NotSupported(direction));
+        }
+        if intra_insert_size == 0 {
+            return Err(IntraRefreshError::InvalidSize);
+        }
+        if u32::from(intra_insertion_location) + u32::from(intra_insert_size) > u32::from(blocks) {
+            return Err(IntraRefreshError::OutOfFrame {
+                location: intra_insertion_location,
+                size: intra_insert_size,
+                blocks,
+            });
+        }
+
+        Ok(Self::new(
+            direction,
+            intra_insertion_location,
+            intra_insert_size,
+            qp_delta_for_inserted_intra,
+        ))
+    }
+
+    pub fn inner(&self) -> &MiscEncParamBuffer<bindings::VAEncMiscParameterRIR> {
+        &self.0
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterRIR> {
+        &mut self.0
+    }
+}
+
+/// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterBufferROI`.
+pub struct EncMiscParameterBufferROI {
+    buffer: Box<MiscEncParamBuffer<bindings::VAEncMiscParameterBufferROI>>,
+
+    // Owns the data pointed to by `buffer`.
+    rois: Vec<bindings::VAEncROI>,
+}
+
+impl EncMiscParameterBufferROI {
+    /// Creates the wrapper for `regions`, ordered by decreasing priority.
+    ///
+    /// If `roi_value_is_qp_delta` is set, the `delta_qp` of each region is a QP delta clamped by
+    /// the driver to `min_delta_qp..=max_delta_qp`. Otherwise it is a priority, with higher values
+    /// meaning a better quality.
+    pub fn new(
+        regions: &[RoiRegion],
+        max_delta_qp: i8,
+        min_delta_qp: i8,
+        roi_value_is_qp_delta: bool,
+    ) -> Self {
+        let mut rois: Vec<bindings::VAEncROI> = regions
+            .iter()
+            .map(|region| bindings::VAEncROI {
+                roi_rectangle: region.rectangle(),
+                roi_value: region.delta_qp,
+            })
+            .collect();
+
+        let buffer = MiscEncParamBuffer::new_boxed(
+            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeROI,
+            bindings::VAEncMiscParameterBufferROI {
+                num_roi: rois.len() as u32,
+                max_delta_qp,
+                min_delta_qp,
+                roi: if rois.is_empty() {
+                    std::ptr::null_mut()
+                } else {
+                    rois.as_mut_ptr()
+                },
+                roi_flags: bindings::_VAEncMiscParameterBufferROI__bindgen_ty_1 {
+                    value: roi_value_is_qp_delta as u32,
+                },
+                ..Default::default()
+            },
+        );
+
+        Self { buffer, rois }
+    }
+
+    /// Creates the wrapper after checking `regions` against the ROI capabilities of the config,
+    /// as returned by [`crate::Display::query_enc_roi`].
+    pub fn new_checked(
+        regions: &[RoiRegion],
+        max_delta_qp: i8,
+        min_delta_qp: i8,
+        roi_value_is_qp_delta: bool,
+        caps: &EncRoiCapabilities,
+    ) -> Result<Self, RoiError> {
+        if regions.len() > caps.max_regions as usize {
+            return Err(RoiError::TooManyRegions {
+                requested: regions.len(),
+                max: caps.max_regions,
+            });
+        }
+        if roi_value_is_qp_delta && !caps.rc_qp_delta_support {
+            return Err(RoiError::QpDeltaNotSupported);
+        }
+        if !roi_value_is_qp_delta && !caps.rc_priority_support {
+            return Err(RoiError::PriorityNotSupported);
+        }
+        if min_delta_qp > max_delta_qp {
+            return Err(RoiError::InvalidDeltaRange {
+                min: min_delta_qp,
+                max: max_delta_qp,
+            });
+        }
+
+        Ok(Self::new(
+            regions,
+            max_delta_qp,
+            min_delta_qp,
+            roi_value_is_qp_delta,
+        ))
+    }
+
+    /// Returns the regions of this buffer.
+    pub fn rois(&self) -> &[bindings::VAEncROI] {
+        &self.rois
+    }
+
+    pub fn inner(&self) -> &MiscEncParamBuffer<bindings::VAEncMiscParameterBufferROI> {
+        &self.buffer
+    }
+
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterBufferROI> {
+        &mut self.buffer
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RoiError {
+    #[error("{requested} ROI regions requested but the driver supports at most {max}")]
+    TooManyRegions { requested: usize, max: u32 },
+    #[error("the driver does not support ROI QP deltas")]
+    QpDeltaNotSupported,
+    #[error("the driver does not support ROI priorities")]
+    PriorityNotSupported,
+    #[error("invalid ROI QP delta range {min}..={max}")]
+    InvalidDeltaRange { min: i8, max: i8 },
+}
+
+/// Maximum number of temporal layers addressable through the `temporal_id` of the rate control
+/// and frame rate misc parameters.
+pub const MAX_TEMPORAL_LAYERS: usize = 8;
+
+/// Rate allocated to a temporal layer. As expected by VA-API, the values are cumulative, i.e. they
+/// include the rates of all the lower layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemporalLayerRate {
+    pub bits_per_second: u32,
+    /// Frame rate of the layer, in the format of `VAEncMiscParameterFrameRate::framerate`.
+    pub framerate: u32,
+}
+
+impl TemporalLayerRate {
+    /// Returns the frame rate of the layer as a `(numerator, denominator)` pair.
+    pub fn fraction(&self) -> (u32, u32) {
+        unpack_framerate(self.framerate)
+    }
+
+    /// Returns whether the frame rate of the layer is lower than that of `other`.
+    fn framerate_lower_than(&self, other: &Self) -> bool {
+        let (num, den) = self.fraction();
+        let (other_num, other_den) = other.fraction();
+
+        u64::from(num) * u64::from(other_den) < u64::from(other_num) * u64::from(den)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TemporalLayerRateError {
+    #[error("invalid number of temporal layers {0}")]
+    InvalidLayerCount(usize),
+    #[error("rate of temporal layer {0} is lower than the rate of the layer below it")]
+    NotCumulative(usize),
+}
+
+/// Per-temporal-layer rate control of an SVC stream.
+#[derive(Debug, Clone)]
+pub struct TemporalLayerRateControl {
+    layers: Vec<TemporalLayerRate>,
+}
+
+impl TemporalLayerRateControl {
+    /// Creates the rate control for `layers`, ordered from the base layer up.
+    pub fn new(layers: Vec<TemporalLayerRate>) -> Result<Self, TemporalLayerRateError> {
+        if layers.is_empty() || layers.len() > MAX_TEMPORAL_LAYERS {
+            return Err(TemporalLayerRateError::InvalidLayerCount(layers.len()));
+        }
+
+        for (i, pair) in layers.windows(2).enumerate() {
+            if pair[1].bits_per_second < pair[0].bits_per_second
+                || pair[1].framerate_lower_than(&pair[0])
+            {
+                return Err(TemporalLayerRateError::NotCumulative(i + 1));
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    /// Returns the rates of each layer.
+    pub fn layers(&self) -> &[TemporalLayerRate] {
+        &self.layers
+    }
+
+    /// Returns the rate control and frame rate misc parameters addressing each layer through its
+    /// `temporal_id`.
+    ///
+    /// `rate_control` is called for each layer with its bitrate and `RcFlags` carrying its
+    /// `temporal_id`, and returns the rate control parameters of the layer.
+    pub fn misc_parameters<F>(&self, mut rate_control: F) -> Vec<EncMiscParameter>
+    where
+        F: FnMut(u32, RcFlags) -> EncMiscParameterRateControl,
+    {
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(temporal_id, layer)| {
+                let temporal_id = temporal_id as u32;
+                let rc_flags = RcFlags::new(0, 0, 0, 0, temporal_id, 0, 0, 0, 0);
+
+                [
+                    EncMiscParameter::RateControl(rate_control(layer.bits_per_second, rc_flags)),
+                    EncMiscParameter::FrameRate(EncMiscParameterFrameRate::new(
+                        layer.framerate,
+                        temporal_id,
+                    )),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Maximum length of the layer pattern of [`EncMiscParameterTemporalLayerStructure`].
+pub const MAX_TEMPORAL_LAYER_PERIODICITY: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum TemporalLayerStructureError {
+    #[error("invalid temporal layer pattern length {0}")]
+    InvalidPeriodicity(usize),
+    #[error("the temporal layer pattern must start with the base layer")]
+    MissingBaseLayer,
+    #[error("temporal layer {0} is not used by the pattern")]
+    MissingLayer(u32),
+    #[error("invalid number of temporal layers {0}")]
+    InvalidLayerCount(u32),
+}
+
+/// Wrapper over `VAEncMiscParameterBuffer` with `VAEncMiscParameterTemporalLayerStructure`.
+#[derive(Default)]
+pub struct EncMiscParameterTemporalLayerStructure(
+    Box<MiscEncParamBuffer<bindings::VAEncMiscParameterTemporalLayerStructure>>,
+);
+
+impl EncMiscParameterTemporalLayerStructure {
+    /// Creates the wrapper from `layer_ids`, the temporal layer of each frame of the repeating
+    /// pattern, e.g. `[0, 2, 1, 2]` for the usual 3-layer structure.
+    pub fn new(layer_ids: &[u32]) -> Result<Self, TemporalLayerStructureError> {
+        if layer_ids.is_empty() || layer_ids.len() > MAX_TEMPORAL_LAYER_PERIODICITY {
+            return Err(TemporalLayerStructureError::InvalidPeriodicity(
+                layer_ids.len(),
+            ));
+        }
+        if layer_ids[0] != 0 {
+            return Err(TemporalLayerStructureError::MissingBaseLayer);
+        }
+
+        let number_of_layers = layer_ids.iter().max().copied().unwrap_or(0) + 1;
+        if number_of_layers as usize > MAX_TEMPORAL_LAYERS {
+            return Err(TemporalLayerStructureError::InvalidLayerCount(
+                number_of_layers,
+            ));
+        }
+        if let Some(missing) = (0..number_of_layers).find(|id| !layer_ids.contains(id)) {
+            return Err(TemporalLayerStructureError::MissingLayer(missing));
+        }
+
+        let mut layer_id = [0u32; MAX_TEMPORAL_LAYER_PERIODICITY];
+        layer_id[..layer_ids.len()].copy_from_slice(layer_ids);
+
+        Ok(Self(MiscEncParamBuffer::new_boxed(
+            bindings::VAEncMiscParameterType::VAEncMiscParameterTypeTemporalLayerStructure,
+            bindings::VAEncMiscParameterTemporalLayerStructure {
+                number_of_layers,
+                periodicity: layer_ids.len() as u32,
+                layer_id,
+                ..Default::default()
+            },
+        )))
+    }
+
+    /// Creates the wrapper for the structure commonly used with `number_of_layers` temporal
+    /// layers, e.g. by WebRTC, or `None` if there is no such structure.
+    pub fn for_layer_count(number_of_layers: u32) -> Option<Self> {
+        let layer_ids: &[u32] = match number_of_layers {
+            1 => &[0],
+            2 => &[0, 1],
+            3 => &[0, 2, 1, 2],
+            _ => return None,
+        };
+
+        Self::new(layer_ids).ok()
+    }
+
+    /// Returns the temporal layer of each frame of the pattern.
+    pub fn layer_ids(&self) -> &[u32] {
+        &self.0.value.layer_id[..self.0.value.periodicity as usize]
+    }
+
+    pub fn inner(&self) -> &MiscEncParamBuffer<bindings::VAEncMiscParameterTemporalLayerStructure> {
+        &self.0
+    }
+
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> &mut MiscEncParamBuffer<bindings::VAEncMiscParameterTemporalLayerStructure> {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporal_layer_rate_control() {
+        let layers = vec![
+            TemporalLayerRate {
+                bits_per_second: 500_000,
+                framerate: 15,
+            },
+            TemporalLayerRate {
+                bits_per_second: 1_000_000,
+                framerate: 30,
+            },
+        ];
+
+        let rc = TemporalLayerRateControl::new(layers.clone()).unwrap();
+        let params = rc.misc_parameters(|bits_per_second, rc_flags| {
+            EncMiscParameterRateControl::new(
+                bits_per_second,
+                100,
+                1000,
+                26,
+                10,
+                0,
+                rc_flags,
+                0,
+                51,
+                0,
+                0,
+            )
+        });
+        assert_eq!(params.len(), 4);
+        match &params[2] {
+            EncMiscParameter::RateControl(rc) => {
+                assert_eq!(rc.inner().value.bits_per_second, 1_000_000);
+                // Safe because all the members of the union are plain integer types.
+                assert_eq!(unsafe { rc.inner().value.rc_flags.bits.temporal_id() }, 1);
+            }
+            _ => panic!("expected a rate control parameter"),
+        }
+
+        let reversed = layers.into_iter().rev().collect();
+        assert!(matches!(
+            TemporalLayerRateControl::new(reversed),
+            Err(TemporalLayerRateError::NotCumulative(1))
+        ));
+        assert!(TemporalLayerRateControl::new(vec![]).is_err());
+
+        // Frame rates are compared as fractions: 30000/1001 is higher than 15, but lower than 30
+        // even though its packed value is higher.
+        let fractional = vec![
+            TemporalLayerRate {
+                bits_per_second: 500_000,
+                framerate: 15,
+            },
+            TemporalLayerRate {
+                bits_per_second: 1_000_000,
+                framerate: 30000 | (1001 << 16),
+            },
+        ];
+        assert!(TemporalLayerRateControl::new(fractional).is_ok());
+        let decreasing = vec![
+            TemporalLayerRate {
+                bits_per_second: 500_000,
+                framerate: 30,
+            },
+            TemporalLayerRate {
+                bits_per_second: 1_000_000,
+                framerate: 30000 | (1001 << 16),
+            },
+        ];
+        assert!(matches!(
+            TemporalLayerRateControl::new(decreasing),
+            Err(TemporalLayerRateError::NotCumulative(1))
+        ));
+    }
+
+    #[test]
+    fn temporal_layer_structure() {
+        let structure = EncMiscParameterTemporalLayerStructure::for_layer_count(3).unwrap();
+        assert_eq!(structure.inner().value.number_of_layers, 3);
+        assert_eq!(structure.inner().value.periodicity, 4);
+        assert_eq!(structure.layer_ids(), &[0, 2, 1, 2]);
+        assert!(EncMiscParameterTemporalLayerStructure::for_layer_count(4).is_none());
+
+        assert!(matches!(
+            EncMiscParameterTemporalLayerStructure::new(&[]),
+            Err(TemporalLayerStructureError::InvalidPeriodicity(0))
+        ));
+        assert!(matches!(
+            EncMiscParameterTemporalLayerStructure::new(&[1, 0]),
+            Err(TemporalLayerStructureError::MissingBaseLayer)
+        ));
+        assert!(matches!(
+            EncMiscParameterTemporalLayerStructure::new(&[0, 2, 0, 2]),
+            Err(TemporalLayerStructureError::MissingLayer(1))
+        ));
+    }
+
+    #[test]
+    fn rolling_intra_refresh() {
+        let intra_refresh = bindings::VA_ENC_INTRA_REFRESH_ROLLING_ROW;
+
+        let rir = EncMiscParameterRIR::new_checked(
+            IntraRefreshDirection::Row,
+            60,
+            8,
+            2,
+            68,
+            intra_refresh,
+        )
+        .unwrap();
+        // Safe because all the members of the union are plain integer types.
+        assert_eq!(unsafe { rir.inner().value.rir_flags.value }, 1 << 1);
+        assert_eq!(rir.inner().value.intra_insertion_location, 60);
+        assert_eq!(rir.inner().value.intra_insert_size, 8);
+
+        assert!(matches!(
+            EncMiscParameterRIR::new_checked(
+                IntraRefreshDirection::Column,
+                0,
+                8,
+                0,
+                68,
+                intra_refresh
+            ),
+            Err(IntraRefreshError::NotSupported(
+                IntraRefreshDirection::Column
+            ))
+        ));
+        assert!(matches!(
+            EncMiscParameterRIR::new_checked(
+                IntraRefreshDirection::Row,
+                64,
+                8,
+                0,
+                68,
+                intra_refresh
+            ),
+            Err(IntraRefreshError::OutOfFrame { .. })
+        ));
+        assert!(!IntraRefreshDirection::Row.is_supported(bindings::VA_ATTRIB_NOT_SUPPORTED));
+    }
+
+    #[test]
+    fn roi_buffer() {
+        let regions = [
+            RoiRegion {
+                x: 0,
+                y: 16,
+                width: 32,
+                height: 48,
+                delta_qp: -4,
+            },
+            RoiRegion {
+                x: 64,
+                y: 0,
+                width: 16,
+                height: 16,
+                delta_qp: 2,
+            },
+        ];
+        let caps = EncRoiCapabilities {
+            max_regions: 2,
+            rc_priority_support: false,
+            rc_qp_delta_support: true,
+        };
+
+        let roi = EncMiscParameterBufferROI::new_checked(&regions, 8, -8, true, &caps).unwrap();
+        assert_eq!(roi.inner().value.num_roi, 2);
+        assert_eq!(roi.inner().value.roi, roi.rois().as_ptr() as *mut _);
+        assert_eq!(roi.rois()[0].roi_rectangle.y, 16);
+        assert_eq!(roi.rois()[0].roi_rectangle.height, 48);
+        assert_eq!(roi.rois()[1].roi_value, 2);
+        // Safe because all the members of the union are plain integer types.
+        assert_eq!(unsafe { roi.inner().value.roi_flags.value }, 1);
+
+        assert!(matches!(
+            EncMiscParameterBufferROI::new_checked(&regions, 8, -8, false, &caps),
+            Err(RoiError::PriorityNotSupported)
+        ));
+        assert!(matches!(
+            EncMiscParameterBufferROI::new_checked(&regions, -8, 8, true, &caps),
+            Err(RoiError::InvalidDeltaRange { min: 8, max: -8 })
+        ));
+        let caps = EncRoiCapabilities {
+            max_regions: 1,
+            ..caps
+        };
+        assert!(matches!(
+            EncMiscParameterBufferROI::new_checked(&regions, 8, -8, true, &caps),
+            Err(RoiError::TooManyRegions {
+                requested: 2,
+                max: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn quality_level_range() {
+        let quality_level = EncMiscParameterBufferQualityLevel::new_checked(4, 7).unwrap();
+        assert_eq!(quality_level.inner().value.quality_level, 4);
+
+        assert!(matches!(
+            EncMiscParameterBufferQualityLevel::new_checked(1, 0),
+            Err(QualityLevelError::NotSupported)
+        ));
+        assert!(matches!(
+            EncMiscParameterBufferQualityLevel::new_checked(0, 7),
+            Err(QualityLevelError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            EncMiscParameterBufferQualityLevel::new_checked(8, 7),
+            Err(QualityLevelError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn fractional_framerate() {
+        let framerate = EncMiscParameterFrameRate::new_fractional(30000, 1001, 2).unwrap();
+        assert_eq!(framerate.inner().value.framerate, 30000 | (1001 << 16));
+        assert_eq!(framerate.fraction(), (30000, 1001));
+        assert_eq!(framerate.temporal_id(), 2);
+
+        assert_eq!(EncMiscParameterFrameRate::new(30, 0).fraction(), (30, 1));
+        assert!(matches!(
+            EncMiscParameterFrameRate::new_fractional(30, 0, 0),
+            Err(FrameRateError::Zero)
+        ));
+        assert!(matches!(
+            EncMiscParameterFrameRate::new_fractional(70000, 1, 0),
+            Err(FrameRateError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn skip_frame() {
+        let skip_frame = EncMiscParameterSkipFrame::frames_dropped(2, 12_000);
+        assert_eq!(skip_frame.inner().value.skip_frame_flag, 1);
+        assert_eq!(skip_frame.inner().value.num_skip_frames, 2);
+        assert_eq!(skip_frame.inner().value.size_skip_frames, 12_000);
+        assert_eq!(skip_frame.mode(), Some(SkipFrameMode::PrecedingSkipped));
+        assert_eq!(EncMiscParameterSkipFrame::new(3, 0, 0).mode(), None);
+    }
+
+    #[test]
+    fn hrd_for_bitrate() {
+        let hrd = EncMiscParameterHRD::for_bitrate(4_000_000, 1500, 50).unwrap();
+        assert_eq!(hrd.inner().value.buffer_size, 6_000_000);
+        assert_eq!(hrd.inner().value.initial_buffer_fullness, 3_000_000);
+
+        assert!(matches!(
+            EncMiscParameterHRD::for_bitrate(0, 1000, 50),
+            Err(HRDError::EmptyBuffer)
+        ));
+        assert!(matches!(
+            EncMiscParameterHRD::for_bitrate(1_000_000, 1000, 150),
+            Err(HRDError::FullnessExceedsSize { .. })
+        ));
+        assert!(matches!(
+            EncMiscParameterHRD::for_bitrate(u32::MAX, 2000, 50),
+            Err(HRDError::BufferTooLarge)
+        ));
+    }
+
+    #[test]
+    fn rate_control_builder() {
+        let h264 = bindings::VAProfile::VAProfileH264Main;
+        let vp9 = bindings::VAProfile::VAProfileVP9Profile0;
+
+        let rc = EncMiscParameterRateControlBuilder::new(RateControlMode::Cbr, h264)
+            .bits_per_second(2_000_000)
+            .qp_range(10, 40)
+            .build()
+            .unwrap();
+        assert_eq!(rc.inner().value.bits_per_second, 2_000_000);
+        assert_eq!(rc.inner().value.target_percentage, 100);
+        assert_eq!(rc.inner().value.min_qp, 10);
+        assert_eq!(rc.inner().value.max_qp, 40);
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cbr, h264)
+                .bits_per_second(2_000_000)
+                .target_percentage(50)
+                .build(),
+            Err(RateControlError::CbrTargetPercentage(50))
+        ));
+
+        let rc = EncMiscParameterRateControlBuilder::new(RateControlMode::Vbr, vp9)
+            .bits_per_second(2_000_000)
+            .target_percentage(50)
+            .qp_range(100, 200)
+            .build()
+            .unwrap();
+        assert_eq!(rc.inner().value.target_percentage, 50);
+        assert_eq!(rc.inner().value.max_qp, 200);
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(10, 200)
+                .build(),
+            Err(RateControlError::InvalidQpRange { min: 10, max: 200 })
+        ));
+
+        let rc = EncMiscParameterRateControlBuilder::new(RateControlMode::Icq, h264)
+            .quality_factor(25)
+            .build()
+            .unwrap();
+        assert_eq!(rc.inner().value.ICQ_quality_factor, 25);
+        assert_eq!(rc.inner().value.quality_factor, 0);
+
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Vbr, h264).build(),
+            Err(RateControlError::MissingBitrate(RateControlMode::Vbr))
+        ));
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(40, 10)
+                .build(),
+            Err(RateControlError::InvalidQpRange { min: 40, max: 10 })
+        ));
+        // The minimum QP is checked against the codec limit when the maximum is left to the
+        // driver.
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(52, 0)
+                .build(),
+            Err(RateControlError::InvalidQpRange { min: 52, max: 0 })
+        ));
+        assert!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(51, 0)
+                .build()
+                .is_ok()
+        );
+
+        let rc = EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+            .qp_range(10, 40)
+            .initial_qp(26)
+            .build()
+            .unwrap();
+        assert_eq!(rc.inner().value.initial_qp, 26);
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(10, 40)
+                .initial_qp(45)
+                .build(),
+            Err(RateControlError::InvalidInitialQp {
+                qp: 45,
+                min: 10,
+                max: 40
+            })
+        ));
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .qp_range(10, 40)
+                .initial_qp(5)
+                .build(),
+            Err(RateControlError::InvalidInitialQp {
+                qp: 5,
+                min: 10,
+                max: 40
+            })
+        ));
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Cqp, h264)
+                .initial_qp(60)
+                .build(),
+            Err(RateControlError::InvalidInitialQp {
+                qp: 60,
+                min: 0,
+                max: 51
+            })
+        ));
+        assert!(matches!(
+            EncMiscParameterRateControlBuilder::new(RateControlMode::Qvbr, h264)
+                .bits_per_second(1_000_000)
+                .build(),
+            Err(RateControlError::InvalidQualityFactor(0))
+        ));
+    }
+}
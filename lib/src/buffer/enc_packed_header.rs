@@ -0,0 +1,295 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Wrappers around packed header `VABuffer` types.
+
+use bitflags::bitflags;
+use thiserror::Error;
+
+use crate::bindings;
+use crate::BufferType;
+
+bitflags! {
+    /// Packed headers supported by a config or attached to a picture, aka
+    /// `VA_ENC_PACKED_HEADER_*`.
+    ///
+    /// The headers reported by `VAConfigAttribEncPackedHeaders` and selected at config creation
+    /// time are not generated by the driver, and must be provided by the client for the pictures
+    /// that carry them (see [`PackedHeaders::required_for_picture`]).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct PackedHeaders: u32 {
+        /// Sequence-level headers, e.g. SPS/VPS.
+        const SEQUENCE = bindings::VA_ENC_PACKED_HEADER_SEQUENCE;
+        /// Picture-level headers, e.g. PPS.
+        const PICTURE = bindings::VA_ENC_PACKED_HEADER_PICTURE;
+        /// Slice headers.
+        const SLICE = bindings::VA_ENC_PACKED_HEADER_SLICE;
+        /// Miscellaneous headers, e.g. SEI.
+        const MISC = bindings::VA_ENC_PACKED_HEADER_MISC;
+        /// Raw data inserted as-is into the bitstream.
+        const RAW_DATA = bindings::VA_ENC_PACKED_HEADER_RAW_DATA;
+    }
+}
+
+impl PackedHeaders {
+    /// Returns the flag corresponding to a `VAEncPackedHeaderType` value.
+    pub fn from_packed_header_type(type_: u32) -> Self {
+        match type_ {
+            bindings::VAEncPackedHeaderType::VAEncPackedHeaderSequence => Self::SEQUENCE,
+            bindings::VAEncPackedHeaderType::VAEncPackedHeaderPicture => Self::PICTURE,
+            bindings::VAEncPackedHeaderType::VAEncPackedHeaderSlice => Self::SLICE,
+            bindings::VAEncPackedHeaderType::VAEncPackedHeaderRawData => Self::RAW_DATA,
+            // Codec-specific types (e.g. `VAEncPackedHeaderH264_SEI`) have the misc bit set.
+            t if t & bindings::VAEncPackedHeaderType::VAEncPackedHeaderMiscMask != 0 => Self::MISC,
+            _ => Self::empty(),
+        }
+    }
+
+    /// Returns the packed headers among those enabled in `self` that must be attached to a
+    /// picture. Slice headers are needed by every picture, while sequence and picture headers are
+    /// only needed by the pictures starting a sequence (e.g. IDR or key frames). Miscellaneous
+    /// headers and raw data are always optional.
+    pub fn required_for_picture(&self, starts_sequence: bool) -> PackedHeaders {
+        let required = if starts_sequence {
+            Self::SEQUENCE | Self::PICTURE | Self::SLICE
+        } else {
+            Self::SLICE
+        };
+
+        self.intersection(required)
+    }
+
+    /// Checks the packed headers `attached` to a picture, `self` being the headers enabled in the
+    /// config. All the headers in `required` must be attached, and only enabled headers may be.
+    pub fn check_attached(
+        &self,
+        required: PackedHeaders,
+        attached: PackedHeaders,
+    ) -> Result<(), PackedHeadersError> {
+        let missing = required.difference(attached);
+        if !missing.is_empty() {
+            return Err(PackedHeadersError::Missing(missing));
+        }
+
+        let unexpected = attached.difference(*self);
+        if !unexpected.is_empty() {
+            return Err(PackedHeadersError::Unexpected(unexpected));
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when the packed headers attached to a picture do not match those the config
+/// requires.
+#[derive(Debug, Error)]
+pub enum PackedHeadersError {
+    #[error("missing packed headers: {0:?}")]
+    Missing(PackedHeaders),
+    #[error("packed headers not enabled in the config: {0:?}")]
+    Unexpected(PackedHeaders),
+}
+
+/// Wrapper over the `VAEncPackedHeaderParameterBuffer` FFI type.
+///
+/// Must be followed by the header data itself, as a
+/// [`BufferType::EncPackedHeaderData`](crate::BufferType::EncPackedHeaderData) buffer.
+pub struct EncPackedHeaderParameterBuffer(Box<bindings::VAEncPackedHeaderParameterBuffer>);
+
+impl EncPackedHeaderParameterBuffer {
+    /// Creates the wrapper.
+    ///
+    /// `type_` is one of `VAEncPackedHeaderType`, `bit_length` the length of the header data in
+    /// bits, and `has_emulation_bytes` whether the data already contains emulation prevention
+    /// bytes.
+    pub fn new(type_: u32, bit_length: u32, has_emulation_bytes: bool) -> Self {
+        Self(Box::new(bindings::VAEncPackedHeaderParameterBuffer {
+            type_,
+            bit_length,
+            has_emulation_bytes: has_emulation_bytes as u8,
+            va_reserved: Default::default(),
+        }))
+    }
+
+    /// Returns the packed header flag corresponding to the type of this header.
+    pub fn packed_header(&self) -> PackedHeaders {
+        PackedHeaders::from_packed_header_type(self.0.type_)
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPackedHeaderParameterBuffer {
+        self.0.as_mut()
+    }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VAEncPackedHeaderParameterBuffer {
+        self.0.as_ref()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PackedHeaderError {
+    #[error("the packed header data is empty")]
+    EmptyData,
+    #[error("bit length {bit_length} does not match the {data_len} bytes of packed header data")]
+    BitLengthMismatch { bit_length: u32, data_len: usize },
+}
+
+/// A caller-generated header (e.g. SPS, PPS, SEI or slice header) to insert into the bitstream,
+/// along with the bit-length bookkeeping expected by the driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedHeader {
+    type_: u32,
+    data: Vec<u8>,
+    bit_length: u32,
+    has_emulation_bytes: bool,
+}
+
+impl PackedHeader {
+    /// Creates a header of `type_`, one of `VAEncPackedHeaderType`, from `data` of which only the
+    /// first `bit_length` bits are valid. The padding bits of the last byte are ignored.
+    pub fn new(
+        type_: u32,
+        data: Vec<u8>,
+        bit_length: u32,
+        has_emulation_bytes: bool,
+    ) -> Result<Self, PackedHeaderError> {
+        if data.is_empty() {
+            return Err(PackedHeaderError::EmptyData);
+        }
+        if (bit_length as usize).div_ceil(8) != data.len() {
+            return Err(PackedHeaderError::BitLengthMismatch {
+                bit_length,
+                data_len: data.len(),
+            });
+        }
+
+        Ok(Self {
+            type_,
+            data,
+            bit_length,
+            has_emulation_bytes,
+        })
+    }
+
+    /// Creates a header of `type_` from `data`, all bits of which are valid.
+    pub fn from_bytes(
+        type_: u32,
+        data: Vec<u8>,
+        has_emulation_bytes: bool,
+    ) -> Result<Self, PackedHeaderError> {
+        let bit_length = data.len() as u32 * 8;
+
+        Self::new(type_, data, bit_length, has_emulation_bytes)
+    }
+
+    /// Returns the packed header flag corresponding to the type of this header.
+    pub fn packed_header(&self) -> PackedHeaders {
+        PackedHeaders::from_packed_header_type(self.type_)
+    }
+
+    /// Returns the length of the header, in bits.
+    pub fn bit_length(&self) -> u32 {
+        self.bit_length
+    }
+
+    /// Returns the header data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the parameter and data buffers to submit with the picture to insert this header.
+    pub fn into_buffers(self) -> [BufferType; 2] {
+        let param = EncPackedHeaderParameterBuffer::new(
+            self.type_,
+            self.bit_length,
+            self.has_emulation_bytes,
+        );
+
+        [
+            BufferType::EncPackedHeaderParameter(param),
+            BufferType::EncPackedHeaderData(self.data),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_attached_packed_headers() {
+        let enabled = PackedHeaders::SEQUENCE
+            | PackedHeaders::PICTURE
+            | PackedHeaders::SLICE
+            | PackedHeaders::RAW_DATA;
+        let key_frame = enabled.required_for_picture(true);
+        let inter_frame = enabled.required_for_picture(false);
+
+        assert_eq!(
+            key_frame,
+            PackedHeaders::SEQUENCE | PackedHeaders::PICTURE | PackedHeaders::SLICE
+        );
+        assert_eq!(inter_frame, PackedHeaders::SLICE);
+        assert_eq!(
+            PackedHeaders::SLICE.required_for_picture(true),
+            PackedHeaders::SLICE
+        );
+
+        assert!(enabled.check_attached(key_frame, key_frame).is_ok());
+        assert!(enabled
+            .check_attached(key_frame, key_frame | PackedHeaders::RAW_DATA)
+            .is_ok());
+        assert!(enabled
+            .check_attached(inter_frame, PackedHeaders::SLICE)
+            .is_ok());
+        assert!(matches!(
+            enabled.check_attached(key_frame, PackedHeaders::SEQUENCE),
+            Err(PackedHeadersError::Missing(m)) if m == PackedHeaders::PICTURE | PackedHeaders::SLICE
+        ));
+        assert!(matches!(
+            enabled.check_attached(inter_frame, PackedHeaders::SLICE | PackedHeaders::MISC),
+            Err(PackedHeadersError::Unexpected(PackedHeaders::MISC))
+        ));
+        assert_eq!(
+            PackedHeaders::from_packed_header_type(
+                bindings::VAEncPackedHeaderTypeH264::VAEncPackedHeaderH264_SEI
+            ),
+            PackedHeaders::MISC
+        );
+    }
+
+    #[test]
+    fn packed_header_bit_length() {
+        let type_ = bindings::VAEncPackedHeaderType::VAEncPackedHeaderSlice;
+
+        let header = PackedHeader::new(type_, vec![0x00, 0x00, 0x01, 0x80], 25, false).unwrap();
+        assert_eq!(header.bit_length(), 25);
+        assert_eq!(header.packed_header(), PackedHeaders::SLICE);
+
+        let [param, data] = header.into_buffers();
+        match param {
+            BufferType::EncPackedHeaderParameter(param) => {
+                assert_eq!(param.inner().bit_length, 25);
+                assert_eq!(param.inner().has_emulation_bytes, 0);
+            }
+            _ => panic!("expected a packed header parameter buffer"),
+        }
+        assert!(matches!(data, BufferType::EncPackedHeaderData(d) if d.len() == 4));
+
+        assert!(matches!(
+            PackedHeader::new(type_, vec![0x00, 0x01], 17, false),
+            Err(PackedHeaderError::BitLengthMismatch { .. })
+        ));
+        assert!(matches!(
+            PackedHeader::from_bytes(type_, vec![], false),
+            Err(PackedHeaderError::EmptyData)
+        ));
+        assert_eq!(
+            PackedHeader::from_bytes(type_, vec![0; 3], true)
+                .unwrap()
+                .bit_length(),
+            24
+        );
+    }
+}
@@ -4,6 +4,8 @@
 
 //! Wrappers around H264 `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
 /// Wrapper over the `VAPictureH264` FFI type.
@@ -27,6 +29,28 @@ impl PictureH264 {
             va_reserved: Default::default(),
         })
     }
+
+    /// Returns whether `pic` does not reference any picture.
+    fn is_invalid_raw(pic: &bindings::VAPictureH264) -> bool {
+        pic.picture_id == bindings::VA_INVALID_SURFACE
+            || pic.flags & bindings::VA_PICTURE_H264_INVALID != 0
+    }
+}
+
+/// Returns whether `profile` is one of the H.264 multiview (MVC) profiles.
+pub fn is_h264_mvc_profile(profile: bindings::VAProfile::Type) -> bool {
+    matches!(
+        profile,
+        bindings::VAProfile::VAProfileH264MultiviewHigh
+            | bindings::VAProfile::VAProfileH264StereoHigh
+    )
+}
+
+/// Error type for [`PictureParameterBufferH264::add_inter_view_references`].
+#[derive(Debug, Error)]
+pub enum H264InterViewRefError {
+    #[error("not enough free reference frame slots for {0} inter-view references")]
+    NoFreeSlot(usize),
 }
 
 /// Wrapper over the `seq_fields` bindgen field in `VAPictureParameterBufferH264`.
@@ -125,6 +149,34 @@ impl H264PicFields {
     }
 }
 
+/// Maximum number of reference frames of an H.264 sequence.
+pub const H264_MAX_NUM_REF_FRAMES: u8 = 16;
+
+/// Error type for the `validate` methods of the H.264 decode parameter buffers.
+#[derive(Debug, Error)]
+pub enum H264DecParameterError {
+    #[error("{0} reference frames exceed the maximum of 16")]
+    TooManyReferenceFrames(u8),
+    #[error("bit depth {0} is out of range 8..=14")]
+    InvalidBitDepth(u32),
+    #[error("{0} slice groups exceed the maximum of 8")]
+    TooManySliceGroups(u32),
+    #[error("QP {0} is out of range")]
+    InvalidQp(i32),
+    #[error("chroma QP index offset {0} is out of range -12..=12")]
+    InvalidChromaQpIndexOffset(i8),
+    #[error("{0} active references exceed the maximum of 32")]
+    TooManyReferences(usize),
+    #[error("invalid cabac_init_idc {0}")]
+    InvalidCabacInitIdc(u8),
+    #[error("invalid disable_deblocking_filter_idc {0}")]
+    InvalidDeblockingFilterIdc(u8),
+    #[error("deblocking filter offset {0} is out of range -6..=6")]
+    InvalidDeblockingFilterOffset(i8),
+    #[error("log2 weight denominator {0} is out of range 0..=7")]
+    InvalidLog2WeightDenom(u8),
+}
+
 /// A wrapper over `VAPictureParameterBufferH264` FFI type
 pub struct PictureParameterBufferH264(Box<bindings::VAPictureParameterBufferH264>);
 
@@ -183,6 +235,88 @@ impl PictureParameterBufferH264 {
         }))
     }
 
+    /// Adds `refs` as inter-view references for decoding a non-base view of an H.264 MVC stream.
+    ///
+    /// libva has no dedicated structure for MVC decoding: inter-view reference pictures, i.e.
+    /// pictures of other views from the same access unit, are passed alongside the temporal
+    /// references in `ReferenceFrames`. They are placed into the slots that are currently invalid,
+    /// and `num_ref_frames` is raised to cover them, as drivers size their reference list from it.
+    ///
+    /// View identifiers (`view_id`, view order index) have no representation in the libva H.264
+    /// decode parameters, so they are not plumbed through: the caller is responsible for only
+    /// passing references of views the current view depends on.
+    pub fn add_inter_view_references(
+        &mut self,
+        refs: &[PictureH264],
+    ) -> Result<(), H264InterViewRefError> {
+        let free_slots = self
+            .0
+            .ReferenceFrames
+            .iter()
+            .filter(|pic| PictureH264::is_invalid_raw(pic))
+            .count();
+        if free_slots < refs.len() {
+            return Err(H264InterViewRefError::NoFreeSlot(refs.len()));
+        }
+
+        let free_slots = self
+            .0
+            .ReferenceFrames
+            .iter_mut()
+            .filter(|pic| PictureH264::is_invalid_raw(pic));
+        for (slot, inter_view_ref) in free_slots.zip(refs) {
+            *slot = inter_view_ref.0;
+        }
+
+        let num_ref_frames = self
+            .0
+            .ReferenceFrames
+            .iter()
+            .filter(|pic| !PictureH264::is_invalid_raw(pic))
+            .count() as u8;
+        self.0.num_ref_frames = std::cmp::max(self.0.num_ref_frames, num_ref_frames);
+
+        Ok(())
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), H264DecParameterError> {
+        let p = self.0.as_ref();
+
+        if p.num_ref_frames > H264_MAX_NUM_REF_FRAMES {
+            return Err(H264DecParameterError::TooManyReferenceFrames(
+                p.num_ref_frames,
+            ));
+        }
+        for bit_depth_minus8 in [p.bit_depth_luma_minus8, p.bit_depth_chroma_minus8] {
+            if bit_depth_minus8 > 6 {
+                return Err(H264DecParameterError::InvalidBitDepth(
+                    u32::from(bit_depth_minus8) + 8,
+                ));
+            }
+        }
+        if p.num_slice_groups_minus1 > 7 {
+            return Err(H264DecParameterError::TooManySliceGroups(
+                u32::from(p.num_slice_groups_minus1) + 1,
+            ));
+        }
+        let qp_bd_offset = 6 * i32::from(p.bit_depth_luma_minus8);
+        for init_qp_minus26 in [p.pic_init_qp_minus26, p.pic_init_qs_minus26] {
+            let init_qp = 26 + i32::from(init_qp_minus26);
+            if !(-qp_bd_offset..=i32::from(H264_MAX_QP)).contains(&init_qp) {
+                return Err(H264DecParameterError::InvalidQp(init_qp));
+            }
+        }
+        for offset in [p.chroma_qp_index_offset, p.second_chroma_qp_index_offset] {
+            if !(-12..=12).contains(&offset) {
+                return Err(H264DecParameterError::InvalidChromaQpIndexOffset(offset));
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAPictureParameterBufferH264 {
         self.0.as_mut()
     }
@@ -348,6 +482,53 @@ impl SliceParameterBufferH264 {
         self.0.push(buf);
     }
 
+    /// Checks the ranges of the fields of the slices, so invalid values are reported before
+    /// reaching the driver. `picture` is the picture the slices belong to.
+    pub fn validate(
+        &self,
+        picture: &PictureParameterBufferH264,
+    ) -> Result<(), H264DecParameterError> {
+        let qp_bd_offset = 6 * i32::from(picture.0.bit_depth_luma_minus8);
+
+        for s in &self.0 {
+            let slice_qp =
+                26 + i32::from(picture.0.pic_init_qp_minus26) + i32::from(s.slice_qp_delta);
+            if !(-qp_bd_offset..=i32::from(H264_MAX_QP)).contains(&slice_qp) {
+                return Err(H264DecParameterError::InvalidQp(slice_qp));
+            }
+            for num_ref_idx_active_minus1 in [
+                s.num_ref_idx_l0_active_minus1,
+                s.num_ref_idx_l1_active_minus1,
+            ] {
+                if usize::from(num_ref_idx_active_minus1) >= H264_MAX_NUM_REF_IDX_ACTIVE {
+                    return Err(H264DecParameterError::TooManyReferences(
+                        usize::from(num_ref_idx_active_minus1) + 1,
+                    ));
+                }
+            }
+            if s.cabac_init_idc > 2 {
+                return Err(H264DecParameterError::InvalidCabacInitIdc(s.cabac_init_idc));
+            }
+            if s.disable_deblocking_filter_idc > 2 {
+                return Err(H264DecParameterError::InvalidDeblockingFilterIdc(
+                    s.disable_deblocking_filter_idc,
+                ));
+            }
+            for offset in [s.slice_alpha_c0_offset_div2, s.slice_beta_offset_div2] {
+                if !(-6..=6).contains(&offset) {
+                    return Err(H264DecParameterError::InvalidDeblockingFilterOffset(offset));
+                }
+            }
+            for denom in [s.luma_log2_weight_denom, s.chroma_log2_weight_denom] {
+                if denom > 7 {
+                    return Err(H264DecParameterError::InvalidLog2WeightDenom(denom));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut Vec<bindings::VASliceParameterBufferH264> {
         &mut self.0
     }
@@ -568,6 +749,47 @@ impl EncSequenceParameterBufferH264 {
     }
 }
 
+/// Entropy coding mode of an H.264 stream, i.e. the value of `entropy_coding_mode_flag`.
+///
+/// The same mode must be used in the PPS written by the caller and in the `pic_fields` of
+/// [`EncPictureParameterBufferH264`], otherwise the produced stream cannot be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum H264EntropyCodingMode {
+    /// Context-adaptive variable-length coding.
+    Cavlc = 0,
+    /// Context-adaptive binary arithmetic coding.
+    Cabac = 1,
+}
+
+impl H264EntropyCodingMode {
+    /// Returns whether this mode can be used with `profile`. CABAC is not allowed by the Baseline
+    /// and Constrained Baseline profiles.
+    pub fn is_supported_by(self, profile: bindings::VAProfile::Type) -> bool {
+        match self {
+            H264EntropyCodingMode::Cavlc => true,
+            H264EntropyCodingMode::Cabac => !matches!(
+                profile,
+                bindings::VAProfile::VAProfileH264Baseline
+                    | bindings::VAProfile::VAProfileH264ConstrainedBaseline
+            ),
+        }
+    }
+
+    /// Returns the most efficient mode allowed by `profile`.
+    pub fn preferred_for(profile: bindings::VAProfile::Type) -> Self {
+        if H264EntropyCodingMode::Cabac.is_supported_by(profile) {
+            H264EntropyCodingMode::Cabac
+        } else {
+            H264EntropyCodingMode::Cavlc
+        }
+    }
+
+    /// Returns the value of `entropy_coding_mode_flag` to write in the PPS for this mode.
+    pub fn flag(self) -> u32 {
+        self as u32
+    }
+}
+
 pub struct H264EncPicFields(bindings::_VAEncPictureParameterBufferH264__bindgen_ty_1);
 
 impl H264EncPicFields {
@@ -608,6 +830,46 @@ impl H264EncPicFields {
             },
         })
     }
+
+    /// Returns the entropy coding mode set in these fields.
+    pub fn entropy_coding_mode(&self) -> H264EntropyCodingMode {
+        // Safe because all the members of the union are plain integer types.
+        match unsafe { self.0.bits.entropy_coding_mode_flag() } {
+            0 => H264EntropyCodingMode::Cavlc,
+            _ => H264EntropyCodingMode::Cabac,
+        }
+    }
+
+    /// Sets the entropy coding mode, i.e. `entropy_coding_mode_flag`.
+    pub fn set_entropy_coding_mode(&mut self, mode: H264EntropyCodingMode) {
+        // Safe because all the members of the union are plain integer types.
+        unsafe { self.0.bits.set_entropy_coding_mode_flag(mode.flag()) };
+    }
+}
+
+/// Maximum QP of 8-bit H.264 streams.
+pub const H264_MAX_QP: u8 = 51;
+
+/// Maximum number of active references in each reference picture list.
+pub const H264_MAX_NUM_REF_IDX_ACTIVE: usize = 32;
+
+/// Error type for the `validate` methods of the H.264 encode parameter buffers.
+#[derive(Debug, Error)]
+pub enum H264EncParameterError {
+    #[error("QP {0} is out of range 0..=51")]
+    InvalidQp(i32),
+    #[error("{0} active references exceed the maximum of 32")]
+    TooManyReferences(usize),
+    #[error("chroma QP index offset {0} is out of range -12..=12")]
+    InvalidChromaQpIndexOffset(i8),
+    #[error("invalid cabac_init_idc {0}")]
+    InvalidCabacInitIdc(u8),
+    #[error("invalid disable_deblocking_filter_idc {0}")]
+    InvalidDeblockingFilterIdc(u8),
+    #[error("deblocking filter offset {0} is out of range -6..=6")]
+    InvalidDeblockingFilterOffset(i8),
+    #[error("log2 weight denominator {0} is out of range 0..=7")]
+    InvalidLog2WeightDenom(u8),
 }
 
 pub struct EncPictureParameterBufferH264(Box<bindings::VAEncPictureParameterBufferH264>);
@@ -657,6 +919,38 @@ impl EncPictureParameterBufferH264 {
         }))
     }
 
+    /// Returns the entropy coding mode of this picture.
+    pub fn entropy_coding_mode(&self) -> H264EntropyCodingMode {
+        H264EncPicFields(self.0.pic_fields).entropy_coding_mode()
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), H264EncParameterError> {
+        let p = self.0.as_ref();
+
+        if p.pic_init_qp > H264_MAX_QP {
+            return Err(H264EncParameterError::InvalidQp(i32::from(p.pic_init_qp)));
+        }
+        for num_ref_idx_active_minus1 in [
+            p.num_ref_idx_l0_active_minus1,
+            p.num_ref_idx_l1_active_minus1,
+        ] {
+            if usize::from(num_ref_idx_active_minus1) >= H264_MAX_NUM_REF_IDX_ACTIVE {
+                return Err(H264EncParameterError::TooManyReferences(
+                    usize::from(num_ref_idx_active_minus1) + 1,
+                ));
+            }
+        }
+        for offset in [p.chroma_qp_index_offset, p.second_chroma_qp_index_offset] {
+            if !(-12..=12).contains(&offset) {
+                return Err(H264EncParameterError::InvalidChromaQpIndexOffset(offset));
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferH264 {
         self.0.as_mut()
     }
@@ -744,6 +1038,50 @@ impl EncSliceParameterBufferH264 {
         }))
     }
 
+    /// Checks the ranges of the fields of this slice, so invalid values are reported before
+    /// reaching the driver. `picture` is the picture this slice belongs to.
+    pub fn validate(
+        &self,
+        picture: &EncPictureParameterBufferH264,
+    ) -> Result<(), H264EncParameterError> {
+        let s = self.0.as_ref();
+
+        let slice_qp = i32::from(picture.0.pic_init_qp) + i32::from(s.slice_qp_delta);
+        if !(0..=i32::from(H264_MAX_QP)).contains(&slice_qp) {
+            return Err(H264EncParameterError::InvalidQp(slice_qp));
+        }
+        for num_ref_idx_active_minus1 in [
+            s.num_ref_idx_l0_active_minus1,
+            s.num_ref_idx_l1_active_minus1,
+        ] {
+            if usize::from(num_ref_idx_active_minus1) >= H264_MAX_NUM_REF_IDX_ACTIVE {
+                return Err(H264EncParameterError::TooManyReferences(
+                    usize::from(num_ref_idx_active_minus1) + 1,
+                ));
+            }
+        }
+        if s.cabac_init_idc > 2 {
+            return Err(H264EncParameterError::InvalidCabacInitIdc(s.cabac_init_idc));
+        }
+        if s.disable_deblocking_filter_idc > 2 {
+            return Err(H264EncParameterError::InvalidDeblockingFilterIdc(
+                s.disable_deblocking_filter_idc,
+            ));
+        }
+        for offset in [s.slice_alpha_c0_offset_div2, s.slice_beta_offset_div2] {
+            if !(-6..=6).contains(&offset) {
+                return Err(H264EncParameterError::InvalidDeblockingFilterOffset(offset));
+            }
+        }
+        for denom in [s.luma_log2_weight_denom, s.chroma_log2_weight_denom] {
+            if denom > 7 {
+                return Err(H264EncParameterError::InvalidLog2WeightDenom(denom));
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncSliceParameterBufferH264 {
         self.0.as_mut()
     }
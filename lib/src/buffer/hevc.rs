@@ -4,7 +4,10 @@
 
 //! Wrappers around HEVC `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
+use crate::buffer::EncSliceStructure;
 
 /// Wrapper over the `VAPictureH264` FFI type.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
@@ -159,6 +162,105 @@ impl HevcSliceParsingFields {
     }
 }
 
+/// Maximum QP of 8-bit HEVC streams.
+pub const HEVC_MAX_QP: u8 = 51;
+
+/// Maximum number of active references in each reference picture list.
+pub const HEVC_MAX_NUM_REF_IDX_ACTIVE: usize = 15;
+
+/// Error type for the `validate` methods of the HEVC parameter buffers.
+#[derive(Debug, Error)]
+pub enum HevcParameterError {
+    #[error("bit depth {0} is out of range 8..=16")]
+    InvalidBitDepth(u32),
+    #[error("CTB size 2^{0} is out of range 16..=64")]
+    InvalidCtbSize(u32),
+    #[error("{0}x{1} tiles exceed the maximum of 20x22")]
+    TooManyTiles(usize, usize),
+    #[error("QP {0} is out of range")]
+    InvalidQp(i32),
+    #[error("chroma QP offset {0} is out of range -12..=12")]
+    InvalidChromaQpOffset(i8),
+    #[error("{0} active references exceed the maximum of 15")]
+    TooManyReferences(usize),
+    #[error("deblocking filter offset {0} is out of range -6..=6")]
+    InvalidDeblockingFilterOffset(i8),
+    #[error("log2 weight denominator {0} is out of range 0..=7")]
+    InvalidLog2WeightDenom(u8),
+    #[error("maximum number of merge candidates {0} is out of range 1..=5")]
+    InvalidMaxNumMergeCand(u8),
+}
+
+/// Checks the range of the fields shared by the decode and encode picture parameters.
+fn validate_hevc_picture_fields(
+    chroma_qp_offsets: [i8; 2],
+    num_tile_columns_minus1: u8,
+    num_tile_rows_minus1: u8,
+    num_ref_idx_default_active_minus1: [u8; 2],
+) -> Result<(), HevcParameterError> {
+    for offset in chroma_qp_offsets {
+        if !(-12..=12).contains(&offset) {
+            return Err(HevcParameterError::InvalidChromaQpOffset(offset));
+        }
+    }
+    let columns = usize::from(num_tile_columns_minus1) + 1;
+    let rows = usize::from(num_tile_rows_minus1) + 1;
+    if columns > HEVC_MAX_TILE_COLUMNS || rows > HEVC_MAX_TILE_ROWS {
+        return Err(HevcParameterError::TooManyTiles(columns, rows));
+    }
+    validate_hevc_num_ref_idx(num_ref_idx_default_active_minus1)
+}
+
+fn validate_hevc_num_ref_idx(num_ref_idx_active_minus1: [u8; 2]) -> Result<(), HevcParameterError> {
+    for num_ref_idx_active_minus1 in num_ref_idx_active_minus1 {
+        if usize::from(num_ref_idx_active_minus1) >= HEVC_MAX_NUM_REF_IDX_ACTIVE {
+            return Err(HevcParameterError::TooManyReferences(
+                usize::from(num_ref_idx_active_minus1) + 1,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the range of the fields shared by the decode and encode slice parameters.
+fn validate_hevc_slice_fields(
+    slice_qp: i32,
+    min_qp: i32,
+    num_ref_idx_active_minus1: [u8; 2],
+    chroma_qp_offsets: [i8; 2],
+    deblocking_filter_offsets: [i8; 2],
+    luma_log2_weight_denom: u8,
+    max_num_merge_cand: u8,
+) -> Result<(), HevcParameterError> {
+    if !(min_qp..=i32::from(HEVC_MAX_QP)).contains(&slice_qp) {
+        return Err(HevcParameterError::InvalidQp(slice_qp));
+    }
+    validate_hevc_num_ref_idx(num_ref_idx_active_minus1)?;
+    for offset in chroma_qp_offsets {
+        if !(-12..=12).contains(&offset) {
+            return Err(HevcParameterError::InvalidChromaQpOffset(offset));
+        }
+    }
+    for offset in deblocking_filter_offsets {
+        if !(-6..=6).contains(&offset) {
+            return Err(HevcParameterError::InvalidDeblockingFilterOffset(offset));
+        }
+    }
+    if luma_log2_weight_denom > 7 {
+        return Err(HevcParameterError::InvalidLog2WeightDenom(
+            luma_log2_weight_denom,
+        ));
+    }
+    if !(1..=5).contains(&max_num_merge_cand) {
+        return Err(HevcParameterError::InvalidMaxNumMergeCand(
+            max_num_merge_cand,
+        ));
+    }
+
+    Ok(())
+}
+
 /// A wrapper over `VAPictureParameterBufferHEVC` FFI type
 pub struct PictureParameterBufferHEVC(Box<bindings::VAPictureParameterBufferHEVC>);
 
@@ -257,6 +359,45 @@ impl PictureParameterBufferHEVC {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), HevcParameterError> {
+        let p = self.0.as_ref();
+
+        for bit_depth_minus8 in [p.bit_depth_luma_minus8, p.bit_depth_chroma_minus8] {
+            if bit_depth_minus8 > 8 {
+                return Err(HevcParameterError::InvalidBitDepth(
+                    u32::from(bit_depth_minus8) + 8,
+                ));
+            }
+        }
+        let log2_ctb_size = u32::from(p.log2_min_luma_coding_block_size_minus3)
+            + 3
+            + u32::from(p.log2_diff_max_min_luma_coding_block_size);
+        if !(4..=6).contains(&log2_ctb_size) {
+            return Err(HevcParameterError::InvalidCtbSize(log2_ctb_size));
+        }
+        let init_qp = 26 + i32::from(p.init_qp_minus26);
+        if !(-6 * i32::from(p.bit_depth_luma_minus8)..=i32::from(HEVC_MAX_QP)).contains(&init_qp) {
+            return Err(HevcParameterError::InvalidQp(init_qp));
+        }
+        for offset in [p.pps_beta_offset_div2, p.pps_tc_offset_div2] {
+            if !(-6..=6).contains(&offset) {
+                return Err(HevcParameterError::InvalidDeblockingFilterOffset(offset));
+            }
+        }
+
+        validate_hevc_picture_fields(
+            [p.pps_cb_qp_offset, p.pps_cr_qp_offset],
+            p.num_tile_columns_minus1,
+            p.num_tile_rows_minus1,
+            [
+                p.num_ref_idx_l0_default_active_minus1,
+                p.num_ref_idx_l1_default_active_minus1,
+            ],
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAPictureParameterBufferHEVC {
         self.0.as_mut()
     }
@@ -569,6 +710,26 @@ impl SliceParameterBufferHEVC {
         unsafe { self.inner_mut().LongSliceFlags.fields.set_LastSliceOfPic(1) };
     }
 
+    /// Checks the ranges of the fields of this slice, so invalid values are reported before
+    /// reaching the driver. `picture` is the picture this slice belongs to.
+    pub fn validate(&self, picture: &PictureParameterBufferHEVC) -> Result<(), HevcParameterError> {
+        let s = self.0.as_ref();
+        let p = picture.0.as_ref();
+
+        validate_hevc_slice_fields(
+            26 + i32::from(p.init_qp_minus26) + i32::from(s.slice_qp_delta),
+            -6 * i32::from(p.bit_depth_luma_minus8),
+            [
+                s.num_ref_idx_l0_active_minus1,
+                s.num_ref_idx_l1_active_minus1,
+            ],
+            [s.slice_cb_qp_offset, s.slice_cr_qp_offset],
+            [s.slice_beta_offset_div2, s.slice_tc_offset_div2],
+            s.luma_log2_weight_denom,
+            5u8.saturating_sub(s.five_minus_max_num_merge_cand),
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VASliceParameterBufferHEVC {
         self.0.as_mut()
     }
@@ -1016,9 +1177,264 @@ impl EncPictureParameterBufferHEVC {
         }))
     }
 
+    /// Sets the tile layout of the picture, and enables tiles in `pic_fields` if `tiles` contains
+    /// more than one tile.
+    pub fn set_tiles(&mut self, tiles: &HevcEncTiles) {
+        self.0.num_tile_columns_minus1 = tiles.num_tile_columns_minus1;
+        self.0.num_tile_rows_minus1 = tiles.num_tile_rows_minus1;
+        self.0.column_width_minus1 = tiles.column_width_minus1;
+        self.0.row_height_minus1 = tiles.row_height_minus1;
+
+        let tiles_enabled = tiles.num_tile_columns_minus1 > 0 || tiles.num_tile_rows_minus1 > 0;
+        // Safe because all the members of the union are plain integer types.
+        unsafe {
+            self.0
+                .pic_fields
+                .bits
+                .set_tiles_enabled_flag(tiles_enabled as u32);
+            self.0
+                .pic_fields
+                .bits
+                .set_loop_filter_across_tiles_enabled_flag(tiles.loop_filter_across_tiles as u32);
+        }
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), HevcParameterError> {
+        let p = self.0.as_ref();
+
+        if p.pic_init_qp > HEVC_MAX_QP {
+            return Err(HevcParameterError::InvalidQp(i32::from(p.pic_init_qp)));
+        }
+
+        validate_hevc_picture_fields(
+            [p.pps_cb_qp_offset, p.pps_cr_qp_offset],
+            p.num_tile_columns_minus1,
+            p.num_tile_rows_minus1,
+            [
+                p.num_ref_idx_l0_default_active_minus1,
+                p.num_ref_idx_l1_default_active_minus1,
+            ],
+        )
+    }
+
+    /// Enables or disables wavefront parallel processing, i.e. `entropy_coding_sync_enabled_flag`.
+    pub fn set_wavefront_parallel_processing(&mut self, enabled: bool) {
+        // Safe because all the members of the union are plain integer types.
+        unsafe {
+            self.0
+                .pic_fields
+                .bits
+                .set_entropy_coding_sync_enabled_flag(enabled as u32);
+        }
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferHEVC {
         &mut self.0
     }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VAEncPictureParameterBufferHEVC {
+        &self.0
+    }
+}
+
+/// Maximum number of tile columns allowed by the HEVC specification.
+const HEVC_MAX_TILE_COLUMNS: usize = 20;
+/// Maximum number of tile rows allowed by the HEVC specification.
+const HEVC_MAX_TILE_ROWS: usize = 22;
+
+/// Error type for [`HevcEncTiles`].
+#[derive(Debug, Error)]
+pub enum HevcEncTilesError {
+    #[error("invalid number of tile columns {0} (must be between 1 and 20)")]
+    InvalidColumns(usize),
+    #[error("invalid number of tile rows {0} (must be between 1 and 22)")]
+    InvalidRows(usize),
+    #[error(
+        "tile sizes must be non-zero, and at most 256 CTBs except for the last column and row"
+    )]
+    InvalidTileSize,
+    #[error("{0}x{1} tiles do not fit in a picture of {2}x{3} CTBs")]
+    PictureTooSmall(usize, usize, u32, u32),
+    #[error("tiles cover {0}x{1} CTBs but the picture is {2}x{3} CTBs")]
+    SizeMismatch(u32, u32, u32, u32),
+    #[error("the driver does not support tiles for this config")]
+    NotSupported,
+    #[error(
+        "the driver cannot encode row-aligned slices, which wavefront parallel processing requires"
+    )]
+    WavefrontNotSupported,
+}
+
+/// Tile layout of an HEVC encoded picture, to be applied with
+/// [`EncPictureParameterBufferHEVC::set_tiles`].
+#[derive(Debug, Clone)]
+pub struct HevcEncTiles {
+    num_tile_columns_minus1: u8,
+    num_tile_rows_minus1: u8,
+    column_width_minus1: [u8; 19usize],
+    row_height_minus1: [u8; 21usize],
+    loop_filter_across_tiles: bool,
+}
+
+impl HevcEncTiles {
+    /// Creates a layout with explicit tile sizes, in CTBs, for a picture of
+    /// `width_in_ctbs`x`height_in_ctbs` CTBs. The sizes of all columns and rows must be given and
+    /// must add up to the picture size. Only the last column and row may be larger than 256 CTBs,
+    /// as their size is not transmitted.
+    pub fn new(
+        width_in_ctbs: u32,
+        height_in_ctbs: u32,
+        column_widths: &[u32],
+        row_heights: &[u32],
+        loop_filter_across_tiles: bool,
+    ) -> Result<Self, HevcEncTilesError> {
+        if column_widths.is_empty() || column_widths.len() > HEVC_MAX_TILE_COLUMNS {
+            return Err(HevcEncTilesError::InvalidColumns(column_widths.len()));
+        }
+        if row_heights.is_empty() || row_heights.len() > HEVC_MAX_TILE_ROWS {
+            return Err(HevcEncTilesError::InvalidRows(row_heights.len()));
+        }
+        if column_widths.len() as u32 > width_in_ctbs || row_heights.len() as u32 > height_in_ctbs {
+            return Err(HevcEncTilesError::PictureTooSmall(
+                column_widths.len(),
+                row_heights.len(),
+                width_in_ctbs,
+                height_in_ctbs,
+            ));
+        }
+
+        // The size of the last column and row is implied by the picture size.
+        let explicit_columns = &column_widths[..column_widths.len() - 1];
+        let explicit_rows = &row_heights[..row_heights.len() - 1];
+        if column_widths
+            .iter()
+            .chain(row_heights.iter())
+            .any(|&size| size == 0)
+            || explicit_columns
+                .iter()
+                .chain(explicit_rows.iter())
+                .any(|&size| size > 256)
+        {
+            return Err(HevcEncTilesError::InvalidTileSize);
+        }
+
+        let total_width = column_widths
+            .iter()
+            .try_fold(0u32, |acc, &width| acc.checked_add(width));
+        let total_height = row_heights
+            .iter()
+            .try_fold(0u32, |acc, &height| acc.checked_add(height));
+        if total_width != Some(width_in_ctbs) || total_height != Some(height_in_ctbs) {
+            return Err(HevcEncTilesError::SizeMismatch(
+                total_width.unwrap_or(u32::MAX),
+                total_height.unwrap_or(u32::MAX),
+                width_in_ctbs,
+                height_in_ctbs,
+            ));
+        }
+
+        let mut column_width_minus1 = [0u8; 19usize];
+        for (dst, width) in column_width_minus1.iter_mut().zip(explicit_columns) {
+            *dst = (width - 1) as u8;
+        }
+        let mut row_height_minus1 = [0u8; 21usize];
+        for (dst, height) in row_height_minus1.iter_mut().zip(explicit_rows) {
+            *dst = (height - 1) as u8;
+        }
+
+        Ok(Self {
+            num_tile_columns_minus1: (column_widths.len() - 1) as u8,
+            num_tile_rows_minus1: (row_heights.len() - 1) as u8,
+            column_width_minus1,
+            row_height_minus1,
+            loop_filter_across_tiles,
+        })
+    }
+
+    /// Creates a layout of `columns`x`rows` uniformly spaced tiles for a picture of
+    /// `width_in_ctbs`x`height_in_ctbs` CTBs, using the spacing defined by the HEVC specification
+    /// for `uniform_spacing_flag`.
+    pub fn uniform(
+        width_in_ctbs: u32,
+        height_in_ctbs: u32,
+        columns: usize,
+        rows: usize,
+        loop_filter_across_tiles: bool,
+    ) -> Result<Self, HevcEncTilesError> {
+        if columns == 0 || columns > HEVC_MAX_TILE_COLUMNS {
+            return Err(HevcEncTilesError::InvalidColumns(columns));
+        }
+        if rows == 0 || rows > HEVC_MAX_TILE_ROWS {
+            return Err(HevcEncTilesError::InvalidRows(rows));
+        }
+        if columns as u32 > width_in_ctbs || rows as u32 > height_in_ctbs {
+            return Err(HevcEncTilesError::PictureTooSmall(
+                columns,
+                rows,
+                width_in_ctbs,
+                height_in_ctbs,
+            ));
+        }
+
+        let uniform_sizes = |size: u32, count: usize| -> Vec<u32> {
+            let count = count as u32;
+            (0..count)
+                .map(|i| ((i + 1) * size) / count - (i * size) / count)
+                .collect()
+        };
+
+        Self::new(
+            width_in_ctbs,
+            height_in_ctbs,
+            &uniform_sizes(width_in_ctbs, columns),
+            &uniform_sizes(height_in_ctbs, rows),
+            loop_filter_across_tiles,
+        )
+    }
+
+    /// Returns the number of tile columns of this layout.
+    pub fn columns(&self) -> usize {
+        self.num_tile_columns_minus1 as usize + 1
+    }
+
+    /// Returns the number of tile rows of this layout.
+    pub fn rows(&self) -> usize {
+        self.num_tile_rows_minus1 as usize + 1
+    }
+
+    /// Checks that this layout can be used with a config whose `VAConfigAttribEncTileSupport`
+    /// attribute has the value `tile_support`, as returned by
+    /// [`crate::Display::get_config_attributes`].
+    ///
+    /// If `wavefront_parallel_processing` is set (see
+    /// [`EncPictureParameterBufferHEVC::set_wavefront_parallel_processing`]), also checks that the
+    /// driver's `slice_structure`, as returned by [`crate::Display::query_enc_slice_structure`],
+    /// allows slices made of whole CTB rows: a slice that starts in the middle of a CTB row must
+    /// end in that same row when WPP is enabled.
+    pub fn validate(
+        &self,
+        tile_support: u32,
+        slice_structure: EncSliceStructure,
+        wavefront_parallel_processing: bool,
+    ) -> Result<(), HevcEncTilesError> {
+        let needs_tiles = self.columns() > 1 || self.rows() > 1;
+        if needs_tiles && (tile_support == bindings::VA_ATTRIB_NOT_SUPPORTED || tile_support == 0) {
+            return Err(HevcEncTilesError::NotSupported);
+        }
+
+        let row_slices = EncSliceStructure::POWER_OF_TWO_ROWS
+            | EncSliceStructure::EQUAL_ROWS
+            | EncSliceStructure::ARBITRARY_ROWS
+            | EncSliceStructure::EQUAL_MULTI_ROWS;
+        if wavefront_parallel_processing && !slice_structure.intersects(row_slices) {
+            return Err(HevcEncTilesError::WavefrontNotSupported);
+        }
+
+        Ok(())
+    }
 }
 
 pub struct HevcEncSliceFields(bindings::_VAEncSliceParameterBufferHEVC__bindgen_ty_1);
@@ -1148,7 +1564,134 @@ impl EncSliceParameterBufferHEVC {
         }))
     }
 
+    /// Checks the ranges of the fields of this slice, so invalid values are reported before
+    /// reaching the driver. `picture` is the picture this slice belongs to.
+    pub fn validate(
+        &self,
+        picture: &EncPictureParameterBufferHEVC,
+    ) -> Result<(), HevcParameterError> {
+        let s = self.0.as_ref();
+
+        validate_hevc_slice_fields(
+            i32::from(picture.0.pic_init_qp) + i32::from(s.slice_qp_delta),
+            0,
+            [
+                s.num_ref_idx_l0_active_minus1,
+                s.num_ref_idx_l1_active_minus1,
+            ],
+            [s.slice_cb_qp_offset, s.slice_cr_qp_offset],
+            [s.slice_beta_offset_div2, s.slice_tc_offset_div2],
+            s.luma_log2_weight_denom,
+            s.max_num_merge_cand,
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncSliceParameterBufferHEVC {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_tiles_must_cover_picture() {
+        let tiles = HevcEncTiles::new(10, 6, &[4, 6], &[2, 2, 2], false).unwrap();
+        assert_eq!(tiles.columns(), 2);
+        assert_eq!(tiles.rows(), 3);
+        assert_eq!(tiles.column_width_minus1[0], 3);
+        assert_eq!(&tiles.row_height_minus1[..2], &[1, 1]);
+
+        assert!(matches!(
+            HevcEncTiles::new(10, 6, &[4], &[2, 2, 2], false),
+            Err(HevcEncTilesError::SizeMismatch(4, 6, 10, 6))
+        ));
+        assert!(matches!(
+            HevcEncTiles::new(10, 6, &[4, 6], &[2, 2, 3], false),
+            Err(HevcEncTilesError::SizeMismatch(10, 7, 10, 6))
+        ));
+        assert!(matches!(
+            HevcEncTiles::new(2, 6, &[1, 1, 1], &[6], false),
+            Err(HevcEncTilesError::PictureTooSmall(3, 1, 2, 6))
+        ));
+        assert!(matches!(
+            HevcEncTiles::new(10, 6, &[0, 10], &[6], false),
+            Err(HevcEncTilesError::InvalidTileSize)
+        ));
+        // Only the last column may be larger than 256 CTBs.
+        assert!(HevcEncTiles::new(300, 6, &[10, 290], &[6], false).is_ok());
+        assert!(matches!(
+            HevcEncTiles::new(300, 6, &[290, 10], &[6], false),
+            Err(HevcEncTilesError::InvalidTileSize)
+        ));
+    }
+
+    #[test]
+    fn uniform_tiles() {
+        let tiles = HevcEncTiles::uniform(30, 17, 4, 3, true).unwrap();
+        assert_eq!(tiles.columns(), 4);
+        assert_eq!(tiles.rows(), 3);
+        assert_eq!(&tiles.column_width_minus1[..3], &[6, 7, 6]);
+        assert_eq!(&tiles.row_height_minus1[..2], &[4, 5]);
+
+        assert!(matches!(
+            HevcEncTiles::uniform(30, 17, 0, 1, false),
+            Err(HevcEncTilesError::InvalidColumns(0))
+        ));
+        assert!(matches!(
+            HevcEncTiles::uniform(3, 17, 4, 1, false),
+            Err(HevcEncTilesError::PictureTooSmall(4, 1, 3, 17))
+        ));
+    }
+
+    #[test]
+    fn validate_tiles_and_wpp() {
+        let single = HevcEncTiles::uniform(30, 17, 1, 1, false).unwrap();
+        let multiple = HevcEncTiles::uniform(30, 17, 2, 2, false).unwrap();
+
+        assert!(single
+            .validate(
+                bindings::VA_ATTRIB_NOT_SUPPORTED,
+                EncSliceStructure::empty(),
+                false
+            )
+            .is_ok());
+        assert!(matches!(
+            multiple.validate(
+                bindings::VA_ATTRIB_NOT_SUPPORTED,
+                EncSliceStructure::empty(),
+                false
+            ),
+            Err(HevcEncTilesError::NotSupported)
+        ));
+        assert!(multiple
+            .validate(1, EncSliceStructure::empty(), false)
+            .is_ok());
+
+        assert!(matches!(
+            single.validate(0, EncSliceStructure::ARBITRARY_MACROBLOCKS, true),
+            Err(HevcEncTilesError::WavefrontNotSupported)
+        ));
+        assert!(single
+            .validate(0, EncSliceStructure::ARBITRARY_ROWS, true)
+            .is_ok());
+    }
+
+    #[test]
+    fn slice_fields_are_range_checked() {
+        assert!(validate_hevc_slice_fields(30, 0, [0, 0], [0, 0], [0, 0], 0, 5).is_ok());
+        assert!(matches!(
+            validate_hevc_slice_fields(52, 0, [0, 0], [0, 0], [0, 0], 0, 5),
+            Err(HevcParameterError::InvalidQp(52))
+        ));
+        assert!(matches!(
+            validate_hevc_slice_fields(30, 0, [15, 0], [0, 0], [0, 0], 0, 5),
+            Err(HevcParameterError::TooManyReferences(16))
+        ));
+        assert!(matches!(
+            validate_hevc_slice_fields(30, 0, [0, 0], [0, 0], [0, 0], 0, 0),
+            Err(HevcParameterError::InvalidMaxNumMergeCand(0))
+        ));
+    }
+}
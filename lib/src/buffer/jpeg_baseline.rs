@@ -4,8 +4,105 @@
 
 //! Wrappers around JPEGBaseline `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
+/// Chroma subsampling of a baseline JPEG picture, derived from the sampling factors of its
+/// components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JpegChromaType {
+    /// Grayscale picture with a single component.
+    Yuv400,
+    Yuv420,
+    /// Horizontally subsampled chroma, as commonly produced by MJPEG cameras.
+    Yuv422,
+    Yuv444,
+}
+
+impl JpegChromaType {
+    /// Returns the chroma type corresponding to the `(h_sampling_factor, v_sampling_factor)` of
+    /// each component of a picture, luma first, or `None` if the layout is not supported.
+    pub fn from_sampling_factors(factors: &[(u8, u8)]) -> Option<Self> {
+        match factors {
+            [_] => Some(Self::Yuv400),
+            [(y_h, y_v), (cb_h, cb_v), (cr_h, cr_v)] => {
+                if (cb_h, cb_v) != (cr_h, cr_v) || *cb_h == 0 || *cb_v == 0 {
+                    return None;
+                }
+
+                match (y_h / cb_h, y_v / cb_v, y_h % cb_h, y_v % cb_v) {
+                    (1, 1, 0, 0) => Some(Self::Yuv444),
+                    (2, 1, 0, 0) => Some(Self::Yuv422),
+                    (2, 2, 0, 0) => Some(Self::Yuv420),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `VA_RT_FORMAT_*` of the surfaces to decode a picture of this chroma type into.
+    pub fn rt_format(&self) -> u32 {
+        match self {
+            Self::Yuv400 => bindings::VA_RT_FORMAT_YUV400,
+            Self::Yuv420 => bindings::VA_RT_FORMAT_YUV420,
+            Self::Yuv422 => bindings::VA_RT_FORMAT_YUV422,
+            Self::Yuv444 => bindings::VA_RT_FORMAT_YUV444,
+        }
+    }
+
+    /// Returns the `VA_FOURCC_*` of the surfaces to decode a picture of this chroma type into.
+    pub fn fourcc(&self) -> u32 {
+        match self {
+            Self::Yuv400 => bindings::VA_FOURCC_Y800,
+            Self::Yuv420 => bindings::VA_FOURCC_NV12,
+            Self::Yuv422 => bindings::VA_FOURCC_YUY2,
+            Self::Yuv444 => bindings::VA_FOURCC_444P,
+        }
+    }
+}
+
+/// Maximum number of components in a baseline JPEG frame or scan.
+pub const JPEG_MAX_COMPONENTS: u8 = 4;
+
+/// Error type for the `validate` methods of the baseline JPEG decode and encode parameter buffers.
+#[derive(Debug, Error)]
+pub enum JpegParameterError {
+    #[error("{0} components are out of range 1..=4")]
+    InvalidNumComponents(u16),
+    #[error("sampling factors {0}x{1} are out of range 1..=4")]
+    InvalidSamplingFactors(u8, u8),
+    #[error("quantization table selector {0} is out of range 0..=3")]
+    InvalidQuantTableSelector(u8),
+    #[error("Huffman table selector {0} is out of range 0..=1")]
+    InvalidHuffmanTableSelector(u8),
+    #[error("quality {0} is out of range 1..=100")]
+    InvalidQuality(u8),
+}
+
+pub(super) fn validate_jpeg_num_components(
+    num_components: u16,
+) -> Result<usize, JpegParameterError> {
+    if !(1..=u16::from(JPEG_MAX_COMPONENTS)).contains(&num_components) {
+        return Err(JpegParameterError::InvalidNumComponents(num_components));
+    }
+
+    Ok(usize::from(num_components))
+}
+
+pub(super) fn validate_jpeg_huffman_table_selectors(
+    selectors: impl IntoIterator<Item = u8>,
+) -> Result<(), JpegParameterError> {
+    for selector in selectors {
+        if selector > 1 {
+            return Err(JpegParameterError::InvalidHuffmanTableSelector(selector));
+        }
+    }
+
+    Ok(())
+}
+
 /// Wrapper over the `components` bindgen field in `VAPictureParameterBufferJPEGBaseline`.
 pub struct PictureParameterBufferJPEGBaselineComponent(
     bindings::_VAPictureParameterBufferJPEGBaseline__bindgen_ty_1,
@@ -62,6 +159,39 @@ impl PictureParameterBufferJPEGBaseline {
         }))
     }
 
+    /// Returns the chroma type of the picture, or `None` if its component layout is not supported.
+    pub fn chroma_type(&self) -> Option<JpegChromaType> {
+        let num_components = (self.0.num_components as usize).min(self.0.components.len());
+        let factors: Vec<_> = self.0.components[..num_components]
+            .iter()
+            .map(|c| (c.h_sampling_factor, c.v_sampling_factor))
+            .collect();
+
+        JpegChromaType::from_sampling_factors(&factors)
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), JpegParameterError> {
+        let num_components = validate_jpeg_num_components(u16::from(self.0.num_components))?;
+
+        for c in &self.0.components[..num_components] {
+            if !(1..=4).contains(&c.h_sampling_factor) || !(1..=4).contains(&c.v_sampling_factor) {
+                return Err(JpegParameterError::InvalidSamplingFactors(
+                    c.h_sampling_factor,
+                    c.v_sampling_factor,
+                ));
+            }
+            if c.quantiser_table_selector > 3 {
+                return Err(JpegParameterError::InvalidQuantTableSelector(
+                    c.quantiser_table_selector,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAPictureParameterBufferJPEGBaseline {
         self.0.as_mut()
     }
@@ -126,6 +256,18 @@ impl SliceParameterBufferJPEGBaseline {
         }))
     }
 
+    /// Checks the ranges of the fields of this scan, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), JpegParameterError> {
+        let num_components = validate_jpeg_num_components(u16::from(self.0.num_components))?;
+
+        validate_jpeg_huffman_table_selectors(
+            self.0.components[..num_components]
+                .iter()
+                .flat_map(|c| [c.dc_table_selector, c.ac_table_selector]),
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VASliceParameterBufferJPEGBaseline {
         self.0.as_mut()
     }
@@ -163,6 +305,71 @@ impl IQMatrixBufferJPEGBaseline {
     }
 }
 
+/// Number of codes of each length of the DC luminance table of ITU-T T.81 Annex K.3.3.1.
+pub const JPEG_STD_DC_LUMINANCE_NUM_CODES: [u8; 16] =
+    [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+/// Number of codes of each length of the DC chrominance table of ITU-T T.81 Annex K.3.3.1.
+pub const JPEG_STD_DC_CHROMINANCE_NUM_CODES: [u8; 16] =
+    [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+/// Values of both DC tables of ITU-T T.81 Annex K.3.3.1.
+pub const JPEG_STD_DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+/// Number of codes of each length of the AC luminance table of ITU-T T.81 Annex K.3.3.2.
+pub const JPEG_STD_AC_LUMINANCE_NUM_CODES: [u8; 16] =
+    [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+/// Values of the AC luminance table of ITU-T T.81 Annex K.3.3.2.
+#[rustfmt::skip]
+pub const JPEG_STD_AC_LUMINANCE_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12,
+    0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08,
+    0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16,
+    0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+    0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+    0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79,
+    0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98,
+    0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6,
+    0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4,
+    0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea,
+    0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+/// Number of codes of each length of the AC chrominance table of ITU-T T.81 Annex K.3.3.2.
+pub const JPEG_STD_AC_CHROMINANCE_NUM_CODES: [u8; 16] =
+    [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+/// Values of the AC chrominance table of ITU-T T.81 Annex K.3.3.2.
+#[rustfmt::skip]
+pub const JPEG_STD_AC_CHROMINANCE_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21,
+    0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91,
+    0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34,
+    0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38,
+    0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+    0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78,
+    0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96,
+    0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4,
+    0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2,
+    0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9,
+    0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
 /// Wrapper over the `huffman_table` bindgen field in `VAHuffmanTableBufferJPEGBaseline`.
 pub struct HuffmanTableBufferJPEGBaselineHuffmanTable(
     bindings::_VAHuffmanTableBufferJPEGBaseline__bindgen_ty_1,
@@ -187,6 +394,30 @@ impl HuffmanTableBufferJPEGBaselineHuffmanTable {
         })
     }
 
+    /// Creates the standard luminance tables of ITU-T T.81 Annex K.3, conventionally used as
+    /// table 0.
+    pub fn standard_luminance() -> Self {
+        Self::new(
+            JPEG_STD_DC_LUMINANCE_NUM_CODES,
+            JPEG_STD_DC_VALUES,
+            JPEG_STD_AC_LUMINANCE_NUM_CODES,
+            JPEG_STD_AC_LUMINANCE_VALUES,
+            Default::default(),
+        )
+    }
+
+    /// Creates the standard chrominance tables of ITU-T T.81 Annex K.3, conventionally used as
+    /// table 1.
+    pub fn standard_chrominance() -> Self {
+        Self::new(
+            JPEG_STD_DC_CHROMINANCE_NUM_CODES,
+            JPEG_STD_DC_VALUES,
+            JPEG_STD_AC_CHROMINANCE_NUM_CODES,
+            JPEG_STD_AC_CHROMINANCE_VALUES,
+            Default::default(),
+        )
+    }
+
     /// Returns the inner FFI type. Useful for testing purposes.
     pub fn inner(&mut self) -> &bindings::_VAHuffmanTableBufferJPEGBaseline__bindgen_ty_1 {
         &self.0
@@ -209,6 +440,21 @@ impl HuffmanTableBufferJPEGBaseline {
         }))
     }
 
+    /// Creates the wrapper with the standard tables of ITU-T T.81 Annex K.3 loaded, the
+    /// luminance ones as table 0 and the chrominance ones as table 1.
+    ///
+    /// These are used by most encoders and by decoders of streams without DHT segments, such
+    /// as Motion JPEG.
+    pub fn standard() -> Self {
+        Self::new(
+            [1, 1],
+            [
+                HuffmanTableBufferJPEGBaselineHuffmanTable::standard_luminance(),
+                HuffmanTableBufferJPEGBaselineHuffmanTable::standard_chrominance(),
+            ],
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAHuffmanTableBufferJPEGBaseline {
         self.0.as_mut()
     }
@@ -218,3 +464,93 @@ impl HuffmanTableBufferJPEGBaseline {
         self.0.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slice_validate() {
+        let components = |ac_table_selector| {
+            [0, 1, 2, 3].map(|i| {
+                VASliceParameterBufferJPEGBaselineComponent::new(i + 1, 0, ac_table_selector)
+            })
+        };
+
+        assert!(
+            SliceParameterBufferJPEGBaseline::new(0, 0, 0, 0, 0, components(1), 3, 0, 0)
+                .validate()
+                .is_ok()
+        );
+        assert!(matches!(
+            SliceParameterBufferJPEGBaseline::new(0, 0, 0, 0, 0, components(1), 5, 0, 0).validate(),
+            Err(JpegParameterError::InvalidNumComponents(5))
+        ));
+        assert!(matches!(
+            SliceParameterBufferJPEGBaseline::new(0, 0, 0, 0, 0, components(2), 3, 0, 0).validate(),
+            Err(JpegParameterError::InvalidHuffmanTableSelector(2))
+        ));
+    }
+
+    #[test]
+    fn chroma_type_from_sampling_factors() {
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(2, 2), (1, 1), (1, 1)]),
+            Some(JpegChromaType::Yuv420)
+        );
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(2, 1), (1, 1), (1, 1)]),
+            Some(JpegChromaType::Yuv422)
+        );
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(1, 1), (1, 1), (1, 1)]),
+            Some(JpegChromaType::Yuv444)
+        );
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(1, 1)]),
+            Some(JpegChromaType::Yuv400)
+        );
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(2, 2), (1, 1), (2, 1)]),
+            None
+        );
+        assert_eq!(
+            JpegChromaType::from_sampling_factors(&[(3, 1), (2, 1), (2, 1)]),
+            None
+        );
+    }
+
+    #[test]
+    fn standard_huffman_tables() {
+        let count = |num_codes: &[u8]| num_codes.iter().map(|&n| usize::from(n)).sum::<usize>();
+
+        assert_eq!(
+            count(&JPEG_STD_DC_LUMINANCE_NUM_CODES),
+            JPEG_STD_DC_VALUES.len()
+        );
+        assert_eq!(
+            count(&JPEG_STD_DC_CHROMINANCE_NUM_CODES),
+            JPEG_STD_DC_VALUES.len()
+        );
+        assert_eq!(
+            count(&JPEG_STD_AC_LUMINANCE_NUM_CODES),
+            JPEG_STD_AC_LUMINANCE_VALUES.len()
+        );
+        assert_eq!(
+            count(&JPEG_STD_AC_CHROMINANCE_NUM_CODES),
+            JPEG_STD_AC_CHROMINANCE_VALUES.len()
+        );
+
+        let mut tables = HuffmanTableBufferJPEGBaseline::standard();
+        let inner = tables.inner();
+        assert_eq!(inner.load_huffman_table, [1, 1]);
+        assert_eq!(
+            inner.huffman_table[1].num_ac_codes,
+            JPEG_STD_AC_CHROMINANCE_NUM_CODES
+        );
+        assert_eq!(
+            inner.huffman_table[0].ac_values[..4],
+            [0x01, 0x02, 0x03, 0x00]
+        );
+    }
+}
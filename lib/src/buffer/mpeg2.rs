@@ -4,8 +4,88 @@
 
 //! Wrappers around MPEG2 `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MPEG2FCodeError {
+    #[error("f_code {0} is not in the 1..=9 range, nor 15 for unused")]
+    InvalidFCode(u8),
+}
+
+/// Error type for the `validate` methods of the MPEG-2 parameter buffers.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MPEG2ParameterError {
+    #[error("picture_coding_type {0} is not I, P nor B")]
+    InvalidPictureCodingType(i32),
+    #[error("picture_structure {0} is not in the 1..=3 range")]
+    InvalidPictureStructure(u32),
+    #[error(transparent)]
+    FCode(#[from] MPEG2FCodeError),
+    #[error("quantiser_scale_code {0} is not in the 1..=31 range")]
+    InvalidQuantiserScaleCode(i32),
+}
+
+/// The four `f_code` values of an MPEG-2 picture coding extension, packed into the `f_code`
+/// member of `VAPictureParameterBufferMPEG2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MPEG2FCode {
+    forward_horizontal: u8,
+    forward_vertical: u8,
+    backward_horizontal: u8,
+    backward_vertical: u8,
+}
+
+impl MPEG2FCode {
+    /// Value of an `f_code` that is not used, e.g. the backward ones of a P picture.
+    pub const UNUSED_VALUE: u8 = 15;
+
+    /// `f_code` values of a picture without motion vectors, e.g. an I picture.
+    pub const UNUSED: Self = Self {
+        forward_horizontal: Self::UNUSED_VALUE,
+        forward_vertical: Self::UNUSED_VALUE,
+        backward_horizontal: Self::UNUSED_VALUE,
+        backward_vertical: Self::UNUSED_VALUE,
+    };
+
+    /// Creates the `f_code` values, each of which must be in the `1..=9` range, or
+    /// [`Self::UNUSED_VALUE`].
+    pub fn new(
+        forward_horizontal: u8,
+        forward_vertical: u8,
+        backward_horizontal: u8,
+        backward_vertical: u8,
+    ) -> Result<Self, MPEG2FCodeError> {
+        for f_code in [
+            forward_horizontal,
+            forward_vertical,
+            backward_horizontal,
+            backward_vertical,
+        ] {
+            if !matches!(f_code, 1..=9 | Self::UNUSED_VALUE) {
+                return Err(MPEG2FCodeError::InvalidFCode(f_code));
+            }
+        }
+
+        Ok(Self {
+            forward_horizontal,
+            forward_vertical,
+            backward_horizontal,
+            backward_vertical,
+        })
+    }
+
+    /// Returns the value to use as the `f_code` member of `VAPictureParameterBufferMPEG2`, i.e.
+    /// `f_code[0][0]` in bits 12-15 down to `f_code[1][1]` in bits 0-3.
+    pub fn value(&self) -> i32 {
+        (i32::from(self.forward_horizontal) << 12)
+            | (i32::from(self.forward_vertical) << 8)
+            | (i32::from(self.backward_horizontal) << 4)
+            | i32::from(self.backward_vertical)
+    }
+}
+
 /// Wrapper over the `picture_coding_extension` bindgen field in `VAPictureParameterBufferMPEG2`.
 pub struct MPEG2PictureCodingExtension(bindings::_VAPictureParameterBufferMPEG2__bindgen_ty_1);
 
@@ -83,6 +163,55 @@ impl PictureParameterBufferMPEG2 {
         }))
     }
 
+    /// Creates the wrapper, taking the `f_code` values as a validated [`MPEG2FCode`].
+    pub fn new_with_f_code(
+        horizontal_size: u16,
+        vertical_size: u16,
+        forward_reference_picture: bindings::VASurfaceID,
+        backward_reference_picture: bindings::VASurfaceID,
+        picture_coding_type: i32,
+        f_code: MPEG2FCode,
+        picture_coding_extension: &MPEG2PictureCodingExtension,
+    ) -> Self {
+        Self::new(
+            horizontal_size,
+            vertical_size,
+            forward_reference_picture,
+            backward_reference_picture,
+            picture_coding_type,
+            f_code.value(),
+            picture_coding_extension,
+        )
+    }
+
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), MPEG2ParameterError> {
+        let p = self.0.as_ref();
+
+        // I, P and B pictures. D pictures are MPEG-1 only.
+        if !(1..=3).contains(&p.picture_coding_type) {
+            return Err(MPEG2ParameterError::InvalidPictureCodingType(
+                p.picture_coding_type,
+            ));
+        }
+        // Safe because all the members of the union are plain integer types.
+        let picture_structure = unsafe { p.picture_coding_extension.bits.picture_structure() };
+        if !(1..=3).contains(&picture_structure) {
+            return Err(MPEG2ParameterError::InvalidPictureStructure(
+                picture_structure,
+            ));
+        }
+        MPEG2FCode::new(
+            ((p.f_code >> 12) & 0xf) as u8,
+            ((p.f_code >> 8) & 0xf) as u8,
+            ((p.f_code >> 4) & 0xf) as u8,
+            (p.f_code & 0xf) as u8,
+        )?;
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAPictureParameterBufferMPEG2 {
         self.0.as_mut()
     }
@@ -122,6 +251,18 @@ impl SliceParameterBufferMPEG2 {
         }))
     }
 
+    /// Checks the ranges of the fields of this slice, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), MPEG2ParameterError> {
+        if !(1..=31).contains(&self.0.quantiser_scale_code) {
+            return Err(MPEG2ParameterError::InvalidQuantiserScaleCode(
+                self.0.quantiser_scale_code,
+            ));
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VASliceParameterBufferMPEG2 {
         self.0.as_mut()
     }
@@ -170,3 +311,34 @@ impl IQMatrixBufferMPEG2 {
         self.0.as_ref()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f_code() {
+        assert_eq!(MPEG2FCode::UNUSED.value(), 0xffff);
+        assert_eq!(MPEG2FCode::new(1, 2, 15, 15).unwrap().value(), 0x12ff);
+        assert_eq!(
+            MPEG2FCode::new(1, 10, 15, 15),
+            Err(MPEG2FCodeError::InvalidFCode(10))
+        );
+        assert_eq!(
+            MPEG2FCode::new(0, 1, 15, 15),
+            Err(MPEG2FCodeError::InvalidFCode(0))
+        );
+    }
+
+    #[test]
+    fn slice_validate() {
+        assert_eq!(
+            SliceParameterBufferMPEG2::new(0, 0, 0, 0, 0, 0, 31, 0).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            SliceParameterBufferMPEG2::new(0, 0, 0, 0, 0, 0, 0, 0).validate(),
+            Err(MPEG2ParameterError::InvalidQuantiserScaleCode(0))
+        );
+    }
+}
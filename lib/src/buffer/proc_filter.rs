@@ -0,0 +1,212 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Wrappers around `VAProcFilterParameterBuffer*` types.
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::FieldParity;
+
+/// Maximum strength of the HVS noise reduction in [`HvsNoiseReductionMode::Manual`] mode.
+pub const HVS_DENOISE_MAX_STRENGTH: u16 = 16;
+
+/// Mode of the human-visual-system noise reduction, aka `VA_PROC_HVS_DENOISE_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HvsNoiseReductionMode {
+    /// Default mode of the driver.
+    #[default]
+    Default,
+    /// Automatic mode optimizing the BD-rate, using the QP of the subsequent encode.
+    AutoBdRate,
+    /// Automatic mode optimizing the subjective quality.
+    AutoSubjective,
+    /// Manual mode, using the strength given by the application.
+    Manual,
+}
+
+impl HvsNoiseReductionMode {
+    fn value(self) -> u16 {
+        (match self {
+            HvsNoiseReductionMode::Default => bindings::VA_PROC_HVS_DENOISE_DEFAULT,
+            HvsNoiseReductionMode::AutoBdRate => bindings::VA_PROC_HVS_DENOISE_AUTO_BDRATE,
+            HvsNoiseReductionMode::AutoSubjective => bindings::VA_PROC_HVS_DENOISE_AUTO_SUBJECTIVE,
+            HvsNoiseReductionMode::Manual => bindings::VA_PROC_HVS_DENOISE_MANUAL,
+        }) as u16
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum HvsNoiseReductionError {
+    #[error("strength {0} is above the maximum of {HVS_DENOISE_MAX_STRENGTH}")]
+    InvalidStrength(u16),
+}
+
+/// Wrapper over the `VAProcFilterParameterBufferHVSNoiseReduction` FFI type.
+///
+/// This is distinct from the standard denoise filter, and is intended to be applied before
+/// encoding to reduce the bitrate of noisy content.
+pub struct ProcFilterParameterBufferHVSNoiseReduction(
+    Box<bindings::VAProcFilterParameterBufferHVSNoiseReduction>,
+);
+
+impl ProcFilterParameterBufferHVSNoiseReduction {
+    /// Creates the wrapper.
+    ///
+    /// `qp` is the QP of the subsequent encode, used by [`HvsNoiseReductionMode::AutoBdRate`].
+    /// `strength` is only used by [`HvsNoiseReductionMode::Manual`] and must not exceed
+    /// [`HVS_DENOISE_MAX_STRENGTH`].
+    pub fn new(
+        mode: HvsNoiseReductionMode,
+        qp: u16,
+        strength: u16,
+    ) -> Result<Self, HvsNoiseReductionError> {
+        if strength > HVS_DENOISE_MAX_STRENGTH {
+            return Err(HvsNoiseReductionError::InvalidStrength(strength));
+        }
+
+        Ok(Self(Box::new(
+            bindings::VAProcFilterParameterBufferHVSNoiseReduction {
+                type_: bindings::VAProcFilterType::VAProcFilterHVSNoiseReduction,
+                qp,
+                strength,
+                mode: mode.value(),
+                ..Default::default()
+            },
+        )))
+    }
+
+    pub(crate) fn inner_mut(
+        &mut self,
+    ) -> &mut bindings::VAProcFilterParameterBufferHVSNoiseReduction {
+        self.0.as_mut()
+    }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VAProcFilterParameterBufferHVSNoiseReduction {
+        self.0.as_ref()
+    }
+}
+
+/// Deinterlacing algorithm, aka `VAProcDeinterlacingType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeinterlacingAlgorithm {
+    Bob,
+    Weave,
+    MotionAdaptive,
+    MotionCompensated,
+}
+
+impl DeinterlacingAlgorithm {
+    fn value(self) -> bindings::VAProcDeinterlacingType::Type {
+        match self {
+            Self::Bob => bindings::VAProcDeinterlacingType::VAProcDeinterlacingBob,
+            Self::Weave => bindings::VAProcDeinterlacingType::VAProcDeinterlacingWeave,
+            Self::MotionAdaptive => {
+                bindings::VAProcDeinterlacingType::VAProcDeinterlacingMotionAdaptive
+            }
+            Self::MotionCompensated => {
+                bindings::VAProcDeinterlacingType::VAProcDeinterlacingMotionCompensated
+            }
+        }
+    }
+}
+
+/// Wrapper over the `VAProcFilterParameterBufferDeinterlacing` FFI type.
+pub struct ProcFilterParameterBufferDeinterlacing(
+    Box<bindings::VAProcFilterParameterBufferDeinterlacing>,
+);
+
+impl ProcFilterParameterBufferDeinterlacing {
+    /// Creates the wrapper.
+    ///
+    /// `field` is the field to output, e.g. the result of [`crate::Picture::field`], and
+    /// `first_field` the field that comes first in time. If `one_field` is set, the input surface
+    /// only contains `field`.
+    pub fn new(
+        algorithm: DeinterlacingAlgorithm,
+        field: FieldParity,
+        first_field: FieldParity,
+        one_field: bool,
+    ) -> Self {
+        let mut flags = 0;
+        if field == FieldParity::Bottom {
+            flags |= bindings::VA_DEINTERLACING_BOTTOM_FIELD;
+        }
+        if first_field == FieldParity::Bottom {
+            flags |= bindings::VA_DEINTERLACING_BOTTOM_FIELD_FIRST;
+        }
+        if one_field {
+            flags |= bindings::VA_DEINTERLACING_ONE_FIELD;
+        }
+
+        Self(Box::new(
+            bindings::VAProcFilterParameterBufferDeinterlacing {
+                type_: bindings::VAProcFilterType::VAProcFilterDeinterlacing,
+                algorithm: algorithm.value(),
+                flags,
+                ..Default::default()
+            },
+        ))
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAProcFilterParameterBufferDeinterlacing {
+        self.0.as_mut()
+    }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VAProcFilterParameterBufferDeinterlacing {
+        self.0.as_ref()
+    }
+}
+
+/// Abstraction over the `VAProcFilterParameterBuffer` types we support.
+pub enum ProcFilterParameter {
+    /// Wrapper over `VAProcFilterParameterBufferHVSNoiseReduction`.
+    HVSNoiseReduction(ProcFilterParameterBufferHVSNoiseReduction),
+    /// Wrapper over `VAProcFilterParameterBufferDeinterlacing`.
+    Deinterlacing(ProcFilterParameterBufferDeinterlacing),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hvs_noise_reduction() {
+        let filter =
+            ProcFilterParameterBufferHVSNoiseReduction::new(HvsNoiseReductionMode::Manual, 0, 8)
+                .unwrap();
+        assert_eq!(
+            filter.inner().type_,
+            bindings::VAProcFilterType::VAProcFilterHVSNoiseReduction
+        );
+        assert_eq!(filter.inner().strength, 8);
+
+        assert!(ProcFilterParameterBufferHVSNoiseReduction::new(
+            HvsNoiseReductionMode::Manual,
+            0,
+            HVS_DENOISE_MAX_STRENGTH + 1
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn deinterlacing_flags() {
+        let filter = ProcFilterParameterBufferDeinterlacing::new(
+            DeinterlacingAlgorithm::MotionAdaptive,
+            FieldParity::Bottom,
+            FieldParity::Top,
+            false,
+        );
+        assert_eq!(
+            filter.inner().type_,
+            bindings::VAProcFilterType::VAProcFilterDeinterlacing
+        );
+        assert_eq!(
+            filter.inner().flags,
+            bindings::VA_DEINTERLACING_BOTTOM_FIELD
+        );
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to lay out slice data buffers according to the slice decoding mode of the driver.
+
+use bitflags::bitflags;
+
+use crate::bindings;
+
+bitflags! {
+    /// Slice decoding modes supported by a decoder, aka `VA_DEC_SLICE_MODE_*`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct DecSliceModes: u32 {
+        /// Complete slice parameters are provided along with the data of each slice.
+        const NORMAL = bindings::VA_DEC_SLICE_MODE_NORMAL;
+        /// Only short-format slice parameters are provided, and the driver parses the slice
+        /// headers from the slice data. This crate does not build short-format slice parameters.
+        const BASE = bindings::VA_DEC_SLICE_MODE_BASE;
+    }
+}
+
+/// How the slice data of a frame is submitted to the driver.
+///
+/// Both layouts use full slice parameters and are thus only valid with drivers supporting
+/// [`DecSliceModes::NORMAL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceDataLayout {
+    /// One slice data buffer per slice, each following its slice parameter buffer.
+    PerSlice,
+    /// A single slice data buffer containing the concatenated data of all the slices of the
+    /// frame, following a single slice parameter buffer holding all the slice parameters. Each
+    /// slice is located through its `slice_data_offset`.
+    PerFrame,
+}
+
+impl SliceDataLayout {
+    /// Returns the layout to use with a driver supporting the slice decoding `modes`, or `None` if
+    /// the driver does not support [`DecSliceModes::NORMAL`], which this crate requires.
+    ///
+    /// [`SliceDataLayout::PerSlice`] is returned as it is the layout every driver accepts in
+    /// normal mode. [`SliceDataLayout::PerFrame`] can be selected explicitly when the codec
+    /// carries several slices or tiles in one chunk of data.
+    pub fn preferred_for(modes: DecSliceModes) -> Option<Self> {
+        if modes.contains(DecSliceModes::NORMAL) {
+            Some(Self::PerSlice)
+        } else {
+            None
+        }
+    }
+}
+
+/// Accumulates the data of the slices of a frame and produces the slice data buffers matching a
+/// given [`SliceDataLayout`].
+#[derive(Debug)]
+pub struct SliceDataPacker {
+    layout: SliceDataLayout,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl SliceDataPacker {
+    /// Creates an empty packer using `layout`.
+    pub fn new(layout: SliceDataLayout) -> Self {
+        Self {
+            layout,
+            buffers: Default::default(),
+        }
+    }
+
+    /// Returns the layout used by this packer.
+    pub fn layout(&self) -> SliceDataLayout {
+        self.layout
+    }
+
+    /// Adds the data of a slice, and returns the `slice_data_offset` to set in its slice
+    /// parameters.
+    pub fn push(&mut self, slice_data: &[u8]) -> u32 {
+        match self.layout {
+            SliceDataLayout::PerSlice => {
+                self.buffers.push(slice_data.to_vec());
+                0
+            }
+            SliceDataLayout::PerFrame => {
+                if self.buffers.is_empty() {
+                    self.buffers.push(Vec::new());
+                }
+                let frame_data = &mut self.buffers[0];
+                let offset = frame_data.len() as u32;
+                frame_data.extend_from_slice(slice_data);
+                offset
+            }
+        }
+    }
+
+    /// Returns the slice data buffers to submit, in order, to be wrapped into
+    /// [`BufferType::SliceData`](crate::BufferType::SliceData).
+    pub fn finish(self) -> Vec<Vec<u8>> {
+        self.buffers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_slice_data() {
+        let mut per_slice = SliceDataPacker::new(SliceDataLayout::PerSlice);
+        assert_eq!(per_slice.push(&[1, 2]), 0);
+        assert_eq!(per_slice.push(&[3]), 0);
+        assert_eq!(per_slice.finish(), vec![vec![1, 2], vec![3]]);
+
+        let mut per_frame = SliceDataPacker::new(SliceDataLayout::PerFrame);
+        assert_eq!(per_frame.push(&[1, 2]), 0);
+        assert_eq!(per_frame.push(&[3]), 2);
+        assert_eq!(per_frame.finish(), vec![vec![1, 2, 3]]);
+
+        assert_eq!(
+            SliceDataLayout::preferred_for(DecSliceModes::NORMAL | DecSliceModes::BASE),
+            Some(SliceDataLayout::PerSlice)
+        );
+        assert_eq!(SliceDataLayout::preferred_for(DecSliceModes::BASE), None);
+    }
+}
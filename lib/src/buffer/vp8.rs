@@ -4,6 +4,8 @@
 
 //! Wrappers around VP8 `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
 /// Wrapper over the `pic_fields` bindgen field in `VAPictureParameterBufferVP8`.
@@ -73,6 +75,55 @@ impl BoolCoderContextVPX {
     }
 }
 
+/// Maximum loop filter level of a VP8 frame.
+pub const VP8_MAX_LOOP_FILTER_LEVEL: u8 = 63;
+
+/// Maximum quantizer index of a VP8 frame.
+pub const VP8_MAX_QINDEX: u16 = 127;
+
+/// Error type for the `validate` methods of the VP8 parameter buffers.
+#[derive(Debug, Error)]
+pub enum Vp8ParameterError {
+    #[error("loop filter level {0} is out of range 0..=63")]
+    InvalidLoopFilterLevel(i32),
+    #[error("loop filter delta {0} is out of range -63..=63")]
+    InvalidLoopFilterDelta(i8),
+    #[error("sharpness level {0} is out of range 0..=7")]
+    InvalidSharpnessLevel(u8),
+    #[error("quantizer index {0} is out of range 0..=127")]
+    InvalidQIndex(i32),
+    #[error("quantizer index delta {0} is out of range -15..=15")]
+    InvalidQIndexDelta(i16),
+}
+
+fn validate_vp8_loop_filter(
+    levels: impl IntoIterator<Item = i32>,
+    deltas: impl IntoIterator<Item = i8>,
+) -> Result<(), Vp8ParameterError> {
+    for level in levels {
+        if !(0..=i32::from(VP8_MAX_LOOP_FILTER_LEVEL)).contains(&level) {
+            return Err(Vp8ParameterError::InvalidLoopFilterLevel(level));
+        }
+    }
+    for delta in deltas {
+        if !(-63..=63).contains(&delta) {
+            return Err(Vp8ParameterError::InvalidLoopFilterDelta(delta));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_vp8_qindex(qindex: impl IntoIterator<Item = u16>) -> Result<(), Vp8ParameterError> {
+    for qindex in qindex {
+        if qindex > VP8_MAX_QINDEX {
+            return Err(Vp8ParameterError::InvalidQIndex(i32::from(qindex)));
+        }
+    }
+
+    Ok(())
+}
+
 /// Wrapper over the `PictureParameterBufferVP8` FFI type.
 pub struct PictureParameterBufferVP8(Box<bindings::VAPictureParameterBufferVP8>);
 
@@ -126,6 +177,20 @@ impl PictureParameterBufferVP8 {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), Vp8ParameterError> {
+        let p = self.0.as_ref();
+
+        validate_vp8_loop_filter(
+            p.loop_filter_level.iter().map(|&level| i32::from(level)),
+            p.loop_filter_deltas_ref_frame
+                .iter()
+                .chain(p.loop_filter_deltas_mode.iter())
+                .copied(),
+        )
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAPictureParameterBufferVP8 {
         self.0.as_mut()
     }
@@ -182,6 +247,12 @@ impl IQMatrixBufferVP8 {
         }))
     }
 
+    /// Checks the ranges of the quantizer indices, so invalid values are reported before reaching
+    /// the driver.
+    pub fn validate(&self) -> Result<(), Vp8ParameterError> {
+        validate_vp8_qindex(self.0.quantization_index.iter().flatten().copied())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAIQMatrixBufferVP8 {
         self.0.as_mut()
     }
@@ -192,18 +263,67 @@ impl IQMatrixBufferVP8 {
     }
 }
 
+/// DCT coefficient probabilities of a VP8 frame, indexed by block type, coefficient band, context
+/// and token, as in `coeff_probs` of RFC 6386.
+pub type Vp8CoeffProbs = [[[[u8; 11usize]; 3usize]; 8usize]; 4usize];
+
+#[derive(Debug, Error)]
+pub enum Vp8CoeffProbUpdateError {
+    #[error("coefficient probability index {0:?} is out of bounds")]
+    OutOfBounds([usize; 4]),
+}
+
 /// Wrapper over the VAProbabilityDataBufferVP8 FFI type.
 pub struct ProbabilityDataBufferVP8(Box<bindings::VAProbabilityDataBufferVP8>);
 
 impl ProbabilityDataBufferVP8 {
     /// Creates the wrapper.
-    pub fn new(dct_coeff_probs: [[[[u8; 11usize]; 3usize]; 8usize]; 4usize]) -> Self {
+    pub fn new(dct_coeff_probs: Vp8CoeffProbs) -> Self {
         Self(Box::new(bindings::VAProbabilityDataBufferVP8 {
             dct_coeff_probs,
             va_reserved: Default::default(),
         }))
     }
 
+    /// Returns the coefficient probabilities of this buffer.
+    pub fn dct_coeff_probs(&self) -> &Vp8CoeffProbs {
+        &self.0.dct_coeff_probs
+    }
+
+    /// Applies the coefficient probability updates signaled in a frame header, as
+    /// `([i, j, k, l], prob)` pairs indexing [`Vp8CoeffProbs`], so the buffer can be reused for
+    /// the next frame.
+    ///
+    /// If the frame header has `refresh_entropy_probs` unset, the updates only apply to that
+    /// frame: the caller should keep a copy of [`Self::dct_coeff_probs`] to restore afterwards.
+    ///
+    /// The probabilities are left untouched if any of the updates is out of bounds.
+    pub fn apply_coeff_prob_updates<I>(&mut self, updates: I) -> Result<(), Vp8CoeffProbUpdateError>
+    where
+        I: IntoIterator<Item = ([usize; 4], u8)>,
+    {
+        let mut dct_coeff_probs = self.0.dct_coeff_probs;
+        for (index, prob) in updates {
+            let [i, j, k, l] = index;
+            let coeff_prob = dct_coeff_probs
+                .get_mut(i)
+                .and_then(|probs| probs.get_mut(j))
+                .and_then(|probs| probs.get_mut(k))
+                .and_then(|probs| probs.get_mut(l))
+                .ok_or(Vp8CoeffProbUpdateError::OutOfBounds(index))?;
+            *coeff_prob = prob;
+        }
+        self.0.dct_coeff_probs = dct_coeff_probs;
+
+        Ok(())
+    }
+
+    /// Replaces all the coefficient probabilities, e.g. to restore them after a frame that did not
+    /// refresh them.
+    pub fn set_dct_coeff_probs(&mut self, dct_coeff_probs: &Vp8CoeffProbs) {
+        self.0.dct_coeff_probs = *dct_coeff_probs;
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAProbabilityDataBufferVP8 {
         self.0.as_mut()
     }
@@ -391,7 +511,113 @@ impl EncPictureParameterBufferVP8 {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), Vp8ParameterError> {
+        let p = self.0.as_ref();
+
+        validate_vp8_loop_filter(
+            p.loop_filter_level.iter().map(|&level| i32::from(level)),
+            p.ref_lf_delta.iter().chain(p.mode_lf_delta.iter()).copied(),
+        )?;
+        if p.sharpness_level > 7 {
+            return Err(Vp8ParameterError::InvalidSharpnessLevel(p.sharpness_level));
+        }
+        validate_vp8_qindex([
+            u16::from(p.clamp_qindex_low),
+            u16::from(p.clamp_qindex_high),
+        ])?;
+        if p.clamp_qindex_low > p.clamp_qindex_high {
+            return Err(Vp8ParameterError::InvalidQIndex(i32::from(
+                p.clamp_qindex_low,
+            )));
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferVP8 {
         &mut self.0
     }
 }
+
+/// Wrapper over the `VAQMatrixBufferVP8` FFI type, which sets the quantization of a VP8 frame
+/// being encoded.
+pub struct QMatrixBufferVP8(Box<bindings::VAQMatrixBufferVP8>);
+
+impl QMatrixBufferVP8 {
+    /// Creates the wrapper.
+    ///
+    /// `quantization_index` contains the base quantizer index of each segment, and
+    /// `quantization_index_delta` the deltas for y1_dc, y2_dc, y2_ac, uv_dc and uv_ac, in that
+    /// order.
+    pub fn new(quantization_index: [u16; 4], quantization_index_delta: [i16; 5]) -> Self {
+        Self(Box::new(bindings::VAQMatrixBufferVP8 {
+            quantization_index,
+            quantization_index_delta,
+            va_reserved: Default::default(),
+        }))
+    }
+
+    /// Checks the ranges of the quantizer indices and deltas, so invalid values are reported
+    /// before reaching the driver.
+    pub fn validate(&self) -> Result<(), Vp8ParameterError> {
+        validate_vp8_qindex(self.0.quantization_index)?;
+        for delta in self.0.quantization_index_delta {
+            if !(-15..=15).contains(&delta) {
+                return Err(Vp8ParameterError::InvalidQIndexDelta(delta));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAQMatrixBufferVP8 {
+        self.0.as_mut()
+    }
+
+    /// Returns the inner FFI type. Useful for testing purposes.
+    pub fn inner(&self) -> &bindings::VAQMatrixBufferVP8 {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coeff_prob_updates() {
+        let mut probs = ProbabilityDataBufferVP8::new([[[[128; 11]; 3]; 8]; 4]);
+
+        probs
+            .apply_coeff_prob_updates([([0, 1, 2, 3], 10), ([3, 7, 2, 10], 20)])
+            .unwrap();
+        assert_eq!(probs.dct_coeff_probs()[0][1][2][3], 10);
+        assert_eq!(probs.dct_coeff_probs()[3][7][2][10], 20);
+
+        assert!(matches!(
+            probs.apply_coeff_prob_updates([([1, 1, 1, 1], 30), ([0, 8, 0, 0], 40)]),
+            Err(Vp8CoeffProbUpdateError::OutOfBounds([0, 8, 0, 0]))
+        ));
+        // Nothing is applied if an update is out of bounds.
+        assert_eq!(probs.dct_coeff_probs()[1][1][1][1], 128);
+    }
+
+    #[test]
+    fn qindex_ranges() {
+        assert!(IQMatrixBufferVP8::new([[127; 6]; 4]).validate().is_ok());
+        assert!(matches!(
+            IQMatrixBufferVP8::new([[0, 0, 128, 0, 0, 0]; 4]).validate(),
+            Err(Vp8ParameterError::InvalidQIndex(128))
+        ));
+
+        assert!(QMatrixBufferVP8::new([10; 4], [15, -15, 0, 0, 0])
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            QMatrixBufferVP8::new([10; 4], [0, 0, 16, 0, 0]).validate(),
+            Err(Vp8ParameterError::InvalidQIndexDelta(16))
+        ));
+    }
+}
@@ -4,6 +4,8 @@
 
 //! Wrappers around VP9 `VABuffer` types.
 
+use thiserror::Error;
+
 use crate::bindings;
 
 /// Wrapper over the `pic_fields` bindgen field in `VAPictureParameterBufferVP9`.
@@ -119,6 +121,29 @@ impl PictureParameterBufferVP9 {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), VP9ParameterError> {
+        let p = self.0.as_ref();
+
+        validate_vp9_frame_fields(
+            p.filter_level,
+            p.sharpness_level,
+            p.log2_tile_columns,
+            p.log2_tile_rows,
+        )?;
+        let valid_bit_depth = match p.profile {
+            0 | 1 => p.bit_depth == 8,
+            2 | 3 => p.bit_depth == 10 || p.bit_depth == 12,
+            _ => false,
+        };
+        if !valid_bit_depth {
+            return Err(VP9ParameterError::InvalidBitDepth(p.profile, p.bit_depth));
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VADecPictureParameterBufferVP9 {
         self.0.as_mut()
     }
@@ -188,6 +213,256 @@ impl SegmentParameterVP9 {
     }
 }
 
+/// Maximum number of segments in a VP9 frame.
+pub const VP9_MAX_SEGMENTS: usize = 8;
+/// Maximum loop filter level in VP9.
+const VP9_MAX_LOOP_FILTER: i32 = 63;
+/// Maximum quantizer index in VP9.
+const VP9_MAX_QINDEX: i32 = 255;
+
+/// Error type for the VP9 segmentation helpers.
+#[derive(Debug, Error)]
+pub enum VP9SegmentationError {
+    #[error("invalid segment id {0}")]
+    InvalidSegmentId(u8),
+    #[error("quantizer feature value {0} out of range [-255, 255]")]
+    InvalidQuantizer(i16),
+    #[error("loop filter feature value {0} out of range [-63, 63]")]
+    InvalidLoopFilter(i8),
+    #[error("reference frame feature value {0} out of range [0, 3]")]
+    InvalidReference(u8),
+    #[error("block ({0}, {1}) is outside of the segmentation map")]
+    OutOfBounds(u32, u32),
+    #[error("segmentation map of {0}x{1} blocks is too large")]
+    InvalidSize(u32, u32),
+}
+
+/// Error type for the `validate` methods of the VP9 parameter buffers.
+#[derive(Debug, Error)]
+pub enum VP9ParameterError {
+    #[error("loop filter level {0} out of range [0, 63]")]
+    InvalidLoopFilterLevel(u8),
+    #[error("loop filter delta {0} out of range [-63, 63]")]
+    InvalidLoopFilterDelta(i8),
+    #[error("sharpness level {0} out of range [0, 7]")]
+    InvalidSharpnessLevel(u8),
+    #[error("log2 of {0}x{1} tiles out of range")]
+    InvalidTiles(u8, u8),
+    #[error("quantizer index delta {0} out of range [-15, 15]")]
+    InvalidQIndexDelta(i8),
+    #[error("profile {0} with bit depth {1} is not valid")]
+    InvalidBitDepth(u8, u8),
+}
+
+/// Checks the loop filter and tile fields shared by the decode and encode picture parameters.
+fn validate_vp9_frame_fields(
+    filter_level: u8,
+    sharpness_level: u8,
+    log2_tile_columns: u8,
+    log2_tile_rows: u8,
+) -> Result<(), VP9ParameterError> {
+    if i32::from(filter_level) > VP9_MAX_LOOP_FILTER {
+        return Err(VP9ParameterError::InvalidLoopFilterLevel(filter_level));
+    }
+    if sharpness_level > 7 {
+        return Err(VP9ParameterError::InvalidSharpnessLevel(sharpness_level));
+    }
+    // At most 64 tile columns and 4 tile rows.
+    if log2_tile_columns > 6 || log2_tile_rows > 2 {
+        return Err(VP9ParameterError::InvalidTiles(
+            log2_tile_columns,
+            log2_tile_rows,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Features of a VP9 segment, as signaled in the `segmentation_params()` syntax of the frame
+/// header.
+///
+/// Depending on `segmentation_abs_or_delta_update`, the quantizer and loop filter values are
+/// either absolute or deltas applied to the frame-level values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VP9SegmentFeatures {
+    /// Quantizer feature (`SEG_LVL_ALT_Q`).
+    pub alt_q: Option<i16>,
+    /// Loop filter feature (`SEG_LVL_ALT_L`).
+    pub alt_lf: Option<i8>,
+    /// Reference frame feature (`SEG_LVL_REF_FRAME`), 0 being the intra frame.
+    pub ref_frame: Option<u8>,
+    /// Skip feature (`SEG_LVL_SKIP`).
+    pub skip: bool,
+}
+
+impl VP9SegmentFeatures {
+    /// Checks that the values of the features are within the ranges allowed by the specification.
+    pub fn validate(&self) -> Result<(), VP9SegmentationError> {
+        if let Some(alt_q) = self.alt_q {
+            if !(-VP9_MAX_QINDEX..=VP9_MAX_QINDEX).contains(&i32::from(alt_q)) {
+                return Err(VP9SegmentationError::InvalidQuantizer(alt_q));
+            }
+        }
+        if let Some(alt_lf) = self.alt_lf {
+            if !(-VP9_MAX_LOOP_FILTER..=VP9_MAX_LOOP_FILTER).contains(&i32::from(alt_lf)) {
+                return Err(VP9SegmentationError::InvalidLoopFilter(alt_lf));
+            }
+        }
+        if let Some(ref_frame) = self.ref_frame {
+            if ref_frame > 3 {
+                return Err(VP9SegmentationError::InvalidReference(ref_frame));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the quantizer index of the segment, given the `base_q_idx` of the frame.
+    pub fn qindex(&self, base_q_idx: u8, abs_or_delta_update: bool) -> u8 {
+        match self.alt_q {
+            Some(alt_q) if abs_or_delta_update => alt_q.clamp(0, VP9_MAX_QINDEX as i16) as u8,
+            Some(alt_q) => {
+                (i32::from(base_q_idx) + i32::from(alt_q)).clamp(0, VP9_MAX_QINDEX) as u8
+            }
+            None => base_q_idx,
+        }
+    }
+
+    /// Returns the loop filter levels of the segment, indexed by reference frame and mode type, as
+    /// expected by the `filter_level` member of `VASegmentParameterVP9`.
+    ///
+    /// `lf_deltas` contains the `loop_filter_ref_deltas` and `loop_filter_mode_deltas` of the
+    /// frame if `loop_filter_delta_enabled` is set.
+    pub fn filter_levels(
+        &self,
+        loop_filter_level: u8,
+        abs_or_delta_update: bool,
+        lf_deltas: Option<([i8; 4], [i8; 2])>,
+    ) -> [[u8; 2]; 4] {
+        let mut level = i32::from(loop_filter_level);
+        if let Some(alt_lf) = self.alt_lf {
+            level = if abs_or_delta_update {
+                i32::from(alt_lf)
+            } else {
+                level + i32::from(alt_lf)
+            };
+            level = level.clamp(0, VP9_MAX_LOOP_FILTER);
+        }
+
+        let mut levels = [[level as u8; 2]; 4];
+        if let Some((ref_deltas, mode_deltas)) = lf_deltas {
+            let scale = 1 << (level >> 5);
+            let clamp = |l: i32| l.clamp(0, VP9_MAX_LOOP_FILTER) as u8;
+
+            // The intra frame does not use mode deltas.
+            levels[0] = [clamp(level + i32::from(ref_deltas[0]) * scale); 2];
+            for (ref_frame, ref_levels) in levels.iter_mut().enumerate().skip(1) {
+                for (mode, mode_level) in ref_levels.iter_mut().enumerate() {
+                    *mode_level = clamp(
+                        level
+                            + i32::from(ref_deltas[ref_frame]) * scale
+                            + i32::from(mode_deltas[mode]) * scale,
+                    );
+                }
+            }
+        }
+
+        levels
+    }
+
+    /// Returns the `segment_flags` of `VASegmentParameterVP9` corresponding to these features.
+    pub fn segment_flags(&self) -> VP9SegmentFlags {
+        VP9SegmentFlags::new(
+            self.ref_frame.is_some() as u16,
+            self.ref_frame.unwrap_or(0) as u16,
+            self.skip as u16,
+        )
+    }
+}
+
+/// Map assigning a segment to each block of a VP9 frame, e.g. to fill an encoder segment map
+/// buffer.
+#[derive(Debug, Clone)]
+pub struct VP9SegmentationMap {
+    width_in_blocks: u32,
+    height_in_blocks: u32,
+    segment_ids: Vec<u8>,
+}
+
+impl VP9SegmentationMap {
+    /// Creates a map of `width_in_blocks`x`height_in_blocks` blocks, all assigned to segment 0.
+    pub fn new(width_in_blocks: u32, height_in_blocks: u32) -> Result<Self, VP9SegmentationError> {
+        let size = usize::try_from(width_in_blocks)
+            .ok()
+            .zip(usize::try_from(height_in_blocks).ok())
+            .and_then(|(width, height)| width.checked_mul(height))
+            .ok_or(VP9SegmentationError::InvalidSize(
+                width_in_blocks,
+                height_in_blocks,
+            ))?;
+
+        Ok(Self {
+            width_in_blocks,
+            height_in_blocks,
+            segment_ids: vec![0; size],
+        })
+    }
+
+    /// Returns the index of block (`x`, `y`) in `segment_ids`. Cannot overflow for a block within
+    /// the map, as the size of the map has been checked on creation.
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width_in_blocks as usize + x as usize
+    }
+
+    /// Assigns the rectangle of `width`x`height` blocks starting at block (`x`, `y`) to
+    /// `segment_id`. The rectangle is clipped to the map.
+    pub fn fill_rect(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        segment_id: u8,
+    ) -> Result<(), VP9SegmentationError> {
+        if segment_id as usize >= VP9_MAX_SEGMENTS {
+            return Err(VP9SegmentationError::InvalidSegmentId(segment_id));
+        }
+        if x >= self.width_in_blocks || y >= self.height_in_blocks {
+            return Err(VP9SegmentationError::OutOfBounds(x, y));
+        }
+
+        // The rectangle is clipped to the map, so a saturated end is equivalent to the actual one.
+        let x_end = x.saturating_add(width).min(self.width_in_blocks);
+        let y_end = y.saturating_add(height).min(self.height_in_blocks);
+        for row in y..y_end {
+            let start = self.index(x, row);
+            let end = self.index(x_end, row);
+            self.segment_ids[start..end].fill(segment_id);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the segment of the block at (`x`, `y`).
+    pub fn segment_id(&self, x: u32, y: u32) -> Option<u8> {
+        if x >= self.width_in_blocks || y >= self.height_in_blocks {
+            return None;
+        }
+
+        Some(self.segment_ids[self.index(x, y)])
+    }
+
+    /// Returns the segment ids of the map in raster order, one byte per block.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.segment_ids
+    }
+
+    /// Consumes the map and returns its segment ids in raster order, one byte per block.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.segment_ids
+    }
+}
+
 /// Wrapper over the `VASliceParameterBufferVP9` FFI type.
 pub struct SliceParameterBufferVP9(Box<bindings::VASliceParameterBufferVP9>);
 
@@ -438,7 +713,96 @@ impl EncPictureParameterBufferVP9 {
         }))
     }
 
+    /// Checks the ranges of the fields of this picture, so invalid values are reported before
+    /// reaching the driver.
+    pub fn validate(&self) -> Result<(), VP9ParameterError> {
+        let p = self.0.as_ref();
+
+        validate_vp9_frame_fields(
+            p.filter_level,
+            p.sharpness_level,
+            p.log2_tile_columns,
+            p.log2_tile_rows,
+        )?;
+        for delta in [
+            p.luma_dc_qindex_delta,
+            p.chroma_ac_qindex_delta,
+            p.chroma_dc_qindex_delta,
+        ] {
+            if !(-15..=15).contains(&delta) {
+                return Err(VP9ParameterError::InvalidQIndexDelta(delta));
+            }
+        }
+        for delta in p.ref_lf_delta.iter().chain(p.mode_lf_delta.iter()) {
+            if !(-VP9_MAX_LOOP_FILTER..=VP9_MAX_LOOP_FILTER).contains(&i32::from(*delta)) {
+                return Err(VP9ParameterError::InvalidLoopFilterDelta(*delta));
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAEncPictureParameterBufferVP9 {
         &mut self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_filter_levels() {
+        let features = VP9SegmentFeatures {
+            alt_lf: Some(-10),
+            ..Default::default()
+        };
+        assert!(features.validate().is_ok());
+
+        // Delta update without mode/ref deltas.
+        assert_eq!(features.filter_levels(40, false, None), [[30; 2]; 4]);
+        // Absolute update, clamped.
+        assert_eq!(features.filter_levels(40, true, None), [[0; 2]; 4]);
+
+        let levels = features.filter_levels(40, false, Some(([1, 0, -1, -1], [0, 2])));
+        assert_eq!(levels, [[31, 31], [30, 32], [29, 31], [29, 31]]);
+
+        let invalid = VP9SegmentFeatures {
+            alt_q: Some(300),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert_eq!(invalid.qindex(100, false), 255);
+    }
+
+    #[test]
+    fn segmentation_map() {
+        let mut map = VP9SegmentationMap::new(4, 3).unwrap();
+        map.fill_rect(1, 1, 10, 10, 3).unwrap();
+        map.fill_rect(3, 2, u32::MAX, u32::MAX, 3).unwrap();
+        assert_eq!(map.as_slice(), &[0, 0, 0, 0, 0, 3, 3, 3, 0, 3, 3, 3]);
+        assert_eq!(map.segment_id(2, 2), Some(3));
+        assert!(map.fill_rect(0, 0, 1, 1, 8).is_err());
+        assert!(map.fill_rect(4, 0, 1, 1, 1).is_err());
+
+        #[cfg(target_pointer_width = "32")]
+        assert!(VP9SegmentationMap::new(u32::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn frame_fields_ranges() {
+        assert!(validate_vp9_frame_fields(63, 7, 6, 2).is_ok());
+        assert!(matches!(
+            validate_vp9_frame_fields(64, 0, 0, 0),
+            Err(VP9ParameterError::InvalidLoopFilterLevel(64))
+        ));
+        assert!(matches!(
+            validate_vp9_frame_fields(0, 8, 0, 0),
+            Err(VP9ParameterError::InvalidSharpnessLevel(8))
+        ));
+        assert!(matches!(
+            validate_vp9_frame_fields(0, 0, 0, 3),
+            Err(VP9ParameterError::InvalidTiles(0, 3))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Summaries of the capabilities of a display, e.g. to schedule work across several GPUs.
+//!
+//! With the `serde` feature, these summaries can be serialized, e.g. to dump the capabilities of
+//! a machine as JSON.
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::Display;
+use crate::DrmDeviceInfo;
+use crate::OpenDrmDisplayError;
+use crate::VaError;
+
+/// Capabilities of a profile and entrypoint pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EntrypointCapabilities {
+    pub entrypoint: bindings::VAEntrypoint::Type,
+    /// Supported render target formats, a combination of `VA_RT_FORMAT_*`.
+    pub rt_formats: u32,
+    /// Maximum picture width, if reported by the driver.
+    pub max_width: Option<u32>,
+    /// Maximum picture height, if reported by the driver.
+    pub max_height: Option<u32>,
+}
+
+/// Capabilities of a profile, for each of its supported entrypoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProfileCapabilities {
+    pub profile: bindings::VAProfile::Type,
+    pub entrypoints: Vec<EntrypointCapabilities>,
+}
+
+/// Capabilities of a display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisplayCapabilities {
+    /// Vendor string of the driver.
+    pub vendor: String,
+    pub profiles: Vec<ProfileCapabilities>,
+    /// FourCCs of the supported image formats.
+    pub image_formats: Vec<u32>,
+}
+
+#[derive(Debug, Error)]
+pub enum ProbeDeviceError {
+    #[error("cannot open display: {0}")]
+    Open(#[from] OpenDrmDisplayError),
+    #[error("cannot query capabilities: {0}")]
+    VaError(#[from] VaError),
+}
+
+impl Display {
+    /// Returns the capabilities of this display, gathered from the supported profiles and
+    /// entrypoints and their attributes.
+    pub fn query_capabilities(&self) -> Result<DisplayCapabilities, VaError> {
+        let mut profiles = Vec::new();
+
+        for profile in self.query_config_profiles()? {
+            let mut entrypoints = Vec::new();
+
+            for entrypoint in self.query_config_entrypoints(profile)? {
+                let mut attrs = [
+                    bindings::VAConfigAttribType::VAConfigAttribRTFormat,
+                    bindings::VAConfigAttribType::VAConfigAttribMaxPictureWidth,
+                    bindings::VAConfigAttribType::VAConfigAttribMaxPictureHeight,
+                ]
+                .map(|type_| bindings::VAConfigAttrib { type_, value: 0 });
+
+                self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+                let supported =
+                    |value: u32| (value != bindings::VA_ATTRIB_NOT_SUPPORTED).then_some(value);
+                entrypoints.push(EntrypointCapabilities {
+                    entrypoint,
+                    rt_formats: supported(attrs[0].value).unwrap_or(0),
+                    max_width: supported(attrs[1].value),
+                    max_height: supported(attrs[2].value),
+                });
+            }
+
+            profiles.push(ProfileCapabilities {
+                profile,
+                entrypoints,
+            });
+        }
+
+        Ok(DisplayCapabilities {
+            vendor: self.query_vendor_string().unwrap_or_default(),
+            profiles,
+            image_formats: self
+                .query_image_formats()?
+                .iter()
+                .map(|format| format.fourcc)
+                .collect(),
+        })
+    }
+
+    /// Opens every DRM render node of the system and returns its capabilities, or the reason why
+    /// they could not be obtained.
+    pub fn probe_all() -> Vec<(DrmDeviceInfo, Result<DisplayCapabilities, ProbeDeviceError>)> {
+        Self::enumerate_devices()
+            .into_iter()
+            .map(|device| {
+                let capabilities = device
+                    .open()
+                    .map_err(ProbeDeviceError::from)
+                    .and_then(|display| Ok(display.query_capabilities()?));
+
+                (device, capabilities)
+            })
+            .collect()
+    }
+}
@@ -11,13 +11,21 @@ use crate::bindings;
 use crate::display::Display;
 use crate::generic_value::GenericValue;
 use crate::va_check;
+use crate::DmabufImportMemoryType;
 use crate::GenericValueError;
+use crate::MemoryType;
+use crate::ProcessingRate;
+use crate::ProcessingRateParameter;
+use crate::SurfaceAlignment;
 use crate::VaError;
+use crate::VaObjectKind;
 
 /// A configuration for a given [`Display`].
 pub struct Config {
     display: Rc<Display>,
     id: bindings::VAConfigID,
+    profile: bindings::VAProfile::Type,
+    entrypoint: bindings::VAEntrypoint::Type,
 }
 
 #[derive(Debug, Error)]
@@ -28,6 +36,88 @@ pub enum QuerySurfaceAttributesError {
     GenericValueError(#[from] GenericValueError),
 }
 
+/// Surface attributes supported by a [`Config`], as reported by `vaQuerySurfaceAttributes`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SupportedSurfaceAttributes {
+    /// Pixel formats (`VA_FOURCC_*`) surfaces can be created with.
+    pub pixel_formats: Vec<u32>,
+    /// Minimum width of surfaces, if reported by the driver.
+    pub min_width: Option<u32>,
+    /// Maximum width of surfaces, if reported by the driver.
+    pub max_width: Option<u32>,
+    /// Minimum height of surfaces, if reported by the driver.
+    pub min_height: Option<u32>,
+    /// Maximum height of surfaces, if reported by the driver.
+    pub max_height: Option<u32>,
+    /// Memory types surfaces can be backed by.
+    pub memory_types: Vec<MemoryType>,
+    /// Alignment applied by the driver to the size of surfaces, if reported.
+    pub alignment: Option<SurfaceAlignment>,
+}
+
+impl SupportedSurfaceAttributes {
+    /// Builds the supported attributes from the `(type, value)` pairs returned by the driver.
+    /// Attributes of unknown type or with a non-integer value are ignored.
+    fn from_values(
+        values: impl IntoIterator<Item = (bindings::VASurfaceAttribType::Type, GenericValue)>,
+    ) -> Self {
+        let mut attributes = Self::default();
+
+        for (attr_type, value) in values {
+            let GenericValue::Integer(value) = value else {
+                continue;
+            };
+            let value = value as u32;
+
+            match attr_type {
+                bindings::VASurfaceAttribType::VASurfaceAttribPixelFormat => {
+                    attributes.pixel_formats.push(value)
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribMinWidth => {
+                    attributes.min_width = Some(value)
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribMaxWidth => {
+                    attributes.max_width = Some(value)
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribMinHeight => {
+                    attributes.min_height = Some(value)
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribMaxHeight => {
+                    attributes.max_height = Some(value)
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribMemoryType => {
+                    for memory_type in [
+                        MemoryType::Va,
+                        MemoryType::V4L2,
+                        MemoryType::UserPtr,
+                        MemoryType::DrmPrime,
+                        MemoryType::DrmPrime2,
+                    ] {
+                        if value & memory_type as u32 != 0
+                            && !attributes.memory_types.contains(&memory_type)
+                        {
+                            attributes.memory_types.push(memory_type);
+                        }
+                    }
+                }
+                bindings::VASurfaceAttribType::VASurfaceAttribAlignmentSize => {
+                    attributes.alignment = Some(SurfaceAlignment::from_attribute_value(value))
+                }
+                _ => (),
+            }
+        }
+
+        attributes
+    }
+
+    /// Returns whether a `width`x`height` surface fits within the reported size limits.
+    pub fn supports_size(&self, width: u32, height: u32) -> bool {
+        (self.min_width.unwrap_or(0)..=self.max_width.unwrap_or(u32::MAX)).contains(&width)
+            && (self.min_height.unwrap_or(0)..=self.max_height.unwrap_or(u32::MAX))
+                .contains(&height)
+    }
+}
+
 impl Config {
     /// Creates a Config by wrapping around the `vaCreateConfig` call. This is just a helper for
     /// [`Display::create_config`].
@@ -54,9 +144,13 @@ impl Config {
             )
         })?;
 
+        display.register_object(VaObjectKind::Config, config_id);
+
         Ok(Self {
             display,
             id: config_id,
+            profile,
+            entrypoint,
         })
     }
 
@@ -65,6 +159,41 @@ impl Config {
         self.id
     }
 
+    /// Returns the profile this config has been created for.
+    pub fn profile(&self) -> bindings::VAProfile::Type {
+        self.profile
+    }
+
+    /// Returns the entrypoint this config has been created for.
+    pub fn entrypoint(&self) -> bindings::VAEntrypoint::Type {
+        self.entrypoint
+    }
+
+    /// Queries the rate at which the hardware can process streams described by `params` with this
+    /// config. Wrapper over `vaQueryProcessingRate`.
+    ///
+    /// The config must have been created with the `VAConfigAttribProcessingRate` attribute
+    /// reported as supported for its profile and entrypoint.
+    pub fn query_processing_rate(
+        &self,
+        params: &mut ProcessingRateParameter,
+    ) -> Result<ProcessingRate, VaError> {
+        let mut processing_rate = 0;
+
+        // Safe because `self` represents a valid VAConfig and `params` is a properly initialized
+        // `VAProcessingRateParameter`.
+        va_check(unsafe {
+            bindings::vaQueryProcessingRate(
+                self.display.handle(),
+                self.id,
+                params.inner_mut(),
+                &mut processing_rate,
+            )
+        })?;
+
+        Ok(ProcessingRate(processing_rate))
+    }
+
     // Queries surface attributes for this config.
     //
     // This function queries for all supported attributes for this configuration. In particular, if
@@ -123,15 +252,140 @@ impl Config {
             })
             .collect()
     }
+
+    /// Returns all the surface attributes supported by this config, such as the pixel formats,
+    /// size limits and memory types surfaces can be created with.
+    pub fn supported_surface_attributes(
+        &mut self,
+    ) -> Result<SupportedSurfaceAttributes, QuerySurfaceAttributesError> {
+        let values = self
+            .query_surface_attributes()?
+            .into_iter()
+            .map(|attr| Ok((attr.type_, GenericValue::try_from(attr.value)?)))
+            .collect::<Result<Vec<_>, QuerySurfaceAttributesError>>()?;
+
+        Ok(SupportedSurfaceAttributes::from_values(values))
+    }
+
+    /// Returns the memory type to use to import DMA-BUFs into surfaces used with this config,
+    /// based on the `VASurfaceAttribMemoryType` attribute reported by the driver. Returns `None`
+    /// if the driver cannot import DMA-BUFs.
+    pub fn dmabuf_import_memory_type(
+        &mut self,
+    ) -> Result<Option<DmabufImportMemoryType>, QuerySurfaceAttributesError> {
+        Ok(DmabufImportMemoryType::select(
+            self.supported_memory_types()?,
+        ))
+    }
+
+    /// Returns the `VA_SURFACE_ATTRIB_MEM_TYPE_*` bits reported by the driver for the
+    /// `VASurfaceAttribMemoryType` attribute, e.g. to pass to
+    /// [`crate::DmabufImportDescriptor::set_protected`].
+    pub fn supported_memory_types(&mut self) -> Result<u32, QuerySurfaceAttributesError> {
+        Ok(self
+            .query_surface_attributes_by_type(
+                bindings::VASurfaceAttribType::VASurfaceAttribMemoryType,
+            )?
+            .into_iter()
+            .fold(0, |acc, value| match value {
+                GenericValue::Integer(value) => acc | value as u32,
+                _ => acc,
+            }))
+    }
+
+    /// Returns the alignment the driver will apply to the coded size of surfaces used with this
+    /// config.
+    ///
+    /// This combines the block size of the codec with the `VASurfaceAttribAlignmentSize` attribute
+    /// if the driver reports it. Callers knowing that the driver allocates field-aligned surfaces
+    /// can apply [`crate::DriverQuirks::FIELD_ALIGNED_SURFACES`] with
+    /// [`SurfaceAlignment::with_quirks`]. The result can be used with
+    /// [`SurfaceAlignment::coded_size`] and [`SurfaceAlignment::plane_layouts`] to allocate
+    /// external buffers suitable for import.
+    pub fn surface_alignment(&mut self) -> Result<SurfaceAlignment, QuerySurfaceAttributesError> {
+        let codec_alignment = SurfaceAlignment::for_profile(self.profile);
+        let driver_alignment = self
+            .query_surface_attributes_by_type(
+                bindings::VASurfaceAttribType::VASurfaceAttribAlignmentSize,
+            )?
+            .into_iter()
+            .find_map(|value| match value {
+                GenericValue::Integer(value) => {
+                    Some(SurfaceAlignment::from_attribute_value(value as u32))
+                }
+                _ => None,
+            });
+
+        Ok(match driver_alignment {
+            Some(driver_alignment) => codec_alignment.combine(driver_alignment),
+            None => codec_alignment,
+        })
+    }
 }
 
 impl Drop for Config {
     fn drop(&mut self) {
         // Safe because `self` represents a valid Config.
         let status = va_check(unsafe { bindings::vaDestroyConfig(self.display.handle(), self.id) });
+        self.display
+            .unregister_object(VaObjectKind::Config, self.id);
 
         if status.is_err() {
             error!("vaDestroyConfig failed: {}", status.unwrap_err());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_surface_attributes() {
+        let attributes = SupportedSurfaceAttributes::from_values([
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribPixelFormat,
+                GenericValue::Integer(bindings::VA_FOURCC_NV12 as i32),
+            ),
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribPixelFormat,
+                GenericValue::Integer(bindings::VA_FOURCC_P010 as i32),
+            ),
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribMaxWidth,
+                GenericValue::Integer(4096),
+            ),
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribMaxHeight,
+                GenericValue::Integer(2304),
+            ),
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribMemoryType,
+                GenericValue::Integer(
+                    (bindings::VA_SURFACE_ATTRIB_MEM_TYPE_VA
+                        | bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME_2)
+                        as i32,
+                ),
+            ),
+            (
+                bindings::VASurfaceAttribType::VASurfaceAttribMinWidth,
+                GenericValue::Float(1.0),
+            ),
+        ]);
+
+        assert_eq!(
+            attributes,
+            SupportedSurfaceAttributes {
+                pixel_formats: vec![bindings::VA_FOURCC_NV12, bindings::VA_FOURCC_P010],
+                min_width: None,
+                max_width: Some(4096),
+                min_height: None,
+                max_height: Some(2304),
+                memory_types: vec![MemoryType::Va, MemoryType::DrmPrime2],
+                alignment: None,
+            }
+        );
+        assert!(attributes.supports_size(3840, 2160));
+        assert!(!attributes.supports_size(8192, 4320));
+    }
+}
@@ -16,6 +16,7 @@ use crate::EncCodedBuffer;
 use crate::Surface;
 use crate::SurfaceMemoryDescriptor;
 use crate::VaError;
+use crate::VaObjectKind;
 
 /// A VA context for a particular [`Display`].
 pub struct Context {
@@ -62,6 +63,8 @@ impl Context {
             )
         })?;
 
+        display.register_object(VaObjectKind::Context, context_id);
+
         Ok(Rc::new(Self {
             display,
             id: context_id,
@@ -78,6 +81,32 @@ impl Context {
         self.id
     }
 
+    /// Returns the video processing filters supported by this context. Wrapper over
+    /// `vaQueryVideoProcFilters`.
+    pub fn query_video_proc_filters(
+        &self,
+    ) -> Result<Vec<bindings::VAProcFilterType::Type>, VaError> {
+        let mut num_filters = bindings::VAProcFilterType::VAProcFilterCount;
+        let mut filters = Vec::with_capacity(num_filters as usize);
+
+        // Safe because `self` represents a valid `VAContext` and `filters` has enough capacity
+        // for `num_filters` elements.
+        va_check(unsafe {
+            bindings::vaQueryVideoProcFilters(
+                self.display.handle(),
+                self.id,
+                filters.as_mut_ptr(),
+                &mut num_filters,
+            )
+        })?;
+
+        // Safe because `filters` is allocated with `VAProcFilterCount` elements and
+        // `vaQueryVideoProcFilters` wrote `num_filters` of them.
+        unsafe { filters.set_len(num_filters as usize) };
+
+        Ok(filters)
+    }
+
     /// Create a new buffer of type `type_`.
     pub fn create_buffer(self: &Rc<Self>, type_: BufferType) -> Result<Buffer, VaError> {
         Buffer::new(Rc::clone(self), type_)
@@ -94,6 +123,8 @@ impl Drop for Context {
         // Safe because `self` represents a valid VAContext.
         let status =
             va_check(unsafe { bindings::vaDestroyContext(self.display.handle(), self.id) });
+        self.display
+            .unregister_object(VaObjectKind::Context, self.id);
 
         if status.is_err() {
             error!("vaDestroyContext failed: {}", status.unwrap_err());
@@ -0,0 +1,95 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! GPU-side copies between surfaces or buffers using `vaCopy`.
+//!
+//! `vaCopy` is only available from VA-API 1.10. With older versions, copies fail with
+//! `VA_STATUS_ERROR_UNIMPLEMENTED`, as they would with a driver that does not implement them.
+
+use crate::bindings;
+use crate::display::Display;
+use crate::va_check;
+use crate::VaError;
+
+/// Engine selection hint for a copy, aka `VA_EXEC_MODE_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CopyMode {
+    /// Let the driver decide.
+    #[default]
+    Default,
+    /// Prefer the engine with the lowest power consumption.
+    PowerSaving,
+    /// Prefer the fastest engine.
+    Performance,
+}
+
+#[cfg(libva_1_10_or_higher)]
+impl CopyMode {
+    fn va_exec_mode(&self) -> u32 {
+        match self {
+            Self::Default => bindings::VA_EXEC_MODE_DEFAULT,
+            Self::PowerSaving => bindings::VA_EXEC_MODE_POWER_SAVING,
+            Self::Performance => bindings::VA_EXEC_MODE_PERFORMANCE,
+        }
+    }
+}
+
+/// Object that can be the source or destination of a copy.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(libva_1_10_or_higher), allow(dead_code))]
+pub(crate) enum CopyObject {
+    Surface(bindings::VASurfaceID),
+    Buffer(bindings::VABufferID),
+}
+
+#[cfg(libva_1_10_or_higher)]
+impl CopyObject {
+    fn to_va(self) -> bindings::VACopyObject {
+        match self {
+            Self::Surface(surface_id) => bindings::VACopyObject {
+                obj_type: bindings::VACopyObjectType::VACopyObjectSurface,
+                object: bindings::_VACopyObject__bindgen_ty_1 { surface_id },
+                ..Default::default()
+            },
+            Self::Buffer(buffer_id) => bindings::VACopyObject {
+                obj_type: bindings::VACopyObjectType::VACopyObjectBuffer,
+                object: bindings::_VACopyObject__bindgen_ty_1 { buffer_id },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Copies `src` into `dst` using `vaCopy`, waiting for the copy to complete before returning.
+#[cfg(libva_1_10_or_higher)]
+pub(crate) fn copy(
+    display: &Display,
+    dst: CopyObject,
+    src: CopyObject,
+    mode: CopyMode,
+) -> Result<(), VaError> {
+    let mut dst = dst.to_va();
+    let mut src = src.to_va();
+    // `va_copy_sync` occupies bits 0-1 and `va_copy_mode` bits 2-5.
+    let option = bindings::VACopyOption {
+        value: bindings::VA_EXEC_SYNC | (mode.va_exec_mode() << 2),
+    };
+
+    // Safe because `display` is a valid VADisplay, and `dst` and `src` are properly initialized
+    // copy objects referring to objects of that display.
+    display.check_device_lost(va_check(unsafe {
+        bindings::vaCopy(display.handle(), &mut dst, &mut src, option)
+    }))
+}
+
+/// Fallback for [`copy`] without `vaCopy`.
+#[cfg(not(libva_1_10_or_higher))]
+pub(crate) fn copy(
+    _display: &Display,
+    _dst: CopyObject,
+    _src: CopyObject,
+    _mode: CopyMode,
+) -> Result<(), VaError> {
+    va_check(bindings::VA_STATUS_ERROR_UNIMPLEMENTED as bindings::VAStatus)
+}
@@ -2,9 +2,14 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use std::cell::Cell;
+use std::cell::RefCell;
 use std::ffi::CStr;
+use std::ffi::CString;
 use std::fs::File;
 use std::io;
+use std::os::fd::BorrowedFd;
+use std::os::fd::OwnedFd;
 use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::path::PathBuf;
@@ -15,11 +20,28 @@ use thiserror::Error;
 use crate::bindings;
 use crate::config::Config;
 use crate::context::Context;
+use crate::registry::ObjectRegistry;
+use crate::rt_format_for_fourcc;
+use crate::subpicture::SubpictureFlags;
 use crate::surface::Surface;
 use crate::va_check;
+use crate::CreateSurfacesError;
+use crate::DecSliceModes;
+use crate::DisplayAttribute;
+use crate::DisplayAttributeInfo;
+use crate::DriverInfo;
+use crate::DrmPrimeSurfaceDescriptor;
+use crate::EncRoiCapabilities;
+use crate::EncSliceStructure;
+use crate::HighBitDepthFormat;
+use crate::LiveObject;
+use crate::PackedHeaders;
+use crate::SurfaceFormatError;
+use crate::SurfaceHandle;
 use crate::SurfaceMemoryDescriptor;
 use crate::UsageHint;
 use crate::VaError;
+use crate::VaObjectKind;
 
 /// Iterates over existing DRM devices.
 ///
@@ -60,21 +82,175 @@ impl Iterator for DrmDeviceIterator {
     }
 }
 
-/// A VADisplay opened over DRM.
+// Xlib's `Display` type is not generated by bindgen as it would clash with our own `Display`, so
+// the functions taking one are declared here with an opaque pointer instead.
+#[cfg(feature = "x11")]
+extern "C" {
+    fn XOpenDisplay(name: *const std::ffi::c_char) -> *mut std::ffi::c_void;
+    fn XCloseDisplay(display: *mut std::ffi::c_void) -> std::ffi::c_int;
+    fn vaGetDisplay(dpy: *mut std::ffi::c_void) -> bindings::VADisplay;
+}
+
+/// Connection to an X server, closed on drop.
+#[cfg(feature = "x11")]
+struct X11Connection(*mut std::ffi::c_void);
+
+#[cfg(feature = "x11")]
+impl Drop for X11Connection {
+    fn drop(&mut self) {
+        // Safe because `self.0` is a connection returned by `XOpenDisplay`, which is not used
+        // anymore since the `VADisplay` using it has been terminated.
+        unsafe { XCloseDisplay(self.0) };
+    }
+}
+
+/// Native display a `VADisplay` has been opened on, and which must be kept alive while the
+/// display is in use.
+#[allow(dead_code)]
+enum DisplayBackend {
+    /// DRM device file.
+    Drm(File),
+    /// Connection to an X server.
+    #[cfg(feature = "x11")]
+    X11(X11Connection),
+}
+
+/// Description of a DRM render node, as returned by [`Display::enumerate_devices`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DrmDeviceInfo {
+    /// Path to the render node, e.g. `/dev/dri/renderD128`.
+    pub path: PathBuf,
+    /// PCI vendor ID of the device, if available.
+    pub vendor_id: Option<u16>,
+    /// PCI device ID of the device, if available.
+    pub device_id: Option<u16>,
+    /// Name of the kernel driver of the device, e.g. `i915` or `amdgpu`, if available.
+    pub kernel_driver: Option<String>,
+}
+
+impl DrmDeviceInfo {
+    /// Returns the information of the render node at `path`, gathered from sysfs.
+    fn from_path(path: PathBuf) -> Self {
+        let sysfs_device = path
+            .file_name()
+            .map(|name| Path::new("/sys/class/drm").join(name).join("device"));
+        let read_id = |file: &str| {
+            sysfs_device
+                .as_ref()
+                .and_then(|device| std::fs::read_to_string(device.join(file)).ok())
+                .and_then(|id| parse_sysfs_id(&id))
+        };
+
+        Self {
+            vendor_id: read_id("vendor"),
+            device_id: read_id("device"),
+            kernel_driver: sysfs_device
+                .and_then(|device| std::fs::read_link(device.join("driver")).ok())
+                .and_then(|driver| {
+                    driver
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                }),
+            path,
+        }
+    }
+
+    /// Opens a `Display` on this device.
+    pub fn open(&self) -> Result<Rc<Display>, OpenDrmDisplayError> {
+        Display::open_drm_display(&self.path)
+    }
+}
+
+/// Parses a sysfs ID such as `0x8086`, possibly followed by a newline.
+fn parse_sysfs_id(id: &str) -> Option<u16> {
+    u16::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// A VADisplay opened over DRM, or X11 if the `x11` feature is enabled.
 ///
 /// A Display is the starting point to using libva. This struct is essentially a safe wrapper over
 /// `VADisplay`, from which [`Surface`]s and [`Context`]s can be allocated in order to perform
 /// actual work using [`Display::create_surfaces`] and [`Display::create_context`], respectively.
-///
-/// Although libva offers several ways to create a display, this struct currently only supports
-/// opening through DRM. It may be extended to support other display types (X11, Wayland) in the
-/// future.
 pub struct Display {
     /// Handle to interact with the underlying `VADisplay`.
     handle: bindings::VADisplay,
-    /// DRM file that must be kept open while the display is in use.
-    #[allow(dead_code)]
-    drm_file: File,
+    /// Native display that must be kept open while the display is in use. It is closed after
+    /// `vaTerminate` has been called in `drop`.
+    backend: DisplayBackend,
+    /// Registry of the live objects created from this display.
+    registry: RefCell<ObjectRegistry>,
+    /// Callbacks receiving the messages of libva. Boxed as libva keeps a pointer to them.
+    message_callbacks: Box<RefCell<MessageCallbacks>>,
+    /// Whether the device has been lost, after which all VA objects of the display are invalid.
+    device_lost: Cell<bool>,
+    /// Name of the VA driver forced when opening the display, if any.
+    driver_name: Option<CString>,
+}
+
+/// Callback receiving a message from libva, without its trailing newline.
+pub type MessageCallback = Box<dyn Fn(&str)>;
+
+/// User callbacks for the messages of libva. Messages are logged if no callback is set.
+#[derive(Default)]
+struct MessageCallbacks {
+    error: Option<MessageCallback>,
+    info: Option<MessageCallback>,
+}
+
+/// Forwards `message` to `callback`, or to `log` with `level` if it is `None`.
+///
+/// # Safety
+///
+/// `user_context` must point to the `RefCell<MessageCallbacks>` of a live display, and `message`
+/// must be a valid C string.
+unsafe fn dispatch_message(
+    user_context: *mut std::ffi::c_void,
+    message: *const std::ffi::c_char,
+    level: log::Level,
+    callback: impl Fn(&MessageCallbacks) -> Option<&MessageCallback>,
+) {
+    let message = CStr::from_ptr(message).to_string_lossy();
+    let message = message.trim_end();
+    let callbacks = &*(user_context as *const RefCell<MessageCallbacks>);
+
+    // The callbacks cannot be borrowed if libva emits a message while they are being replaced.
+    match callbacks.try_borrow() {
+        Ok(callbacks) => match callback(&callbacks) {
+            Some(callback) => callback(message),
+            None => log::log!(target: "libva", level, "{}", message),
+        },
+        Err(_) => log::log!(target: "libva", level, "{}", message),
+    }
+}
+
+unsafe extern "C" fn error_message_callback(
+    user_context: *mut std::ffi::c_void,
+    message: *const std::ffi::c_char,
+) {
+    // Safe because libva passes the user context and message given to `vaSetErrorCallback`.
+    dispatch_message(user_context, message, log::Level::Error, |c| {
+        c.error.as_ref()
+    });
+}
+
+unsafe extern "C" fn info_message_callback(
+    user_context: *mut std::ffi::c_void,
+    message: *const std::ffi::c_char,
+) {
+    // Safe because libva passes the user context and message given to `vaSetInfoCallback`.
+    dispatch_message(user_context, message, log::Level::Info, |c| c.info.as_ref());
+}
+
+/// Health status of a display, as returned by [`Display::health`].
+#[derive(Debug)]
+pub enum DisplayHealth {
+    /// The driver answered the probe successfully.
+    Healthy,
+    /// The device has been lost, and the display must be reopened.
+    DeviceLost,
+    /// The driver failed to answer the probe.
+    Failing(VaError),
 }
 
 /// Error type for `Display::open_drm_display`.
@@ -84,21 +260,170 @@ pub enum OpenDrmDisplayError {
     DeviceOpen(io::Error),
     #[error("vaGetDisplayDRM returned NULL")]
     VaGetDisplayDrm,
+    #[error("call to vaSetDriverName failed: {0}")]
+    VaSetDriverName(VaError),
     #[error("call to vaInitialize failed: {0}")]
     VaInitialize(VaError),
+    #[error("the display is not opened on a DRM device")]
+    NotDrmDisplay,
 }
 
-impl Display {
+/// Error type for `Display::attach_surface`.
+#[derive(Debug, Error)]
+pub enum AttachSurfaceError {
+    #[error("cannot attach surface: {0}")]
+    SurfaceFormat(#[from] SurfaceFormatError),
+    #[error("error while creating surface: {0}")]
+    VaError(#[from] VaError),
+}
+
+/// Error type for `Display::open_x11`.
+#[cfg(feature = "x11")]
+#[derive(Debug, Error)]
+pub enum OpenX11DisplayError {
+    #[error("cannot connect to the X server")]
+    XOpenDisplay,
+    #[error("vaGetDisplay returned NULL")]
+    VaGetDisplay,
+    #[error("call to vaSetDriverName failed: {0}")]
+    VaSetDriverName(VaError),
+    #[error("call to vaInitialize failed: {0}")]
+    VaInitialize(VaError),
+}
+
+/// Builder to open a [`Display`] with non-default options.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayBuilder {
+    driver_name: Option<CString>,
+}
+
+impl DisplayBuilder {
+    /// Creates a builder with the default options.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Forces the use of the VA driver `name`, e.g. `iHD`, instead of the one libva would pick
+    /// for the device. Wrapper over `vaSetDriverName`.
+    ///
+    /// This avoids relying on the `LIBVA_DRIVER_NAME` environment variable, which may not be
+    /// settable in sandboxed environments.
+    pub fn driver_name(mut self, name: &CStr) -> Self {
+        self.driver_name = Some(name.to_owned());
+        self
+    }
+
     /// Opens and initializes a specific DRM `Display`.
     ///
     /// `path` is the path to a DRM device that supports VAAPI, e.g. `/dev/dri/renderD128`.
-    pub fn open_drm_display<P: AsRef<Path>>(path: P) -> Result<Rc<Self>, OpenDrmDisplayError> {
+    pub fn open_drm_display<P: AsRef<Path>>(
+        self,
+        path: P,
+    ) -> Result<Rc<Display>, OpenDrmDisplayError> {
         let file = std::fs::File::options()
             .read(true)
             .write(true)
             .open(path.as_ref())
             .map_err(OpenDrmDisplayError::DeviceOpen)?;
 
+        Display::open_drm_file(file, self.driver_name)
+    }
+
+    /// Opens and initializes a `Display` on the DRM device `fd`, which is closed when the display
+    /// is dropped.
+    pub fn open_drm_display_from_owned_fd(
+        self,
+        fd: OwnedFd,
+    ) -> Result<Rc<Display>, OpenDrmDisplayError> {
+        Display::open_drm_file(File::from(fd), self.driver_name)
+    }
+
+    /// Opens and initializes a `Display` on the DRM device `fd`, e.g. a file descriptor owned by a
+    /// compositor or GBM device.
+    ///
+    /// `fd` is duplicated, and the display only closes its duplicate when dropped. The caller
+    /// thus remains free to close `fd` at any time.
+    pub fn open_drm_display_from_fd(
+        self,
+        fd: BorrowedFd,
+    ) -> Result<Rc<Display>, OpenDrmDisplayError> {
+        let fd = fd
+            .try_clone_to_owned()
+            .map_err(OpenDrmDisplayError::DeviceOpen)?;
+
+        self.open_drm_display_from_owned_fd(fd)
+    }
+
+    /// Opens and initializes a `Display` on the X server `name`, or on the one designated by the
+    /// `DISPLAY` environment variable if `name` is `None`.
+    ///
+    /// Surfaces of displays opened this way can be presented with `vaPutSurface`.
+    #[cfg(feature = "x11")]
+    pub fn open_x11(self, name: Option<&CStr>) -> Result<Rc<Display>, OpenX11DisplayError> {
+        // Safe because `name` is either NULL or a valid C string, and the returned pointer is
+        // checked for NULL afterwards.
+        let x11_display = unsafe { XOpenDisplay(name.map_or(std::ptr::null(), CStr::as_ptr)) };
+        if x11_display.is_null() {
+            return Err(OpenX11DisplayError::XOpenDisplay);
+        }
+        let connection = X11Connection(x11_display);
+
+        // Safe because `x11_display` is a valid X11 connection, and the pointer is checked for
+        // NULL afterwards.
+        let display = unsafe { vaGetDisplay(connection.0) };
+        if display.is_null() {
+            return Err(OpenX11DisplayError::VaGetDisplay);
+        }
+
+        if let Some(driver_name) = &self.driver_name {
+            Display::set_driver_name(display, driver_name)
+                .map_err(OpenX11DisplayError::VaSetDriverName)?;
+        }
+
+        // The X11 connection will be closed on drop.
+        Display::initialize(display, DisplayBackend::X11(connection), self.driver_name)
+            .map_err(OpenX11DisplayError::VaInitialize)
+    }
+}
+
+impl Display {
+    /// Opens and initializes a specific DRM `Display`.
+    ///
+    /// `path` is the path to a DRM device that supports VAAPI, e.g. `/dev/dri/renderD128`.
+    pub fn open_drm_display<P: AsRef<Path>>(path: P) -> Result<Rc<Self>, OpenDrmDisplayError> {
+        DisplayBuilder::new().open_drm_display(path)
+    }
+
+    /// Opens and initializes a `Display` on the DRM device `fd`, which is closed when the display
+    /// is dropped.
+    pub fn open_drm_display_from_owned_fd(fd: OwnedFd) -> Result<Rc<Self>, OpenDrmDisplayError> {
+        DisplayBuilder::new().open_drm_display_from_owned_fd(fd)
+    }
+
+    /// Opens and initializes a `Display` on the DRM device `fd`, e.g. a file descriptor owned by a
+    /// compositor or GBM device.
+    ///
+    /// `fd` is duplicated, and the display only closes its duplicate when dropped. The caller
+    /// thus remains free to close `fd` at any time.
+    pub fn open_drm_display_from_fd(fd: BorrowedFd) -> Result<Rc<Self>, OpenDrmDisplayError> {
+        DisplayBuilder::new().open_drm_display_from_fd(fd)
+    }
+
+    /// Opens and initializes a `Display` on the X server `name`, or on the one designated by the
+    /// `DISPLAY` environment variable if `name` is `None`.
+    ///
+    /// Surfaces of displays opened this way can be presented with `vaPutSurface`.
+    #[cfg(feature = "x11")]
+    pub fn open_x11(name: Option<&CStr>) -> Result<Rc<Self>, OpenX11DisplayError> {
+        DisplayBuilder::new().open_x11(name)
+    }
+
+    /// Opens and initializes a `Display` on the DRM device `file`, using the VA driver
+    /// `driver_name` if set.
+    fn open_drm_file(
+        file: File,
+        driver_name: Option<CString>,
+    ) -> Result<Rc<Self>, OpenDrmDisplayError> {
         // Safe because fd represents a valid file descriptor and the pointer is checked for
         // NULL afterwards.
         let display = unsafe { bindings::vaGetDisplayDRM(file.as_raw_fd()) };
@@ -106,18 +431,160 @@ impl Display {
             return Err(OpenDrmDisplayError::VaGetDisplayDrm);
         }
 
+        if let Some(driver_name) = &driver_name {
+            Self::set_driver_name(display, driver_name)
+                .map_err(OpenDrmDisplayError::VaSetDriverName)?;
+        }
+
+        // The File will close the DRM fd on drop.
+        Self::initialize(display, DisplayBackend::Drm(file), driver_name)
+            .map_err(OpenDrmDisplayError::VaInitialize)
+    }
+
+    /// Sets the driver of `handle`, a non-NULL `VADisplay` that has not been initialized yet.
+    fn set_driver_name(handle: bindings::VADisplay, driver_name: &CStr) -> Result<(), VaError> {
+        // Safe because `handle` is a valid `VADisplay` and `driver_name` a valid C string, which
+        // libva copies.
+        va_check(unsafe { bindings::vaSetDriverName(handle, driver_name.as_ptr() as *mut _) })
+    }
+
+    /// Initializes `handle`, a non-NULL `VADisplay` opened on `backend`, and returns the
+    /// corresponding `Display`.
+    fn initialize(
+        handle: bindings::VADisplay,
+        backend: DisplayBackend,
+        driver_name: Option<CString>,
+    ) -> Result<Rc<Self>, VaError> {
+        let message_callbacks: Box<RefCell<MessageCallbacks>> = Default::default();
+        let user_context = message_callbacks.as_ref() as *const _ as *mut std::ffi::c_void;
+
+        // Safe because `handle` is a valid `VADisplay`, and `user_context` points to the boxed
+        // callbacks which are kept alive by the display until after `vaTerminate` is called.
+        unsafe {
+            bindings::vaSetErrorCallback(handle, Some(error_message_callback), user_context);
+            bindings::vaSetInfoCallback(handle, Some(info_message_callback), user_context);
+        }
+
         let mut major = 0i32;
         let mut minor = 0i32;
         // Safe because we ensure that the display is valid (i.e not NULL) before calling
-        // vaInitialize. The File will close the DRM fd on drop.
-        va_check(unsafe { bindings::vaInitialize(display, &mut major, &mut minor) })
-            .map(|()| {
-                Rc::new(Self {
-                    handle: display,
-                    drm_file: file,
-                })
-            })
-            .map_err(OpenDrmDisplayError::VaInitialize)
+        // vaInitialize.
+        va_check(unsafe { bindings::vaInitialize(handle, &mut major, &mut minor) })?;
+
+        Ok(Rc::new(Self {
+            handle,
+            backend,
+            registry: Default::default(),
+            message_callbacks,
+            device_lost: Cell::new(false),
+            driver_name,
+        }))
+    }
+
+    /// Returns whether the device of this display has been lost, e.g. after a GPU hang or reset.
+    ///
+    /// The device is considered lost once syncing, copying or submitting a picture fails with
+    /// `VA_STATUS_ERROR_OPERATION_FAILED` or `VA_STATUS_ERROR_HW_BUSY`, which is how drivers report
+    /// a hung or reset GPU, or once [`Display::mark_device_lost`] has been called.
+    ///
+    /// This state is sticky: once lost, all the VA objects created from this display are invalid
+    /// and must be dropped. A new display can then be opened with [`Display::reopen`] to
+    /// re-create them.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.get()
+    }
+
+    /// Probes the driver to check whether it is still responsive, e.g. before submitting a frame
+    /// in a long-running service.
+    ///
+    /// The probe allocates and releases a small surface, which goes through the driver down to the
+    /// kernel, unlike capability queries which are usually answered from static tables. It can
+    /// thus detect a driver or kernel device that fails requests, but not a GPU engine that hangs
+    /// while still accepting allocations. Such hangs only surface as timeouts when syncing, e.g.
+    /// with [`crate::Surface::sync_with_timeout`].
+    pub fn health(self: &Rc<Self>) -> DisplayHealth {
+        if self.is_device_lost() {
+            return DisplayHealth::DeviceLost;
+        }
+
+        match self.create_surfaces(bindings::VA_RT_FORMAT_YUV420, None, 16, 16, None, vec![()]) {
+            Ok(_) => DisplayHealth::Healthy,
+            Err(e) => DisplayHealth::Failing(e),
+        }
+    }
+
+    /// Returns whether the driver of this display is healthy, as reported by
+    /// [`Display::health`].
+    pub fn is_alive(self: &Rc<Self>) -> bool {
+        matches!(self.health(), DisplayHealth::Healthy)
+    }
+
+    /// Marks the device of this display as lost.
+    ///
+    /// libva has no status dedicated to a lost device, so [`Display::is_device_lost`] only detects
+    /// the failures listed there. Callers detecting a reset through another signal, e.g. the reset
+    /// statistics of their DRM driver, can call this method in response.
+    pub fn mark_device_lost(&self) {
+        self.device_lost.set(true);
+    }
+
+    /// Marks the device as lost if `result` failed with a status reported by drivers after a GPU
+    /// hang or reset, and returns `result` unchanged.
+    ///
+    /// `VA_STATUS_ERROR_HW_BUSY` may also be returned by a GPU that is merely overloaded, but as
+    /// it cannot be told apart from a hung one, it is treated as device loss as well.
+    pub(crate) fn check_device_lost<T>(&self, result: Result<T, VaError>) -> Result<T, VaError> {
+        if let Err(e) = &result {
+            let status = e.va_status();
+            if status == bindings::VA_STATUS_ERROR_OPERATION_FAILED as i32
+                || status == bindings::VA_STATUS_ERROR_HW_BUSY as i32
+            {
+                self.mark_device_lost();
+            }
+        }
+
+        result
+    }
+
+    /// Opens and initializes a new `Display` on the same DRM device as this one, e.g. to recover
+    /// after the device has been lost.
+    ///
+    /// The objects created from this display are not transferred and must be re-created from the
+    /// new one.
+    pub fn reopen(&self) -> Result<Rc<Self>, OpenDrmDisplayError> {
+        match &self.backend {
+            DisplayBackend::Drm(file) => Self::open_drm_file(
+                file.try_clone().map_err(OpenDrmDisplayError::DeviceOpen)?,
+                self.driver_name.clone(),
+            ),
+            #[cfg(feature = "x11")]
+            DisplayBackend::X11(_) => Err(OpenDrmDisplayError::NotDrmDisplay),
+        }
+    }
+
+    /// Sets the callback receiving the error messages of libva for this display, or restores
+    /// the default one logging them with [`log::error`] if `callback` is `None`.
+    pub fn set_error_callback(&self, callback: Option<MessageCallback>) {
+        self.message_callbacks.borrow_mut().error = callback;
+    }
+
+    /// Sets the callback receiving the informational messages of libva for this display, or
+    /// restores the default one logging them with [`log::info`] if `callback` is `None`.
+    pub fn set_info_callback(&self, callback: Option<MessageCallback>) {
+        self.message_callbacks.borrow_mut().info = callback;
+    }
+
+    /// Returns all the DRM render nodes of the system, along with the vendor and driver of their
+    /// device, so a specific one can be opened on multi-GPU systems using
+    /// [`DrmDeviceInfo::open`] or [`Display::open_drm_display`].
+    pub fn enumerate_devices() -> Vec<DrmDeviceInfo> {
+        // Unlike `DrmDeviceIterator`, do not stop at the first missing node as there may be gaps
+        // in the numbering of the nodes.
+        (DRM_RENDER_NODE_START..DRM_RENDER_NODE_START + DRM_NUM_NODES)
+            .map(|idx| PathBuf::from(format!("{}{}", DRM_NODE_DEFAULT_PREFIX, idx)))
+            .filter(|path| path.exists())
+            .map(DrmDeviceInfo::from_path)
+            .collect()
     }
 
     /// Opens the first device that succeeds and returns its `Display`.
@@ -137,6 +604,29 @@ impl Display {
         None
     }
 
+    /// Enables or disables the tracking of the objects created from this display, which can then
+    /// be listed with [`Display::live_objects`] to locate leaks. Disabled by default.
+    ///
+    /// Only objects created after the tracking is enabled are listed. In debug builds, the
+    /// backtrace of the creation of each object is also recorded.
+    pub fn set_object_tracking(&self, enabled: bool) {
+        self.registry.borrow_mut().set_enabled(enabled);
+    }
+
+    /// Returns the objects created from this display that are still alive, if object tracking
+    /// has been enabled with [`Display::set_object_tracking`].
+    pub fn live_objects(&self) -> Vec<LiveObject> {
+        self.registry.borrow().live_objects()
+    }
+
+    pub(crate) fn register_object(&self, kind: VaObjectKind, id: u32) {
+        self.registry.borrow_mut().register(kind, id);
+    }
+
+    pub(crate) fn unregister_object(&self, kind: VaObjectKind, id: u32) {
+        self.registry.borrow_mut().unregister(kind, id);
+    }
+
     /// Returns the handle of this display.
     pub(crate) fn handle(&self) -> bindings::VADisplay {
         self.handle
@@ -187,6 +677,13 @@ impl Display {
             .to_string())
     }
 
+    /// Returns the information of the driver of this display, parsed from its vendor string. Its
+    /// [`DriverInfo::quirks`] tell which workarounds callers may need.
+    pub fn query_driver_info(&self) -> std::result::Result<DriverInfo, &'static str> {
+        self.query_vendor_string()
+            .map(|vendor| DriverInfo::from_vendor_string(&vendor))
+    }
+
     /// Query supported entrypoints for a given profile by wrapping `vaQueryConfigEntrypoints`.
     pub fn query_config_entrypoints(
         &self,
@@ -241,6 +738,169 @@ impl Display {
         })
     }
 
+    /// Returns the packed headers supported by `profile`/`entrypoint`, as reported by the
+    /// `VAConfigAttribEncPackedHeaders` attribute.
+    ///
+    /// The headers enabled at config creation must then be attached by the client to the encoded
+    /// pictures that carry them, which can be verified with
+    /// [`crate::Picture::check_packed_headers`].
+    pub fn query_packed_headers(
+        &self,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<PackedHeaders, VaError> {
+        let mut attrs = [bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribEncPackedHeaders,
+            value: 0,
+        }];
+
+        self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+        if attrs[0].value == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return Ok(PackedHeaders::empty());
+        }
+
+        Ok(PackedHeaders::from_bits_truncate(attrs[0].value))
+    }
+
+    /// Returns the slice decoding modes supported by `profile`/`entrypoint`, as reported by the
+    /// `VAConfigAttribDecSliceMode` attribute. Drivers not reporting the attribute only support
+    /// [`DecSliceModes::NORMAL`].
+    ///
+    /// [`crate::SliceDataLayout::preferred_for`] can be used to pick how slice data is submitted.
+    pub fn query_dec_slice_modes(
+        &self,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<DecSliceModes, VaError> {
+        let mut attrs = [bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribDecSliceMode,
+            value: 0,
+        }];
+
+        self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+        if attrs[0].value == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return Ok(DecSliceModes::NORMAL);
+        }
+
+        Ok(DecSliceModes::from_bits_truncate(attrs[0].value))
+    }
+
+    /// Returns the slice structures supported by the encoder for `profile`/`entrypoint`, as
+    /// reported by the `VAConfigAttribEncSliceStructure` attribute.
+    pub fn query_enc_slice_structure(
+        &self,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<EncSliceStructure, VaError> {
+        let mut attrs = [bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribEncSliceStructure,
+            value: 0,
+        }];
+
+        self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+        if attrs[0].value == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return Ok(EncSliceStructure::empty());
+        }
+
+        Ok(EncSliceStructure::from_bits_truncate(attrs[0].value))
+    }
+
+    /// Returns the ROI encoding capabilities of `profile` and `entrypoint`, aka the
+    /// `VAConfigAttribEncROI` attribute.
+    pub fn query_enc_roi(
+        &self,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<EncRoiCapabilities, VaError> {
+        let mut attrs = [bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribEncROI,
+            value: 0,
+        }];
+
+        self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+        Ok(EncRoiCapabilities::from_attribute_value(attrs[0].value))
+    }
+
+    /// Returns the number of quality levels supported by the encoder for `profile`/`entrypoint`,
+    /// as reported by the `VAConfigAttribEncQualityRange` attribute, or 0 if the encoder does not
+    /// support quality levels.
+    pub fn query_enc_quality_range(
+        &self,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<u32, VaError> {
+        let mut attrs = [bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribEncQualityRange,
+            value: 0,
+        }];
+
+        self.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+        if attrs[0].value == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return Ok(0);
+        }
+
+        Ok(attrs[0].value)
+    }
+
+    /// Returns the display attributes supported by the driver, along with their range and current
+    /// value. Wrapper over `vaQueryDisplayAttributes`.
+    pub fn query_display_attributes(&self) -> Result<Vec<DisplayAttributeInfo>, VaError> {
+        // Safe because `self` represents a valid VADisplay.
+        let max_num_attributes = unsafe { bindings::vaMaxNumDisplayAttributes(self.handle) };
+        let mut attributes: Vec<bindings::VADisplayAttribute> =
+            vec![Default::default(); max_num_attributes as usize];
+        let mut num_attributes = 0;
+
+        // Safe because `self` represents a valid VADisplay and `attributes` has room for
+        // `max_num_attributes` elements.
+        va_check(unsafe {
+            bindings::vaQueryDisplayAttributes(
+                self.handle,
+                attributes.as_mut_ptr(),
+                &mut num_attributes,
+            )
+        })?;
+
+        attributes.truncate(num_attributes as usize);
+
+        Ok(attributes.iter().map(DisplayAttributeInfo::from).collect())
+    }
+
+    /// Returns the current value of `attribute`. Wrapper over `vaGetDisplayAttributes`.
+    pub fn get_display_attribute(&self, attribute: DisplayAttribute) -> Result<i32, VaError> {
+        let mut attr = bindings::VADisplayAttribute {
+            type_: attribute.attrib_type(),
+            ..Default::default()
+        };
+
+        // Safe because `self` represents a valid VADisplay and we pass a single attribute.
+        va_check(unsafe { bindings::vaGetDisplayAttributes(self.handle, &mut attr, 1) })?;
+
+        Ok(attr.value)
+    }
+
+    /// Sets the value of `attribute` to `value`. Wrapper over `vaSetDisplayAttributes`.
+    pub fn set_display_attribute(
+        &self,
+        attribute: DisplayAttribute,
+        value: i32,
+    ) -> Result<(), VaError> {
+        let mut attr = bindings::VADisplayAttribute {
+            type_: attribute.attrib_type(),
+            value,
+            flags: bindings::VA_DISPLAY_ATTRIB_SETTABLE,
+            ..Default::default()
+        };
+
+        // Safe because `self` represents a valid VADisplay and we pass a single attribute.
+        va_check(unsafe { bindings::vaSetDisplayAttributes(self.handle, &mut attr, 1) })
+    }
+
     /// Creates `Surface`s by wrapping around a `vaCreateSurfaces` call.
     ///
     /// The number of surfaces created will be equal to the length of `descriptors`.
@@ -282,6 +942,69 @@ impl Display {
         )
     }
 
+    /// Same as [`Display::create_surfaces`], but if not all the surfaces can be created, e.g.
+    /// because the driver ran out of memory, the error contains the surfaces that have been
+    /// successfully created. The caller can then decide to go on with fewer surfaces, or to drop
+    /// them to release their memory.
+    pub fn create_surfaces_partial<D: SurfaceMemoryDescriptor>(
+        self: &Rc<Self>,
+        rt_format: u32,
+        va_fourcc: Option<u32>,
+        width: u32,
+        height: u32,
+        usage_hint: Option<UsageHint>,
+        descriptors: Vec<D>,
+    ) -> Result<Vec<Surface<D>>, CreateSurfacesError<D>> {
+        Surface::new_partial(
+            Rc::clone(self),
+            rt_format,
+            va_fourcc,
+            width,
+            height,
+            usage_hint,
+            descriptors,
+        )
+    }
+
+    /// Creates a surface sharing the memory of the surface `handle` has been created from,
+    /// possibly on another `Display` and thread.
+    pub fn attach_surface(
+        self: &Rc<Self>,
+        handle: SurfaceHandle,
+    ) -> Result<Surface<DrmPrimeSurfaceDescriptor>, AttachSurfaceError> {
+        let desc = handle.into_prime_descriptor();
+        let fourcc = desc.fourcc;
+        let rt_format =
+            rt_format_for_fourcc(fourcc).ok_or(SurfaceFormatError::UnknownFourcc(fourcc))?;
+        let (width, height) = (desc.width, desc.height);
+
+        let mut surfaces =
+            self.create_surfaces(rt_format, Some(fourcc), width, height, None, vec![desc])?;
+
+        // `create_surfaces` returns as many surfaces as descriptors on success.
+        Ok(surfaces.pop().unwrap())
+    }
+
+    /// Creates `width`x`height` surfaces of the high bit depth `format`, e.g. to decode HEVC
+    /// Main10 or AV1 10-bit streams into. See [`Display::create_surfaces`].
+    pub fn create_high_bit_depth_surfaces<D: SurfaceMemoryDescriptor>(
+        self: &Rc<Self>,
+        format: HighBitDepthFormat,
+        width: u32,
+        height: u32,
+        usage_hint: Option<UsageHint>,
+        descriptors: Vec<D>,
+    ) -> Result<Vec<Surface<D>>, VaError> {
+        self.create_surfaces(
+            format.rt_format(),
+            Some(format.fourcc()),
+            width,
+            height,
+            usage_hint,
+            descriptors,
+        )
+    }
+
     /// Creates a `Context` by wrapping around a `vaCreateContext` call.
     ///
     /// # Arguments
@@ -309,6 +1032,16 @@ impl Display {
         )
     }
 
+    /// Creates a [`ProtectedSession`] from `config`, a config created for
+    /// `VAEntrypointProtectedContent`.
+    #[cfg(libva_1_11_or_higher)]
+    pub fn create_protected_session(
+        self: &Rc<Self>,
+        config: &Config,
+    ) -> Result<ProtectedSession, VaError> {
+        ProtectedSession::new(Rc::clone(self), config)
+    }
+
     /// Creates a `Config` by wrapping around the `vaCreateConfig` call.
     ///
     /// `attrs` describe the attributes to set for this config. A list of the supported attributes
@@ -349,6 +1082,41 @@ impl Display {
 
         Ok(image_formats)
     }
+
+    /// Returns the image formats that can be used as subpictures on this display, along with the
+    /// [`SubpictureFlags`] supported for each of them. Wrapper around `vaQuerySubpictureFormats`.
+    pub fn query_subpicture_formats(
+        &self,
+    ) -> Result<Vec<(bindings::VAImageFormat, SubpictureFlags)>, VaError> {
+        // Safe because `self` represents a valid VADisplay.
+        let mut num_formats = unsafe { bindings::vaMaxNumSubpictureFormats(self.handle) } as u32;
+        let mut formats = Vec::with_capacity(num_formats as usize);
+        let mut flags = Vec::<u32>::with_capacity(num_formats as usize);
+
+        // Safe because `self` represents a valid VADisplay. Both vectors have a capacity of
+        // `num_formats`, which is the maximum number of entries the C function can write.
+        va_check(unsafe {
+            bindings::vaQuerySubpictureFormats(
+                self.handle,
+                formats.as_mut_ptr(),
+                flags.as_mut_ptr(),
+                &mut num_formats,
+            )
+        })?;
+
+        // Safe because the C function will have written exactly `num_formats` entries to both
+        // vectors, which is known to be within their capacity.
+        unsafe {
+            formats.set_len(num_formats as usize);
+            flags.set_len(num_formats as usize);
+        }
+
+        Ok(formats
+            .into_iter()
+            .zip(flags)
+            .map(|(format, flags)| (format, SubpictureFlags::from_bits_truncate(flags)))
+            .collect())
+    }
 }
 
 impl Drop for Display {
@@ -356,7 +1124,7 @@ impl Drop for Display {
         // Safe because `self` represents a valid VADisplay.
         unsafe {
             bindings::vaTerminate(self.handle);
-            // The File will close the DRM fd on drop.
+            // The backend will close the DRM fd or X11 connection on drop.
         }
     }
 }
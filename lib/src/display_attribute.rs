@@ -0,0 +1,120 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use crate::bindings;
+
+/// Display attributes that can be queried with [`crate::Display::query_display_attributes`],
+/// aka `VADisplayAttribType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayAttribute {
+    Brightness,
+    Contrast,
+    Hue,
+    Saturation,
+    /// Background color, as `0xRRGGBB`.
+    BackgroundColor,
+    /// Rotation of the rendering, one of `VA_ROTATION_*`.
+    Rotation,
+    /// Whether out-of-loop deblocking is applied.
+    OutOfLoopDeblock,
+    /// Rendering mode, a combination of `VA_RENDER_MODE_*`.
+    RenderMode,
+    /// Rendering device, a combination of `VA_RENDER_DEVICE_*`.
+    RenderDevice,
+    /// Copy mode, a combination of `VA_DISPLAY_ATTRIB_COPY_*`.
+    Copy,
+    /// PCI ID of the device, as `(vendor_id << 16) | device_id`.
+    PciId,
+    /// Any other attribute not covered by the variants above.
+    Other(bindings::VADisplayAttribType::Type),
+}
+
+impl DisplayAttribute {
+    /// Returns the `VADisplayAttribType` of this attribute.
+    pub fn attrib_type(&self) -> bindings::VADisplayAttribType::Type {
+        match self {
+            DisplayAttribute::Brightness => {
+                bindings::VADisplayAttribType::VADisplayAttribBrightness
+            }
+            DisplayAttribute::Contrast => bindings::VADisplayAttribType::VADisplayAttribContrast,
+            DisplayAttribute::Hue => bindings::VADisplayAttribType::VADisplayAttribHue,
+            DisplayAttribute::Saturation => {
+                bindings::VADisplayAttribType::VADisplayAttribSaturation
+            }
+            DisplayAttribute::BackgroundColor => {
+                bindings::VADisplayAttribType::VADisplayAttribBackgroundColor
+            }
+            DisplayAttribute::Rotation => bindings::VADisplayAttribType::VADisplayAttribRotation,
+            DisplayAttribute::OutOfLoopDeblock => {
+                bindings::VADisplayAttribType::VADisplayAttribOutofLoopDeblock
+            }
+            DisplayAttribute::RenderMode => {
+                bindings::VADisplayAttribType::VADisplayAttribRenderMode
+            }
+            DisplayAttribute::RenderDevice => {
+                bindings::VADisplayAttribType::VADisplayAttribRenderDevice
+            }
+            DisplayAttribute::Copy => bindings::VADisplayAttribType::VADisplayAttribCopy,
+            DisplayAttribute::PciId => bindings::VADisplayAttribType::VADisplayPCIID,
+            DisplayAttribute::Other(type_) => *type_,
+        }
+    }
+
+    /// Returns the attribute corresponding to `type_`.
+    pub fn from_attrib_type(type_: bindings::VADisplayAttribType::Type) -> Self {
+        match type_ {
+            bindings::VADisplayAttribType::VADisplayAttribBrightness => {
+                DisplayAttribute::Brightness
+            }
+            bindings::VADisplayAttribType::VADisplayAttribContrast => DisplayAttribute::Contrast,
+            bindings::VADisplayAttribType::VADisplayAttribHue => DisplayAttribute::Hue,
+            bindings::VADisplayAttribType::VADisplayAttribSaturation => {
+                DisplayAttribute::Saturation
+            }
+            bindings::VADisplayAttribType::VADisplayAttribBackgroundColor => {
+                DisplayAttribute::BackgroundColor
+            }
+            bindings::VADisplayAttribType::VADisplayAttribRotation => DisplayAttribute::Rotation,
+            bindings::VADisplayAttribType::VADisplayAttribOutofLoopDeblock => {
+                DisplayAttribute::OutOfLoopDeblock
+            }
+            bindings::VADisplayAttribType::VADisplayAttribRenderMode => {
+                DisplayAttribute::RenderMode
+            }
+            bindings::VADisplayAttribType::VADisplayAttribRenderDevice => {
+                DisplayAttribute::RenderDevice
+            }
+            bindings::VADisplayAttribType::VADisplayAttribCopy => DisplayAttribute::Copy,
+            bindings::VADisplayAttribType::VADisplayPCIID => DisplayAttribute::PciId,
+            type_ => DisplayAttribute::Other(type_),
+        }
+    }
+}
+
+/// Description of a display attribute supported by the driver, aka `VADisplayAttribute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayAttributeInfo {
+    pub attribute: DisplayAttribute,
+    pub min_value: i32,
+    pub max_value: i32,
+    /// Current value of the attribute.
+    pub value: i32,
+    /// Whether the attribute can be read with [`crate::Display::get_display_attribute`].
+    pub gettable: bool,
+    /// Whether the attribute can be written with [`crate::Display::set_display_attribute`].
+    pub settable: bool,
+}
+
+impl From<&bindings::VADisplayAttribute> for DisplayAttributeInfo {
+    fn from(attr: &bindings::VADisplayAttribute) -> Self {
+        Self {
+            attribute: DisplayAttribute::from_attrib_type(attr.type_),
+            min_value: attr.min_value,
+            max_value: attr.max_value,
+            value: attr.value,
+            gettable: attr.flags & bindings::VA_DISPLAY_ATTRIB_GETTABLE != 0,
+            settable: attr.flags & bindings::VA_DISPLAY_ATTRIB_SETTABLE != 0,
+        }
+    }
+}
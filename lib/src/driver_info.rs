@@ -0,0 +1,158 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Identification of the driver behind a display, and of the known quirks it requires.
+
+use bitflags::bitflags;
+
+/// Family of a VA-API driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriverFamily {
+    /// Intel Media Driver, for Broadwell and newer Intel GPUs.
+    IntelIhd,
+    /// Legacy Intel driver.
+    IntelI965,
+    /// Mesa Gallium driver, e.g. for AMD GPUs.
+    MesaGallium,
+    /// Any other driver.
+    Unknown,
+}
+
+/// Version of a driver, as reported in its vendor string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DriverVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+bitflags! {
+    /// Known deviations of a driver from the expected VA-API behavior, that callers may need to
+    /// work around.
+    ///
+    /// [`DriverInfo::quirks`] only reports the quirks that are known to apply to a whole driver
+    /// family. Others depend on the hardware or driver configuration, and can be set by callers
+    /// from their own knowledge of the platform.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct DriverQuirks: u32 {
+        /// `vaSyncBuffer` is not implemented, `vaSyncSurface` must be used to wait for coded
+        /// buffers instead.
+        const NO_SYNC_BUFFER = 1 << 0;
+        /// `vaSyncSurface2` is not implemented, so surfaces cannot be waited on with a timeout.
+        const NO_SYNC_SURFACE_TIMEOUT = 1 << 1;
+        /// `vaDeriveImage` cannot be relied upon for P010 surfaces, which must be read with
+        /// `vaGetImage` instead.
+        const BROKEN_P010_DERIVE = 1 << 2;
+        /// Surfaces are allocated as two separately aligned fields, so their coded height is a
+        /// multiple of 32 rather than of the macroblock height.
+        const FIELD_ALIGNED_SURFACES = 1 << 3;
+    }
+}
+
+/// Driver information parsed from the vendor string of a display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverInfo {
+    /// Vendor string as returned by `vaQueryVendorString`.
+    pub vendor: String,
+    pub family: DriverFamily,
+    /// Version of the driver, if it could be parsed.
+    pub version: Option<DriverVersion>,
+}
+
+impl DriverInfo {
+    /// Parses the driver information from `vendor`, as returned by `vaQueryVendorString`.
+    pub fn from_vendor_string(vendor: &str) -> Self {
+        let family = if vendor.contains("iHD") {
+            DriverFamily::IntelIhd
+        } else if vendor.contains("i965") {
+            DriverFamily::IntelI965
+        } else if vendor.contains("Mesa Gallium") {
+            DriverFamily::MesaGallium
+        } else {
+            DriverFamily::Unknown
+        };
+
+        Self {
+            vendor: vendor.to_string(),
+            family,
+            version: vendor.split_whitespace().find_map(parse_version),
+        }
+    }
+
+    /// Returns the quirks known to be required by this driver.
+    pub fn quirks(&self) -> DriverQuirks {
+        match self.family {
+            // The last release of the i965 driver (2.4.1) predates VA-API 1.9, which introduced
+            // `vaSyncBuffer` and `vaSyncSurface2`, so no version of it implements them.
+            DriverFamily::IntelI965 => {
+                DriverQuirks::NO_SYNC_BUFFER | DriverQuirks::NO_SYNC_SURFACE_TIMEOUT
+            }
+            DriverFamily::IntelIhd | DriverFamily::MesaGallium | DriverFamily::Unknown => {
+                DriverQuirks::empty()
+            }
+        }
+    }
+}
+
+/// Parses a version such as `22.2.2` or `23.1.0-devel` from `token`.
+fn parse_version(token: &str) -> Option<DriverVersion> {
+    let version = token
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    let mut components = version.split('.').map(|c| c.parse::<u32>());
+
+    let major = components.next()?.ok()?;
+    let minor = components.next()?.ok()?;
+    let patch = components.next().and_then(Result::ok).unwrap_or(0);
+
+    Some(DriverVersion {
+        major,
+        minor,
+        patch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vendor_strings() {
+        let ihd = DriverInfo::from_vendor_string(
+            "Intel iHD driver for Intel(R) Gen Graphics - 22.2.2 ()",
+        );
+        assert_eq!(ihd.family, DriverFamily::IntelIhd);
+        assert_eq!(
+            ihd.version,
+            Some(DriverVersion {
+                major: 22,
+                minor: 2,
+                patch: 2
+            })
+        );
+        assert!(ihd.quirks().is_empty());
+
+        let gallium = DriverInfo::from_vendor_string(
+            "Mesa Gallium driver 23.1.0-devel for AMD Radeon RX 6600 (navi23, LLVM 15.0.7)",
+        );
+        assert_eq!(gallium.family, DriverFamily::MesaGallium);
+        assert_eq!(
+            gallium.version,
+            Some(DriverVersion {
+                major: 23,
+                minor: 1,
+                patch: 0
+            })
+        );
+
+        assert!(gallium.quirks().is_empty());
+
+        let i965 = DriverInfo::from_vendor_string("Intel i965 driver for Intel(R) Haswell - 2.4.1");
+        assert!(i965.quirks().contains(DriverQuirks::NO_SYNC_BUFFER));
+
+        let unknown = DriverInfo::from_vendor_string("Some driver");
+        assert_eq!(unknown.family, DriverFamily::Unknown);
+        assert_eq!(unknown.version, None);
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Vendor-agnostic speed/quality presets for encoders.
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::Display;
+use crate::EncMiscParameter;
+use crate::EncMiscParameterBufferQualityLevel;
+use crate::EncMiscParameterMaxSliceSize;
+use crate::EncMiscParameterQuantization;
+use crate::EncMiscParameterRIR;
+use crate::IntraRefreshDirection;
+use crate::MaxSliceSizeError;
+use crate::VaError;
+
+/// Speed/quality trade-off of an encoder.
+///
+/// Drivers expose their trade-offs through a range of quality levels (reported by
+/// `VAConfigAttribEncQualityRange`, with 1 being the best quality and the maximum value the
+/// fastest), trellis quantization and the choice between the regular and low-power entrypoints.
+/// This maps a simple preset onto these knobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodePreset {
+    /// Favor encoding speed and latency, e.g. for video calls.
+    Realtime,
+    /// Middle ground between speed and quality.
+    #[default]
+    Balanced,
+    /// Favor quality, e.g. for offline transcoding.
+    Quality,
+}
+
+impl EncodePreset {
+    /// Returns the entrypoint to use among the `available` ones for an encode profile, or `None`
+    /// if none of them is an encode entrypoint.
+    ///
+    /// The low-power entrypoint is preferred for realtime encoding, the regular one otherwise.
+    pub fn select_entrypoint(
+        &self,
+        available: &[bindings::VAEntrypoint::Type],
+    ) -> Option<bindings::VAEntrypoint::Type> {
+        let preference = match self {
+            Self::Realtime => [
+                bindings::VAEntrypoint::VAEntrypointEncSliceLP,
+                bindings::VAEntrypoint::VAEntrypointEncSlice,
+            ],
+            Self::Balanced | Self::Quality => [
+                bindings::VAEntrypoint::VAEntrypointEncSlice,
+                bindings::VAEntrypoint::VAEntrypointEncSliceLP,
+            ],
+        };
+
+        preference
+            .into_iter()
+            .find(|entrypoint| available.contains(entrypoint))
+    }
+
+    /// Returns the quality level to use given the `quality_range` reported by the
+    /// `VAConfigAttribEncQualityRange` attribute, or `None` if the driver does not support quality
+    /// levels.
+    pub fn quality_level(&self, quality_range: u32) -> Option<u32> {
+        if quality_range == 0 || quality_range == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return None;
+        }
+
+        Some(match self {
+            Self::Realtime => quality_range,
+            Self::Balanced => quality_range.div_ceil(2),
+            Self::Quality => 1,
+        })
+    }
+
+    /// Returns the trellis quantization settings of this preset.
+    pub fn quantization(&self) -> EncMiscParameterQuantization {
+        match self {
+            Self::Realtime => EncMiscParameterQuantization::new(true, false, false, false),
+            Self::Balanced => EncMiscParameterQuantization::new(false, true, false, false),
+            Self::Quality => EncMiscParameterQuantization::new(false, true, true, true),
+        }
+    }
+
+    /// Returns the misc parameters to submit with the sequence parameters to apply this preset.
+    ///
+    /// `quality_range` is the value of the `VAConfigAttribEncQualityRange` attribute.
+    pub fn misc_parameters(&self, quality_range: u32) -> Vec<EncMiscParameter> {
+        let mut params = vec![];
+
+        if let Some(quality_level) = self.quality_level(quality_range) {
+            params.push(EncMiscParameter::QualityLevel(
+                EncMiscParameterBufferQualityLevel::new(quality_level),
+            ));
+        }
+        params.push(EncMiscParameter::Quantization(self.quantization()));
+
+        params
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LowDelayConfigError {
+    #[error("error while querying the config attributes: {0}")]
+    VaError(#[from] VaError),
+    #[error("the intra period must be non-zero")]
+    InvalidIntraPeriod,
+    #[error("the driver does not support rolling intra refresh")]
+    IntraRefreshNotSupported,
+    #[error("invalid maximum slice size: {0}")]
+    MaxSliceSize(#[from] MaxSliceSizeError),
+}
+
+/// Configuration of a low-delay encoder, e.g. for cloud gaming or remote desktop.
+///
+/// Low delay encoding implies that:
+///
+/// * No B-frames are used (`ip_period` is 1), so frames are coded in presentation order.
+/// * No lookahead is done: each frame is submitted as soon as it is available.
+/// * Intra refresh can be used instead of periodic IDR frames to avoid bitrate spikes.
+/// * Slices can be capped in size so that each one fits into a single network packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowDelayConfig {
+    /// Distance between two I-frames, in frames.
+    pub intra_period: u32,
+    /// Whether to spread intra coding over several frames with rolling intra refresh.
+    pub intra_refresh: bool,
+    /// Maximum size of a slice, in bytes.
+    pub max_slice_size: Option<u32>,
+}
+
+impl LowDelayConfig {
+    /// Returns the distance between two anchor frames, which is always 1 as no B-frames are used.
+    pub fn ip_period(&self) -> u32 {
+        1
+    }
+
+    /// Checks that this configuration is valid and supported by the driver for
+    /// `profile`/`entrypoint`.
+    pub fn validate(
+        &self,
+        display: &Display,
+        profile: bindings::VAProfile::Type,
+        entrypoint: bindings::VAEntrypoint::Type,
+    ) -> Result<(), LowDelayConfigError> {
+        if self.intra_period == 0 {
+            return Err(LowDelayConfigError::InvalidIntraPeriod);
+        }
+
+        if self.intra_refresh {
+            let mut attrs = [bindings::VAConfigAttrib {
+                type_: bindings::VAConfigAttribType::VAConfigAttribEncIntraRefresh,
+                value: 0,
+            }];
+            display.get_config_attributes(profile, entrypoint, &mut attrs)?;
+
+            let rolling = bindings::VA_ENC_INTRA_REFRESH_ROLLING_COLUMN
+                | bindings::VA_ENC_INTRA_REFRESH_ROLLING_ROW;
+            if attrs[0].value == bindings::VA_ATTRIB_NOT_SUPPORTED || attrs[0].value & rolling == 0
+            {
+                return Err(LowDelayConfigError::IntraRefreshNotSupported);
+            }
+        }
+
+        if let Some(max_slice_size) = self.max_slice_size {
+            let slice_structure = display.query_enc_slice_structure(profile, entrypoint)?;
+            EncMiscParameterMaxSliceSize::new_checked(max_slice_size, slice_structure)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the misc parameters to submit with frame `frame_num` to apply this configuration,
+    /// on top of those of [`EncodePreset::Realtime`].
+    ///
+    /// `quality_range` is the value of the `VAConfigAttribEncQualityRange` attribute, and
+    /// `intra_refresh` that of the `VAConfigAttribEncIntraRefresh` attribute. The frame is
+    /// `width_in_blocks`x`height_in_blocks` macroblocks or CTUs.
+    ///
+    /// If [`Self::intra_refresh`] is set, an [`EncMiscParameterRIR`] moving the intra-coded area
+    /// across the frame is included, so that the whole frame is refreshed every
+    /// [`Self::intra_period`] frames. Rolling columns are used if supported, rows otherwise.
+    pub fn misc_parameters(
+        &self,
+        quality_range: u32,
+        intra_refresh: u32,
+        frame_num: u32,
+        width_in_blocks: u16,
+        height_in_blocks: u16,
+    ) -> Vec<EncMiscParameter> {
+        let mut params = EncodePreset::Realtime.misc_parameters(quality_range);
+
+        if let Some(max_slice_size) = self.max_slice_size {
+            params.push(EncMiscParameter::MaxSliceSize(
+                EncMiscParameterMaxSliceSize::new(max_slice_size),
+            ));
+        }
+
+        if self.intra_refresh && self.intra_period > 0 {
+            let refresh = [
+                (IntraRefreshDirection::Column, width_in_blocks),
+                (IntraRefreshDirection::Row, height_in_blocks),
+            ]
+            .into_iter()
+            .find(|(direction, blocks)| direction.is_supported(intra_refresh) && *blocks > 0);
+
+            if let Some((direction, blocks)) = refresh {
+                let size = u32::from(blocks).div_ceil(self.intra_period);
+                let location = (frame_num % self.intra_period)
+                    .saturating_mul(size)
+                    .min(u32::from(blocks) - size);
+                params.push(EncMiscParameter::RIR(EncMiscParameterRIR::new(
+                    direction,
+                    location as u16,
+                    size as u16,
+                    0,
+                )));
+            }
+        }
+
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_mapping() {
+        assert_eq!(EncodePreset::Realtime.quality_level(7), Some(7));
+        assert_eq!(EncodePreset::Balanced.quality_level(7), Some(4));
+        assert_eq!(EncodePreset::Quality.quality_level(7), Some(1));
+        assert_eq!(EncodePreset::Quality.quality_level(0), None);
+
+        let available = [
+            bindings::VAEntrypoint::VAEntrypointVLD,
+            bindings::VAEntrypoint::VAEntrypointEncSlice,
+            bindings::VAEntrypoint::VAEntrypointEncSliceLP,
+        ];
+        assert_eq!(
+            EncodePreset::Realtime.select_entrypoint(&available),
+            Some(bindings::VAEntrypoint::VAEntrypointEncSliceLP)
+        );
+        assert_eq!(
+            EncodePreset::Quality.select_entrypoint(&available),
+            Some(bindings::VAEntrypoint::VAEntrypointEncSlice)
+        );
+        assert_eq!(
+            EncodePreset::Realtime.select_entrypoint(&available[..2]),
+            Some(bindings::VAEntrypoint::VAEntrypointEncSlice)
+        );
+        assert_eq!(
+            EncodePreset::Balanced.select_entrypoint(&available[..1]),
+            None
+        );
+    }
+
+    #[test]
+    fn low_delay_intra_refresh() {
+        let config = LowDelayConfig {
+            intra_period: 4,
+            intra_refresh: true,
+            max_slice_size: None,
+        };
+        let rows = bindings::VA_ENC_INTRA_REFRESH_ROLLING_ROW;
+
+        let locations: Vec<_> = (0..5)
+            .map(|frame_num| {
+                let params = config.misc_parameters(0, rows, frame_num, 120, 67);
+                match params.last() {
+                    Some(EncMiscParameter::RIR(rir)) => {
+                        assert_eq!(rir.inner().value.intra_insert_size, 17);
+                        rir.inner().value.intra_insertion_location
+                    }
+                    _ => panic!("missing RIR parameter"),
+                }
+            })
+            .collect();
+        // The last band is moved up so it stays within the 67 rows of the frame.
+        assert_eq!(locations, vec![0, 17, 34, 50, 0]);
+
+        let params = config.misc_parameters(0, bindings::VA_ATTRIB_NOT_SUPPORTED, 0, 120, 67);
+        assert!(!params
+            .iter()
+            .any(|param| matches!(param, EncMiscParameter::RIR(_))));
+
+        let config = LowDelayConfig {
+            intra_refresh: false,
+            ..config
+        };
+        let params = config.misc_parameters(0, rows, 0, 120, 67);
+        assert!(!params
+            .iter()
+            .any(|param| matches!(param, EncMiscParameter::RIR(_))));
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Import of GBM buffer objects as the memory backing of surfaces.
+
+use std::any::Any;
+use std::fs::File;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::DrmPrimeSurfaceDescriptor;
+use crate::DrmPrimeSurfaceDescriptorLayer;
+use crate::DrmPrimeSurfaceDescriptorObject;
+use crate::SurfaceMemoryDescriptor;
+
+/// Opaque `struct gbm_bo` from libgbm.
+#[repr(C)]
+pub struct GbmBo {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn gbm_bo_get_fd_for_plane(bo: *mut GbmBo, plane: std::ffi::c_int) -> std::ffi::c_int;
+    fn gbm_bo_get_width(bo: *mut GbmBo) -> u32;
+    fn gbm_bo_get_height(bo: *mut GbmBo) -> u32;
+    fn gbm_bo_get_format(bo: *mut GbmBo) -> u32;
+    fn gbm_bo_get_modifier(bo: *mut GbmBo) -> u64;
+    fn gbm_bo_get_plane_count(bo: *mut GbmBo) -> std::ffi::c_int;
+    fn gbm_bo_get_stride_for_plane(bo: *mut GbmBo, plane: std::ffi::c_int) -> u32;
+    fn gbm_bo_get_offset(bo: *mut GbmBo, plane: std::ffi::c_int) -> u32;
+}
+
+#[derive(Debug, Error)]
+pub enum GbmBoDescriptorError {
+    #[error("gbm_bo_get_fd_for_plane failed for plane {0}")]
+    ExportFd(i32),
+    #[error("between 1 and 4 planes are required, got {0}")]
+    InvalidPlaneCount(i32),
+    #[error("failed to get the size of the buffer object: {0}")]
+    Size(std::io::Error),
+    #[error("buffer object of {0} bytes is too large")]
+    TooLarge(u64),
+}
+
+/// A GBM buffer object to use as the memory backing of a surface.
+///
+/// Each plane of the buffer object is exported as a DMA-BUF when the descriptor is created, so the
+/// buffer object does not need to outlive the descriptor: the memory is kept alive by the DMA-BUFs
+/// until the surface is dropped. Exporting planes separately supports buffer objects whose planes
+/// are disjoint allocations.
+pub struct GbmBoDescriptor(DrmPrimeSurfaceDescriptor);
+
+impl GbmBoDescriptor {
+    /// Creates a descriptor importing `bo`, to be interpreted with format `fourcc`
+    /// (`VA_FOURCC_*`).
+    ///
+    /// # Safety
+    ///
+    /// `bo` must be a valid pointer to a GBM buffer object.
+    pub unsafe fn new(bo: *mut GbmBo, fourcc: u32) -> Result<Self, GbmBoDescriptorError> {
+        let num_planes = gbm_bo_get_plane_count(bo);
+        if !(1..=4).contains(&num_planes) {
+            return Err(GbmBoDescriptorError::InvalidPlaneCount(num_planes));
+        }
+
+        let drm_format_modifier = gbm_bo_get_modifier(bo);
+        let mut objects = Vec::with_capacity(num_planes as usize);
+        let mut object_index = [0u8; 4];
+        let mut offset = [0u32; 4];
+        let mut pitch = [0u32; 4];
+        for plane in 0..num_planes {
+            let fd = gbm_bo_get_fd_for_plane(bo, plane);
+            if fd < 0 {
+                return Err(GbmBoDescriptorError::ExportFd(plane));
+            }
+            // Safe because `gbm_bo_get_fd_for_plane` returns a new file descriptor that we own.
+            let mut file = File::from(OwnedFd::from_raw_fd(fd));
+            let size = file
+                .seek(SeekFrom::End(0))
+                .map_err(GbmBoDescriptorError::Size)?;
+
+            objects.push(DrmPrimeSurfaceDescriptorObject {
+                fd: OwnedFd::from(file),
+                size: u32::try_from(size).map_err(|_| GbmBoDescriptorError::TooLarge(size))?,
+                drm_format_modifier,
+            });
+            object_index[plane as usize] = plane as u8;
+            offset[plane as usize] = gbm_bo_get_offset(bo, plane);
+            pitch[plane as usize] = gbm_bo_get_stride_for_plane(bo, plane);
+        }
+
+        Ok(Self(DrmPrimeSurfaceDescriptor {
+            fourcc,
+            width: gbm_bo_get_width(bo),
+            height: gbm_bo_get_height(bo),
+            objects,
+            layers: vec![DrmPrimeSurfaceDescriptorLayer {
+                drm_format: gbm_bo_get_format(bo),
+                num_planes: num_planes as u32,
+                object_index,
+                offset,
+                pitch,
+            }],
+        }))
+    }
+
+    /// Returns the PRIME descriptor of the exported buffer object.
+    pub fn prime_descriptor(&self) -> &DrmPrimeSurfaceDescriptor {
+        &self.0
+    }
+}
+
+impl SurfaceMemoryDescriptor for GbmBoDescriptor {
+    fn add_attrs(&mut self, attrs: &mut Vec<bindings::VASurfaceAttrib>) -> Option<Box<dyn Any>> {
+        self.0.add_attrs(attrs)
+    }
+}
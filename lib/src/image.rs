@@ -4,13 +4,68 @@
 
 use std::rc::Rc;
 
+use thiserror::Error;
+
 use crate::bindings;
+use crate::buffer::map_buffer;
 use crate::va_check;
 use crate::Display;
+use crate::MapFlags;
+use crate::PlaneLayout;
 use crate::Surface;
 use crate::SurfaceMemoryDescriptor;
 use crate::VaError;
 
+#[derive(Debug, Error)]
+pub enum ReadSurfaceError {
+    #[error("error while reading surface: {0}")]
+    VaError(#[from] VaError),
+    #[error("the image has {image} planes, but {requested} were requested")]
+    PlaneCountMismatch { image: usize, requested: usize },
+    #[error("plane {0} does not fit in the destination buffer")]
+    DestinationTooSmall(usize),
+    #[error("plane {0} is larger than the image")]
+    SourceTooSmall(usize),
+}
+
+/// Copies the planes of `image`, whose data is `src`, into `dst` following the layout of
+/// `dst_planes`. Each line is copied up to the smallest of the source and destination pitches.
+pub(crate) fn copy_image_planes(
+    image: &bindings::VAImage,
+    src: &[u8],
+    dst: &mut [u8],
+    dst_planes: &[PlaneLayout],
+) -> Result<(), ReadSurfaceError> {
+    let num_planes = (image.num_planes as usize).min(image.pitches.len());
+    if dst_planes.len() > num_planes {
+        return Err(ReadSurfaceError::PlaneCountMismatch {
+            image: num_planes,
+            requested: dst_planes.len(),
+        });
+    }
+
+    for (i, dst_plane) in dst_planes.iter().enumerate() {
+        let src_pitch = image.pitches[i] as usize;
+        let src_offset = image.offsets[i] as usize;
+        let dst_pitch = dst_plane.pitch as usize;
+        let dst_offset = dst_plane.offset as usize;
+        let line_len = src_pitch.min(dst_pitch);
+
+        for line in 0..dst_plane.height as usize {
+            let src_start = src_offset + line * src_pitch;
+            let dst_start = dst_offset + line * dst_pitch;
+            let src_line = src
+                .get(src_start..src_start + line_len)
+                .ok_or(ReadSurfaceError::SourceTooSmall(i))?;
+            dst.get_mut(dst_start..dst_start + line_len)
+                .ok_or(ReadSurfaceError::DestinationTooSmall(i))?
+                .copy_from_slice(src_line);
+        }
+    }
+
+    Ok(())
+}
+
 /// Wrapper around `VAImage` that is tied to the lifetime of a given `Picture`.
 ///
 /// An image is used to either get the surface data to client memory, or to copy image data in
@@ -40,7 +95,8 @@ pub struct Image<'a> {
 }
 
 impl<'a> Image<'a> {
-    /// Helper method to map a `VAImage` using `vaMapBuffer` and return an `Image`.
+    /// Helper method to map a `VAImage` for the accesses described by `flags` and return an
+    /// `Image`.
     ///
     /// Returns an error if the mapping failed.
     fn new<D: SurfaceMemoryDescriptor>(
@@ -48,22 +104,19 @@ impl<'a> Image<'a> {
         image: bindings::VAImage,
         derived: bool,
         display_resolution: (u32, u32),
+        flags: MapFlags,
     ) -> Result<Self, VaError> {
-        let mut addr = std::ptr::null_mut();
-
-        // Safe since `picture.inner.context` represents a valid `VAContext` and `image` has been
-        // successfully created at this point.
-        match va_check(unsafe {
-            bindings::vaMapBuffer(surface.display().handle(), image.buf, &mut addr)
-        }) {
-            Ok(_) => {
+        // `surface` represents a valid display and `image` has been successfully created at this
+        // point.
+        match map_buffer(surface.display().handle(), image.buf, flags) {
+            Ok(addr) => {
                 // Assert that libva provided us with a coded resolution that is
                 // at least as large as `display_resolution`.
                 assert!(u32::from(image.width) >= display_resolution.0);
                 assert!(u32::from(image.height) >= display_resolution.1);
 
                 // Safe since `addr` points to data mapped onto our address space since we called
-                // `vaMapBuffer` above, which also guarantees that the data is valid for
+                // `map_buffer` above, which also guarantees that the data is valid for
                 // `image.data_size`.
                 let data =
                     unsafe { std::slice::from_raw_parts_mut(addr as _, image.data_size as usize) };
@@ -98,6 +151,18 @@ impl<'a> Image<'a> {
     pub fn derive_from<D: SurfaceMemoryDescriptor>(
         surface: &'a Surface<D>,
         visible_rect: (u32, u32),
+    ) -> Result<Self, VaError> {
+        Self::derive_from_with_flags(surface, visible_rect, MapFlags::empty())
+    }
+
+    /// Same as [`Image::derive_from`], but maps the image for the accesses described by `flags`.
+    ///
+    /// Passing [`MapFlags::WRITE`] when only uploading data, e.g. the input frames of an
+    /// encoder, avoids needless readbacks of write-combined memory.
+    pub fn derive_from_with_flags<D: SurfaceMemoryDescriptor>(
+        surface: &'a Surface<D>,
+        visible_rect: (u32, u32),
+        flags: MapFlags,
     ) -> Result<Self, VaError> {
         // An all-zero byte-pattern is a valid initial value for `VAImage`.
         let mut image: bindings::VAImage = Default::default();
@@ -107,7 +172,7 @@ impl<'a> Image<'a> {
             bindings::vaDeriveImage(surface.display().handle(), surface.id(), &mut image)
         })?;
 
-        Self::new(surface, image, true, visible_rect)
+        Self::new(surface, image, true, visible_rect, flags)
     }
 
     /// Create new image from `surface` using `vaCreateImage` and `vaGetImage`.
@@ -151,7 +216,7 @@ impl<'a> Image<'a> {
                 image.image_id,
             )
         }) {
-            Ok(()) => Self::new(surface, image, false, visible_rect),
+            Ok(()) => Self::new(surface, image, false, visible_rect, MapFlags::empty()),
 
             Err(e) => {
                 // Safe because `image` is a valid `VAImage`.
@@ -164,6 +229,11 @@ impl<'a> Image<'a> {
         }
     }
 
+    /// Returns the display this image has been created from.
+    pub(crate) fn display(&self) -> &Rc<Display> {
+        &self.display
+    }
+
     /// Get a reference to the underlying `VAImage` that describes this image.
     pub fn image(&self) -> &bindings::VAImage {
         &self.image
@@ -233,3 +303,48 @@ impl<'a> Drop for Image<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_planes_with_stride() {
+        let mut image = bindings::VAImage {
+            num_planes: 2,
+            ..Default::default()
+        };
+        image.pitches[0] = 4;
+        image.pitches[1] = 4;
+        image.offsets[1] = 8;
+        let src: Vec<u8> = (0..12).collect();
+        let mut dst = [0xffu8; 9];
+
+        let dst_planes = [
+            PlaneLayout {
+                offset: 0,
+                pitch: 3,
+                height: 2,
+            },
+            PlaneLayout {
+                offset: 6,
+                pitch: 3,
+                height: 1,
+            },
+        ];
+        copy_image_planes(&image, &src, &mut dst, &dst_planes).unwrap();
+        assert_eq!(dst, [0, 1, 2, 4, 5, 6, 8, 9, 10]);
+
+        assert!(matches!(
+            copy_image_planes(&image, &src, &mut dst[..8], &dst_planes),
+            Err(ReadSurfaceError::DestinationTooSmall(1))
+        ));
+        assert!(matches!(
+            copy_image_planes(&image, &src, &mut dst, &[dst_planes[0]; 3]),
+            Err(ReadSurfaceError::PlaneCountMismatch {
+                image: 2,
+                requested: 3
+            })
+        ));
+    }
+}
@@ -7,29 +7,70 @@
 //! The starting point to using this crate is to open a [`Display`], from which a [`Context`] and
 //! [`Surface`]s can be allocated and used for doing actual work.
 
+mod alignment;
 mod bindings;
 pub mod buffer;
+mod capabilities;
 mod config;
 mod context;
+mod copy;
 mod display;
+mod display_attribute;
+mod driver_info;
+mod encode_preset;
+#[cfg(feature = "gbm")]
+mod gbm;
 mod generic_value;
 mod image;
 mod picture;
+mod processing_rate;
+#[cfg(libva_1_11_or_higher)]
+mod protected_session;
+mod registry;
+mod reorder;
+mod saliency;
+mod sei;
+mod subpicture;
 mod surface;
+mod surface_format;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timestamp;
 mod usage_hint;
+mod vulkan;
 
+pub use alignment::*;
 pub use bindings::_VADRMPRIMESurfaceDescriptor__bindgen_ty_1 as VADRMPRIMESurfaceDescriptorObject;
 pub use bindings::_VADRMPRIMESurfaceDescriptor__bindgen_ty_2 as VADRMPRIMESurfaceDescriptorLayer;
 pub use bindings::*;
 pub use buffer::*;
+pub use capabilities::*;
 pub use config::*;
 pub use context::*;
+pub use copy::CopyMode;
 pub use display::*;
+pub use display_attribute::*;
+pub use driver_info::*;
+pub use encode_preset::*;
+#[cfg(feature = "gbm")]
+pub use gbm::*;
 pub use generic_value::*;
 pub use image::*;
 pub use picture::*;
+pub use processing_rate::*;
+#[cfg(libva_1_11_or_higher)]
+pub use protected_session::*;
+pub use registry::LiveObject;
+pub use registry::VaObjectKind;
+pub use reorder::*;
+pub use saliency::*;
+pub use sei::*;
+pub use subpicture::*;
 pub use surface::*;
+pub use surface_format::*;
+pub use timestamp::*;
 pub use usage_hint::*;
+pub use vulkan::*;
 
 use std::num::NonZeroI32;
 
@@ -3,16 +3,26 @@
 // found in the LICENSE file.
 
 use std::borrow::Borrow;
+use std::fmt::Write;
+use std::fs;
 use std::marker::PhantomData;
+use std::path::Path;
 use std::rc::Rc;
 
+use thiserror::Error;
+
 use crate::bindings;
 use crate::buffer::Buffer;
+use crate::buffer::BufferType;
+use crate::buffer::OperationKind;
 use crate::context::Context;
 use crate::surface::Surface;
 use crate::va_check;
 use crate::Image;
+use crate::PackedHeaders;
+use crate::PackedHeadersError;
 use crate::SurfaceMemoryDescriptor;
+use crate::SurfaceStatus;
 use crate::VaError;
 
 // Use the sealed trait pattern to make sure that new states are not created in caller code. More
@@ -75,6 +85,106 @@ pub trait PictureReclaimableSurface: PictureState + private::Sealed {}
 impl PictureReclaimableSurface for PictureNew {}
 impl PictureReclaimableSurface for PictureSync {}
 
+/// Operation performed by a [`Picture`], used to make sure at compile time that only buffers
+/// meant for that operation are submitted with it.
+pub trait PictureOperation: private::Sealed {
+    /// Kind of the operation, or `None` if any buffer can be submitted.
+    const KIND: Option<OperationKind>;
+}
+
+/// A [`PictureOperation`] restricting the buffers that can be submitted.
+pub trait TypedPictureOperation: PictureOperation {}
+
+/// Picture whose operation is not tracked, i.e. any buffer can be added to it. This is the
+/// default operation.
+pub enum AnyOperation {}
+impl PictureOperation for AnyOperation {
+    const KIND: Option<OperationKind> = None;
+}
+impl private::Sealed for AnyOperation {}
+
+/// Picture being decoded.
+pub enum DecodeOperation {}
+impl PictureOperation for DecodeOperation {
+    const KIND: Option<OperationKind> = Some(OperationKind::Decode);
+}
+impl TypedPictureOperation for DecodeOperation {}
+impl private::Sealed for DecodeOperation {}
+
+/// Picture being encoded.
+pub enum EncodeOperation {}
+impl PictureOperation for EncodeOperation {
+    const KIND: Option<OperationKind> = Some(OperationKind::Encode);
+}
+impl TypedPictureOperation for EncodeOperation {}
+impl private::Sealed for EncodeOperation {}
+
+/// Picture being produced by video processing.
+pub enum ProcOperation {}
+impl PictureOperation for ProcOperation {
+    const KIND: Option<OperationKind> = Some(OperationKind::Proc);
+}
+impl TypedPictureOperation for ProcOperation {}
+impl private::Sealed for ProcOperation {}
+
+#[derive(Debug, Error)]
+pub enum TypedBufferError {
+    #[error("buffer cannot be used for {0:?} operations")]
+    WrongOperation(OperationKind),
+    #[error("error while creating buffer: {0}")]
+    VaError(#[from] VaError),
+}
+
+/// A [`Buffer`] that has been checked to be usable for operation `O`, and can thus be added to a
+/// `Picture` performing that operation.
+pub struct TypedBuffer<O: TypedPictureOperation> {
+    buffer: Buffer,
+    phantom: PhantomData<O>,
+}
+
+impl<O: TypedPictureOperation> TypedBuffer<O> {
+    /// Creates a buffer of type `type_` for operation `O`, failing if `type_` cannot be used for
+    /// that operation.
+    pub fn new(context: &Rc<Context>, type_: BufferType) -> Result<Self, TypedBufferError> {
+        if let Some(kind) = O::KIND {
+            if !type_.supports(kind) {
+                return Err(TypedBufferError::WrongOperation(kind));
+            }
+        }
+
+        Ok(Self {
+            buffer: context.create_buffer(type_)?,
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// Field of an interlaced frame a picture contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldParity {
+    Top,
+    Bottom,
+}
+
+impl FieldParity {
+    /// Returns the other field of the frame.
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+        }
+    }
+
+    /// Returns the `VA_TOP_FIELD` or `VA_BOTTOM_FIELD` flag to use as the `filter_flags` or
+    /// `input_surface_flag` of a [`crate::ProcPipelineParameterBuffer`].
+    pub fn va_flag(self) -> u32 {
+        match self {
+            Self::Top => bindings::VA_TOP_FIELD,
+            Self::Bottom => bindings::VA_BOTTOM_FIELD,
+        }
+    }
+}
+
 /// Inner type for [`Picture`], that is, the part that exists in all states.
 struct PictureInner<T> {
     /// Timestamp of the picture.
@@ -86,6 +196,10 @@ struct PictureInner<T> {
     /// Contains the actual decoded data. Note that the surface may be shared in
     /// interlaced decoding.
     surface: Rc<T>,
+    /// ID of `surface`, kept so it can be used without knowing the type of its memory descriptor.
+    surface_id: bindings::VASurfaceID,
+    /// Field of the surface this picture contains, if it is a field picture.
+    field: Option<FieldParity>,
 }
 
 /// A `Surface` that is being rendered into.
@@ -102,9 +216,9 @@ struct PictureInner<T> {
 /// to add the generic argument of [`Surface`] to this type as well, turning it into a type with 3
 /// generics, one of which is redundant. To avoid that we leave `T` unconstrained and instead
 /// constrain the methods that require to act on it as a [`Surface`].
-pub struct Picture<S: PictureState, T> {
+pub struct Picture<S: PictureState, T, O: PictureOperation = AnyOperation> {
     inner: Box<PictureInner<T>>,
-    phantom: std::marker::PhantomData<S>,
+    phantom: std::marker::PhantomData<(S, O)>,
 }
 
 impl<T> Picture<PictureNew, T> {
@@ -119,17 +233,64 @@ impl<T> Picture<PictureNew, T> {
                 timestamp,
                 context,
                 buffers: Default::default(),
+                surface_id: Borrow::<Surface<D>>::borrow(&surface).id(),
                 surface: Rc::new(surface),
+                field: None,
             }),
 
             phantom: PhantomData,
         }
     }
 
+    /// Add `buffer` to the picture.
+    pub fn add_buffer(&mut self, buffer: Buffer) {
+        self.inner.buffers.push(buffer);
+    }
+}
+
+impl<T, O: TypedPictureOperation> Picture<PictureNew, T, O> {
+    /// Creates a new Picture performing operation `O` with a given `timestamp`. `surface` is the
+    /// underlying surface that libva will render to.
+    ///
+    /// Only buffers created for `O` can be added to the picture, using [`Self::add_typed_buffer`].
+    pub fn new_typed<D: SurfaceMemoryDescriptor>(
+        timestamp: u64,
+        context: Rc<Context>,
+        surface: T,
+    ) -> Self
+    where
+        T: Borrow<Surface<D>>,
+    {
+        Self {
+            inner: Box::new(PictureInner {
+                timestamp,
+                context,
+                buffers: Default::default(),
+                surface_id: Borrow::<Surface<D>>::borrow(&surface).id(),
+                surface: Rc::new(surface),
+                field: None,
+            }),
+
+            phantom: PhantomData,
+        }
+    }
+
+    /// Add `buffer` to the picture.
+    pub fn add_typed_buffer(&mut self, buffer: TypedBuffer<O>) {
+        self.inner.buffers.push(buffer.buffer);
+    }
+}
+
+impl<T, O: PictureOperation> Picture<PictureNew, T, O> {
     /// Creates a new Picture with a given `timestamp` to identify it,
     /// reusing the Surface from `picture`. This is useful for interlaced
     /// decoding as one can render both fields to the same underlying surface.
-    pub fn new_from_same_surface<S: PictureState>(timestamp: u64, picture: &Picture<S, T>) -> Self {
+    ///
+    /// If `picture` is a field picture, the new picture contains the opposite field.
+    pub fn new_from_same_surface<S: PictureState>(
+        timestamp: u64,
+        picture: &Picture<S, T, O>,
+    ) -> Self {
         let context = Rc::clone(&picture.inner.context);
         Picture {
             inner: Box::new(PictureInner {
@@ -137,31 +298,62 @@ impl<T> Picture<PictureNew, T> {
                 context,
                 buffers: Default::default(),
                 surface: Rc::clone(&picture.inner.surface),
+                surface_id: picture.inner.surface_id,
+                field: picture.inner.field.map(FieldParity::opposite),
             }),
 
             phantom: PhantomData,
         }
     }
 
-    /// Add `buffer` to the picture.
-    pub fn add_buffer(&mut self, buffer: Buffer) {
-        self.inner.buffers.push(buffer);
+    /// Marks this picture as containing the `field` field of its surface, or the whole frame if
+    /// `field` is `None`.
+    pub fn set_field(&mut self, field: Option<FieldParity>) {
+        self.inner.field = field;
+    }
+
+    /// Checks that the packed header buffers added to this picture include all those in
+    /// `required`, and only headers in `enabled`, i.e. the packed headers enabled in the config of
+    /// an encode context. `required` is usually obtained with
+    /// [`PackedHeaders::required_for_picture`].
+    ///
+    /// Drivers silently produce broken streams when expected packed headers are missing, so this
+    /// should be called before submitting a picture to be encoded.
+    pub fn check_packed_headers(
+        &self,
+        enabled: PackedHeaders,
+        required: PackedHeaders,
+    ) -> Result<(), PackedHeadersError> {
+        let attached = self
+            .inner
+            .buffers
+            .iter()
+            .fold(PackedHeaders::empty(), |acc, buffer| {
+                acc | buffer.packed_header()
+            });
+
+        enabled.check_attached(required, attached)
     }
 
     /// Wrapper around `vaBeginPicture`.
-    pub fn begin<D: SurfaceMemoryDescriptor>(self) -> Result<Picture<PictureBegin, T>, VaError>
+    pub fn begin<D: SurfaceMemoryDescriptor>(self) -> Result<Picture<PictureBegin, T, O>, VaError>
     where
         T: Borrow<Surface<D>>,
     {
+        let display = self.inner.context.display();
         // Safe because `self.inner.context` represents a valid VAContext and
         // `self.inner.surface` represents a valid VASurface.
-        let res = va_check(unsafe {
+        let res = display.check_device_lost(va_check(unsafe {
             bindings::vaBeginPicture(
-                self.inner.context.display().handle(),
+                display.handle(),
                 self.inner.context.id(),
                 self.surface().id(),
             )
-        });
+        }));
+
+        if res.is_ok() {
+            self.surface().touch();
+        }
 
         res.map(|()| Picture {
             inner: self.inner,
@@ -170,71 +362,135 @@ impl<T> Picture<PictureNew, T> {
     }
 }
 
-impl<T> Picture<PictureBegin, T> {
+impl<T, O: PictureOperation> Picture<PictureBegin, T, O> {
     /// Wrapper around `vaRenderPicture`.
-    pub fn render(self) -> Result<Picture<PictureRender, T>, VaError> {
+    pub fn render(self) -> Result<Picture<PictureRender, T, O>, VaError> {
+        let display = self.inner.context.display();
         // Safe because `self.inner.context` represents a valid `VAContext` and `self.inner.surface`
         // represents a valid `VASurface`. `buffers` point to a Rust struct and the vector length is
         // passed to the C function, so it is impossible to write past the end of the vector's
         // storage by mistake.
-        va_check(unsafe {
-            bindings::vaRenderPicture(
-                self.inner.context.display().handle(),
-                self.inner.context.id(),
-                Buffer::as_id_vec(&self.inner.buffers).as_mut_ptr(),
-                self.inner.buffers.len() as i32,
-            )
-        })
-        .map(|()| Picture {
-            inner: self.inner,
-            phantom: PhantomData,
-        })
+        display
+            .check_device_lost(va_check(unsafe {
+                bindings::vaRenderPicture(
+                    display.handle(),
+                    self.inner.context.id(),
+                    Buffer::as_id_vec(&self.inner.buffers).as_mut_ptr(),
+                    self.inner.buffers.len() as i32,
+                )
+            }))
+            .map(|()| Picture {
+                inner: self.inner,
+                phantom: PhantomData,
+            })
     }
 }
 
-impl<T> Picture<PictureRender, T> {
+impl<T, O: PictureOperation> Picture<PictureRender, T, O> {
     /// Wrapper around `vaEndPicture`.
-    pub fn end(self) -> Result<Picture<PictureEnd, T>, VaError> {
+    pub fn end(self) -> Result<Picture<PictureEnd, T, O>, VaError> {
+        let display = self.inner.context.display();
         // Safe because `self.inner.context` represents a valid `VAContext`.
-        va_check(unsafe {
-            bindings::vaEndPicture(
-                self.inner.context.display().handle(),
-                self.inner.context.id(),
-            )
-        })
-        .map(|()| Picture {
-            inner: self.inner,
-            phantom: PhantomData,
-        })
+        display
+            .check_device_lost(va_check(unsafe {
+                bindings::vaEndPicture(display.handle(), self.inner.context.id())
+            }))
+            .map(|()| Picture {
+                inner: self.inner,
+                phantom: PhantomData,
+            })
     }
 }
 
-impl<T> Picture<PictureEnd, T> {
+impl<T, O: PictureOperation> Picture<PictureEnd, T, O> {
     /// Syncs the picture, ensuring that all pending operations are complete when this call returns.
     pub fn sync<D: SurfaceMemoryDescriptor>(
         self,
-    ) -> Result<Picture<PictureSync, T>, (VaError, Self)>
+    ) -> Result<Picture<PictureSync, T, O>, (VaError, Self)>
     where
         T: Borrow<Surface<D>>,
     {
         let res = self.surface().sync();
 
         match res {
-            Ok(()) => Ok(Picture {
-                inner: self.inner,
-                phantom: PhantomData,
-            }),
+            Ok(()) => {
+                self.surface().mark_used();
+                Ok(Picture {
+                    inner: self.inner,
+                    phantom: PhantomData,
+                })
+            }
             Err(e) => Err((e, self)),
         }
     }
 }
 
-impl<S: PictureState, T> Picture<S, T> {
+/// Object-safe view of a [`Picture`] that has been submitted to the hardware.
+///
+/// This allows schedulers to keep track of pictures of different codecs, operations and surface
+/// types in a single collection, e.g. `Vec<Box<dyn InFlightFrame>>`.
+pub trait InFlightFrame {
+    /// Returns the timestamp of the picture.
+    fn timestamp(&self) -> u64;
+
+    /// Returns the status of the surface of the picture. Wrapper over `vaQuerySurfaceStatus`.
+    fn status(&self) -> Result<SurfaceStatus, VaError>;
+
+    /// Returns whether the operation on the picture has completed, without blocking.
+    ///
+    /// Errors that occurred during the operation are reported once it has completed.
+    fn try_complete(&mut self) -> Result<bool, VaError>;
+}
+
+impl<T, O: PictureOperation> InFlightFrame for Picture<PictureEnd, T, O> {
+    fn timestamp(&self) -> u64 {
+        self.inner.timestamp
+    }
+
+    fn status(&self) -> Result<SurfaceStatus, VaError> {
+        let mut status: bindings::VASurfaceStatus::Type = 0;
+
+        // Safe because `self.inner.context` represents a valid `VAContext` and
+        // `self.inner.surface_id` a valid `VASurface`.
+        va_check(unsafe {
+            bindings::vaQuerySurfaceStatus(
+                self.inner.context.display().handle(),
+                self.inner.surface_id,
+                &mut status,
+            )
+        })?;
+
+        Ok(SurfaceStatus::from(status))
+    }
+
+    fn try_complete(&mut self) -> Result<bool, VaError> {
+        if self.status()? == SurfaceStatus::Rendering {
+            return Ok(false);
+        }
+
+        // Safe because `self.inner.context` represents a valid `VAContext` and
+        // `self.inner.surface_id` a valid `VASurface`. The operation has already completed so this
+        // does not block, but reports errors that may have happened during it.
+        va_check(unsafe {
+            bindings::vaSyncSurface(self.inner.context.display().handle(), self.inner.surface_id)
+        })?;
+
+        Ok(true)
+    }
+}
+
+impl<S: PictureState, T, O: PictureOperation> Picture<S, T, O> {
     /// Returns the timestamp of this picture.
     pub fn timestamp(&self) -> u64 {
         self.inner.timestamp
     }
 
+    /// Returns the field of its surface this picture contains, or `None` if it contains the whole
+    /// frame.
+    pub fn field(&self) -> Option<FieldParity> {
+        self.inner.field
+    }
+
     /// Returns a reference to the underlying `Surface`.
     ///
     /// If you are interested in obtaining the container of the `Surface`, use `as_ref()` instead.
@@ -248,7 +504,7 @@ impl<S: PictureState, T> Picture<S, T> {
     }
 }
 
-impl<S: PictureReclaimableSurface, T> Picture<S, T> {
+impl<S: PictureReclaimableSurface, T, O: PictureOperation> Picture<S, T, O> {
     /// Reclaim ownership of the Surface this picture has been created from, consuming the picture
     /// in the process. Useful if the Surface is part of a pool.
     ///
@@ -261,9 +517,11 @@ impl<S: PictureReclaimableSurface, T> Picture<S, T> {
             Err(surface) => Err(Self {
                 inner: Box::new(PictureInner {
                     surface,
+                    surface_id: inner.surface_id,
                     context: inner.context,
                     buffers: inner.buffers,
                     timestamp: inner.timestamp,
+                    field: inner.field,
                 }),
                 phantom: PhantomData,
             }),
@@ -300,7 +558,74 @@ impl<S: PictureReclaimableSurface, T> Picture<S, T> {
     }
 }
 
-impl<S: PictureState, T> AsRef<T> for Picture<S, T> {
+#[derive(Debug, Error)]
+pub enum PictureDumpError {
+    #[error("I/O error while writing the dump: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("error while reading the picture: {0}")]
+    VaError(#[from] VaError),
+}
+
+impl<T, O: PictureOperation> Picture<PictureSync, T, O> {
+    /// Writes a snapshot of this picture into directory `dir`, e.g. to attach to a driver bug
+    /// report.
+    ///
+    /// The snapshot contains the content of the surface copied into an image of `format`
+    /// (`surface.bin`), a copy of each buffer submitted with the picture (`buffer_<index>.bin`),
+    /// and a `metadata.txt` file describing them.
+    pub fn dump<D: SurfaceMemoryDescriptor>(
+        &self,
+        dir: &Path,
+        format: bindings::VAImageFormat,
+    ) -> Result<(), PictureDumpError>
+    where
+        T: Borrow<Surface<D>>,
+    {
+        fs::create_dir_all(dir)?;
+
+        let surface = self.surface();
+        let size = surface.size();
+        let image = Image::create_from(surface, format, size, size)?;
+        fs::write(dir.join("surface.bin"), image.as_ref())?;
+
+        let va_image = image.image();
+        let mut metadata = String::new();
+        let _ = writeln!(metadata, "timestamp: {}", self.timestamp());
+        let _ = writeln!(metadata, "surface_id: {}", surface.id());
+        let _ = writeln!(metadata, "size: {}x{}", size.0, size.1);
+        let _ = writeln!(metadata, "fourcc: {:#010x}", va_image.format.fourcc);
+        let _ = writeln!(
+            metadata,
+            "planes: {} pitches: {:?} offsets: {:?}",
+            va_image.num_planes, va_image.pitches, va_image.offsets
+        );
+
+        for (i, buffer) in self.inner.buffers.iter().enumerate() {
+            match buffer.read_contents() {
+                Ok((type_, contents)) => {
+                    fs::write(dir.join(format!("buffer_{}.bin", i)), &contents)?;
+                    let _ = writeln!(
+                        metadata,
+                        "buffer {}: type {} size {}",
+                        i,
+                        type_,
+                        contents.len()
+                    );
+                }
+                // Some drivers do not allow mapping buffers once they have been rendered.
+                Err(e) => {
+                    let _ = writeln!(metadata, "buffer {}: cannot be read: {}", i, e);
+                }
+            }
+        }
+
+        fs::write(dir.join("metadata.txt"), metadata)?;
+
+        Ok(())
+    }
+}
+
+impl<S: PictureState, T, O: PictureOperation> AsRef<T> for Picture<S, T, O> {
     fn as_ref(&self) -> &T {
         (*self.inner.surface).borrow()
     }
@@ -0,0 +1,99 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use crate::bindings;
+
+/// Wrapper over the `VAProcessingRateParameter` FFI type, describing the stream for which a
+/// processing rate is queried with [`crate::Config::query_processing_rate`].
+pub struct ProcessingRateParameter(bindings::VAProcessingRateParameter);
+
+impl ProcessingRateParameter {
+    /// Creates parameters for an encode config, wrapping `VAProcessingRateParameterEnc`.
+    pub fn new_encode(
+        level_idc: u8,
+        quality_level: u32,
+        intra_period: u32,
+        ip_period: u32,
+    ) -> Self {
+        Self(bindings::VAProcessingRateParameter {
+            __bindgen_anon_1: bindings::_VAProcessingRateParameter__bindgen_ty_1 {
+                proc_buf_enc: bindings::VAProcessingRateParameterEnc {
+                    level_idc,
+                    quality_level,
+                    intra_period,
+                    ip_period,
+                    ..Default::default()
+                },
+            },
+        })
+    }
+
+    /// Creates parameters for a decode config, wrapping `VAProcessingRateParameterDec`.
+    pub fn new_decode(level_idc: u8) -> Self {
+        Self(bindings::VAProcessingRateParameter {
+            __bindgen_anon_1: bindings::_VAProcessingRateParameter__bindgen_ty_1 {
+                proc_buf_dec: bindings::VAProcessingRateParameterDec {
+                    level_idc,
+                    ..Default::default()
+                },
+            },
+        })
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut bindings::VAProcessingRateParameter {
+        &mut self.0
+    }
+}
+
+/// Processing rate reported by the driver for a given config, in macroblocks (16x16 pixels) per
+/// second.
+///
+/// This can be used to estimate whether a given stream, or how many instances of it, can be
+/// processed in real time by the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessingRate(pub u32);
+
+impl ProcessingRate {
+    /// Returns the number of macroblocks per second required to process a `width`x`height` stream
+    /// at `fps` frames per second.
+    pub fn required_for(width: u32, height: u32, fps: f64) -> f64 {
+        let mbs_per_frame = u64::from(width.div_ceil(16)) * u64::from(height.div_ceil(16));
+
+        mbs_per_frame as f64 * fps
+    }
+
+    /// Returns whether a single `width`x`height` stream at `fps` frames per second can be sustained
+    /// at this processing rate.
+    pub fn can_sustain(&self, width: u32, height: u32, fps: f64) -> bool {
+        self.max_streams(width, height, fps) >= 1
+    }
+
+    /// Returns how many concurrent `width`x`height` streams at `fps` frames per second fit into
+    /// this processing rate.
+    pub fn max_streams(&self, width: u32, height: u32, fps: f64) -> u32 {
+        let required = Self::required_for(width, height, fps);
+        if required <= 0.0 {
+            return 0;
+        }
+
+        (f64::from(self.0) / required) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processing_rate_estimation() {
+        // 1080p is 120x68 macroblocks.
+        assert_eq!(ProcessingRate::required_for(1920, 1080, 30.0), 244800.0);
+
+        let rate = ProcessingRate(1_000_000);
+        assert!(rate.can_sustain(1920, 1080, 30.0));
+        assert_eq!(rate.max_streams(1920, 1080, 30.0), 4);
+        assert!(!rate.can_sustain(3840, 2160, 60.0));
+        assert_eq!(rate.max_streams(0, 0, 30.0), 0);
+    }
+}
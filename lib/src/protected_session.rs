@@ -0,0 +1,71 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Protected sessions, in which drivers allocate and process secure surfaces.
+
+use std::rc::Rc;
+
+use log::error;
+
+use crate::bindings;
+use crate::va_check;
+use crate::Config;
+use crate::Context;
+use crate::Display;
+use crate::VaError;
+
+/// A protected session created with `vaCreateProtectedSession`.
+///
+/// libva has no usage hint for protected surfaces: the surfaces allocated by the driver become
+/// secure when they are processed by a context this session is attached to, e.g. the output of a
+/// decoder for DRM-protected playback.
+pub struct ProtectedSession {
+    display: Rc<Display>,
+    id: bindings::VAProtectedSessionID,
+}
+
+impl ProtectedSession {
+    /// Creates a protected session by wrapping around a `vaCreateProtectedSession` call. This is
+    /// just a helper for [`Display::create_protected_session`].
+    pub(crate) fn new(display: Rc<Display>, config: &Config) -> Result<Self, VaError> {
+        let mut id = 0;
+
+        // Safe because `display` represents a valid `VADisplay` and `config` a valid `VAConfig`
+        // of that display.
+        va_check(unsafe {
+            bindings::vaCreateProtectedSession(display.handle(), config.id(), &mut id)
+        })?;
+
+        Ok(Self { display, id })
+    }
+
+    /// Attaches this session to `context`, making the surfaces it processes secure. Wrapper over
+    /// `vaAttachProtectedSession`.
+    pub fn attach(&self, context: &Context) -> Result<(), VaError> {
+        // Safe because `self` represents a valid protected session and `context` a valid
+        // `VAContext` of the same display.
+        va_check(unsafe {
+            bindings::vaAttachProtectedSession(self.display.handle(), context.id(), self.id)
+        })
+    }
+
+    /// Detaches the session attached to `context`. Wrapper over `vaDetachProtectedSession`.
+    pub fn detach(&self, context: &Context) -> Result<(), VaError> {
+        // Safe because `context` represents a valid `VAContext` of the display of `self`.
+        va_check(unsafe { bindings::vaDetachProtectedSession(self.display.handle(), context.id()) })
+    }
+}
+
+impl Drop for ProtectedSession {
+    fn drop(&mut self) {
+        // Safe because `self` represents a valid protected session.
+        let status = va_check(unsafe {
+            bindings::vaDestroyProtectedSession(self.display.handle(), self.id)
+        });
+
+        if status.is_err() {
+            error!("vaDestroyProtectedSession failed: {}", status.unwrap_err());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Registry of the live VA objects of a [`crate::Display`], for diagnosing leaks.
+
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Kind of VA object tracked by the registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VaObjectKind {
+    Config,
+    Context,
+    Surface,
+    Buffer,
+}
+
+/// A VA object that is currently alive.
+#[derive(Debug, Clone)]
+pub struct LiveObject {
+    pub kind: VaObjectKind,
+    /// The `VAGenericID` of the object.
+    pub id: u32,
+    /// Backtrace of the creation of the object. Only captured in debug builds.
+    pub backtrace: Option<Rc<Backtrace>>,
+}
+
+/// Registry of the live objects of a display. Disabled by default.
+#[derive(Default)]
+pub(crate) struct ObjectRegistry {
+    enabled: bool,
+    objects: HashMap<(VaObjectKind, u32), Option<Rc<Backtrace>>>,
+}
+
+impl ObjectRegistry {
+    /// Enables or disables the registry. Disabling it forgets all the objects registered so far.
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.objects.clear();
+        }
+    }
+
+    pub(crate) fn register(&mut self, kind: VaObjectKind, id: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        let backtrace = if cfg!(debug_assertions) {
+            Some(Rc::new(Backtrace::force_capture()))
+        } else {
+            None
+        };
+
+        self.objects.insert((kind, id), backtrace);
+    }
+
+    pub(crate) fn unregister(&mut self, kind: VaObjectKind, id: u32) {
+        if self.enabled {
+            self.objects.remove(&(kind, id));
+        }
+    }
+
+    pub(crate) fn live_objects(&self) -> Vec<LiveObject> {
+        self.objects
+            .iter()
+            .map(|(&(kind, id), backtrace)| LiveObject {
+                kind,
+                id,
+                backtrace: backtrace.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        let mut registry = ObjectRegistry::default();
+
+        // Nothing is tracked while disabled.
+        registry.register(VaObjectKind::Surface, 1);
+        assert!(registry.live_objects().is_empty());
+
+        registry.set_enabled(true);
+        registry.register(VaObjectKind::Surface, 1);
+        registry.register(VaObjectKind::Buffer, 1);
+        assert_eq!(registry.live_objects().len(), 2);
+
+        registry.unregister(VaObjectKind::Surface, 1);
+        let objects = registry.live_objects();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].kind, VaObjectKind::Buffer);
+    }
+}
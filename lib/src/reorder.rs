@@ -0,0 +1,147 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Queue releasing decoded pictures in display order.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Entry of a [`DisplayOrderQueue`], ordered so that the max-heap yields the smallest display
+/// order first, and pictures with the same display order in insertion order.
+struct Entry<T> {
+    order: i64,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.order, other.seq).cmp(&(self.order, self.seq))
+    }
+}
+
+/// Buffers pictures output in decoding order and releases them in display order.
+///
+/// Each picture is pushed with its display order, e.g. the picture order count for H.264 and
+/// HEVC. A picture is released once more than `depth` pictures are buffered, `depth` being the
+/// maximum number of pictures that can precede another one in decoding order while following it
+/// in display order (e.g. `max_num_reorder_frames`).
+///
+/// Since display orders restart at each IDR picture, the queue must be drained with
+/// [`Self::flush`] before pushing one.
+pub struct DisplayOrderQueue<T> {
+    depth: usize,
+    low_latency: bool,
+    next_seq: u64,
+    pending: BinaryHeap<Entry<T>>,
+}
+
+impl<T> DisplayOrderQueue<T> {
+    /// Creates a queue buffering up to `depth` pictures.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            low_latency: false,
+            next_seq: 0,
+            pending: Default::default(),
+        }
+    }
+
+    /// Returns the reorder depth of this queue.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Changes the reorder depth of this queue, e.g. upon a new sequence.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    /// Enables or disables low-latency mode, in which pictures are released as soon as they are
+    /// pushed. This is suitable for streams known not to reorder pictures.
+    pub fn set_low_latency(&mut self, low_latency: bool) {
+        self.low_latency = low_latency;
+    }
+
+    /// Adds a picture with display order `order` to the queue.
+    pub fn push(&mut self, order: i64, item: T) {
+        self.pending.push(Entry {
+            order,
+            seq: self.next_seq,
+            item,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Returns the next picture in display order if it can be released.
+    pub fn pop(&mut self) -> Option<T> {
+        let depth = if self.low_latency { 0 } else { self.depth };
+
+        if self.pending.len() > depth {
+            self.pending.pop().map(|entry| entry.item)
+        } else {
+            None
+        }
+    }
+
+    /// Returns all the buffered pictures in display order, e.g. at the end of the stream, before
+    /// an IDR picture or when seeking, and resets the queue so it can be reused for the following
+    /// pictures. The reorder depth and low-latency mode are kept.
+    ///
+    /// Pictures still being decoded are returned as well, so they should be synced (e.g. with
+    /// [`crate::Picture::sync`]) before being displayed.
+    pub fn flush(&mut self) -> Vec<T> {
+        let pictures = std::iter::from_fn(|| self.pending.pop().map(|entry| entry.item)).collect();
+        self.next_seq = 0;
+        pictures
+    }
+
+    /// Returns the number of buffered pictures.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether no picture is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_order() {
+        let mut queue = DisplayOrderQueue::new(2);
+        let mut output = vec![];
+
+        // I0 P6 B2 B4 P12 B8 B10 in decoding order.
+        for order in [0, 6, 2, 4, 12, 8, 10] {
+            queue.push(order, order);
+            output.extend(queue.pop());
+        }
+        output.extend(queue.flush());
+        assert_eq!(output, vec![0, 2, 4, 6, 8, 10, 12]);
+        assert!(queue.is_empty());
+
+        queue.set_low_latency(true);
+        queue.push(0, 0);
+        assert_eq!(queue.pop(), Some(0));
+    }
+}
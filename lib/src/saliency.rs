@@ -0,0 +1,245 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to turn an importance map of a frame into ROI regions or a QP map for the encoder.
+
+use thiserror::Error;
+
+use crate::bindings;
+
+/// ROI encoding capabilities of a config, parsed from the `VAConfigAttribEncROI` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncRoiCapabilities {
+    /// Maximum number of ROI regions per frame. Zero if ROI encoding is not supported.
+    pub max_regions: u32,
+    /// Whether the rate control supports ROI priorities.
+    pub rc_priority_support: bool,
+    /// Whether the rate control supports ROI QP deltas.
+    pub rc_qp_delta_support: bool,
+}
+
+impl EncRoiCapabilities {
+    /// Parses the value of the `VAConfigAttribEncROI` attribute, aka `VAConfigAttribValEncROI`.
+    pub fn from_attribute_value(value: u32) -> Self {
+        if value == bindings::VA_ATTRIB_NOT_SUPPORTED {
+            return Default::default();
+        }
+
+        Self {
+            max_regions: value & 0xff,
+            rc_priority_support: value & (1 << 8) != 0,
+            rc_qp_delta_support: value & (1 << 9) != 0,
+        }
+    }
+}
+
+/// Range of QP deltas to apply, from `min` for the most important blocks to `max` for the least
+/// important ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QpDeltaRange {
+    pub min: i8,
+    pub max: i8,
+}
+
+impl QpDeltaRange {
+    /// Returns the QP delta for a block of importance `importance`, between `0.0` and `1.0`.
+    fn delta_for(&self, importance: f32) -> i8 {
+        let delta = f32::from(self.max) + (f32::from(self.min) - f32::from(self.max)) * importance;
+
+        (delta.round() as i8).clamp(self.min, self.max)
+    }
+}
+
+/// A region of interest, in pixels, with the QP delta to apply to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoiRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub delta_qp: i8,
+}
+
+impl RoiRegion {
+    /// Returns the rectangle of this region, as expected in `VAEncROI`.
+    pub fn rectangle(&self) -> bindings::VARectangle {
+        bindings::VARectangle {
+            x: self.x as i16,
+            y: self.y as i16,
+            width: self.width as u16,
+            height: self.height as u16,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SaliencyMapError {
+    #[error("block size must not be zero")]
+    InvalidBlockSize,
+    #[error("expected {expected} importance values, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+}
+
+/// Importance of each block of a frame, e.g. as computed by a face detector.
+pub struct SaliencyMap {
+    width: u32,
+    height: u32,
+    block_size: u32,
+    width_in_blocks: u32,
+    importance: Vec<f32>,
+}
+
+impl SaliencyMap {
+    /// Creates the map of a `width`x`height` frame split into blocks of `block_size` pixels, with
+    /// `importance` giving the importance of each block in raster order, between `0.0` and `1.0`.
+    /// Values out of this range are clamped.
+    pub fn new(
+        width: u32,
+        height: u32,
+        block_size: u32,
+        importance: Vec<f32>,
+    ) -> Result<Self, SaliencyMapError> {
+        if block_size == 0 {
+            return Err(SaliencyMapError::InvalidBlockSize);
+        }
+
+        let width_in_blocks = width.div_ceil(block_size);
+        let expected = (width_in_blocks * height.div_ceil(block_size)) as usize;
+        if importance.len() != expected {
+            return Err(SaliencyMapError::InvalidLength {
+                expected,
+                actual: importance.len(),
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            block_size,
+            width_in_blocks,
+            importance: importance.into_iter().map(|i| i.clamp(0.0, 1.0)).collect(),
+        })
+    }
+
+    /// Returns the QP delta of each block in raster order, e.g. to fill a QP map buffer.
+    pub fn to_qp_map(&self, range: QpDeltaRange) -> Vec<i8> {
+        self.importance
+            .iter()
+            .map(|&importance| range.delta_for(importance))
+            .collect()
+    }
+
+    /// Returns up to `caps.max_regions` regions covering the blocks with a non-zero QP delta,
+    /// built by merging neighboring blocks with the same delta into rectangles.
+    ///
+    /// Regions are returned by decreasing importance, which is also the priority order expected
+    /// by drivers. When there are more regions than supported, the least important ones are
+    /// dropped and their blocks are encoded with the base QP.
+    pub fn to_roi_regions(&self, range: QpDeltaRange, caps: &EncRoiCapabilities) -> Vec<RoiRegion> {
+        let deltas = self.to_qp_map(range);
+        let width = self.width_in_blocks as usize;
+        let height = if width == 0 { 0 } else { deltas.len() / width };
+        let mut visited = vec![false; deltas.len()];
+        let mut regions = Vec::new();
+
+        for start in 0..deltas.len() {
+            let delta = deltas[start];
+            if visited[start] || delta == 0 {
+                continue;
+            }
+
+            let (bx, by) = (start % width, start / width);
+            let matches = |x: usize, y: usize| {
+                let i = y * width + x;
+                !visited[i] && deltas[i] == delta
+            };
+
+            // Grow the region to the right, then downwards as long as whole rows match.
+            let mut w = 1;
+            while bx + w < width && matches(bx + w, by) {
+                w += 1;
+            }
+            let mut h = 1;
+            while by + h < height && (bx..bx + w).all(|x| matches(x, by + h)) {
+                h += 1;
+            }
+
+            for y in by..by + h {
+                visited[y * width + bx..y * width + bx + w].fill(true);
+            }
+
+            let x = bx as u32 * self.block_size;
+            let y = by as u32 * self.block_size;
+            regions.push(RoiRegion {
+                x,
+                y,
+                width: (w as u32 * self.block_size).min(self.width - x),
+                height: (h as u32 * self.block_size).min(self.height - y),
+                delta_qp: delta,
+            });
+        }
+
+        regions.sort_by_key(|r| (r.delta_qp, std::cmp::Reverse(r.width * r.height)));
+        regions.truncate(caps.max_regions as usize);
+
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saliency_to_roi() {
+        let caps = EncRoiCapabilities::from_attribute_value(0x302);
+        assert_eq!(
+            caps,
+            EncRoiCapabilities {
+                max_regions: 2,
+                rc_priority_support: true,
+                rc_qp_delta_support: true,
+            }
+        );
+
+        // 60x40 frame, 3x2 blocks of 20 pixels, the last column is a bit less important.
+        #[rustfmt::skip]
+        let map = SaliencyMap::new(60, 40, 20, vec![
+            0.0, 1.0, 0.5,
+            0.0, 1.0, 0.5,
+        ])
+        .unwrap();
+        let range = QpDeltaRange { min: -8, max: 0 };
+
+        assert_eq!(map.to_qp_map(range), vec![0, -8, -4, 0, -8, -4]);
+        assert_eq!(
+            map.to_roi_regions(range, &caps),
+            vec![
+                RoiRegion {
+                    x: 20,
+                    y: 0,
+                    width: 20,
+                    height: 40,
+                    delta_qp: -8
+                },
+                RoiRegion {
+                    x: 40,
+                    y: 0,
+                    width: 20,
+                    height: 40,
+                    delta_qp: -4
+                },
+            ]
+        );
+
+        // Only the most important region is kept if the driver supports a single one.
+        let caps = EncRoiCapabilities {
+            max_regions: 1,
+            ..caps
+        };
+        assert_eq!(map.to_roi_regions(range, &caps).len(), 1);
+
+        assert!(SaliencyMap::new(60, 40, 20, vec![0.0; 5]).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Builders serializing H.264 and HEVC SEI messages into packed header buffers, and their AV1
+//! metadata OBU counterparts.
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::BufferType;
+use crate::EncPackedHeaderParameterBuffer;
+
+/// Codec of the stream SEI messages are inserted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeiCodec {
+    H264,
+    Hevc,
+}
+
+impl SeiCodec {
+    /// Returns the NAL unit header of a (prefix) SEI NAL unit.
+    fn nal_header(&self) -> &'static [u8] {
+        match self {
+            // nal_ref_idc = 0, nal_unit_type = 6.
+            SeiCodec::H264 => &[0x06],
+            // nal_unit_type = 39 (PREFIX_SEI_NUT), nuh_layer_id = 0, nuh_temporal_id_plus1 = 1.
+            SeiCodec::Hevc => &[0x4e, 0x01],
+        }
+    }
+
+    /// Returns the `VAEncPackedHeaderType` to use for SEI packed headers.
+    fn packed_header_type(&self) -> u32 {
+        match self {
+            SeiCodec::H264 => bindings::VAEncPackedHeaderTypeH264::VAEncPackedHeaderH264_SEI,
+            SeiCodec::Hevc => bindings::VAEncPackedHeaderTypeHEVC::VAEncPackedHeaderHEVC_SEI,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SeiError {
+    #[error("SEI message with payload type {0} is not supported for {1:?}")]
+    UnsupportedMessage(u32, SeiCodec),
+    #[error("value {0} does not fit in {1} bits")]
+    ValueTooLarge(u32, u8),
+    #[error("no SEI message to serialize")]
+    NoMessage,
+    #[error("invalid number of caption triplets {0}")]
+    InvalidCaptionCount(usize),
+}
+
+/// H.264 buffering period SEI message (payload type 0), for the NAL HRD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferingPeriod {
+    pub seq_parameter_set_id: u32,
+    /// `initial_cpb_removal_delay_length_minus1 + 1` from the HRD parameters.
+    pub initial_cpb_removal_delay_length: u8,
+    /// `(initial_cpb_removal_delay, initial_cpb_removal_delay_offset)` of each `SchedSelIdx`.
+    pub initial_cpb_removal_delays: Vec<(u32, u32)>,
+}
+
+/// H.264 picture timing SEI message (payload type 1), without clock timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PicTiming {
+    /// `(cpb_removal_delay, dpb_output_delay)`, present if `CpbDpbDelaysPresentFlag` is set.
+    pub delays: Option<(u32, u32)>,
+    /// `cpb_removal_delay_length_minus1 + 1` from the HRD parameters.
+    pub cpb_removal_delay_length: u8,
+    /// `dpb_output_delay_length_minus1 + 1` from the HRD parameters.
+    pub dpb_output_delay_length: u8,
+    /// `pic_struct`, present if `pic_struct_present_flag` is set in the VUI.
+    pub pic_struct: Option<u8>,
+}
+
+/// Mastering display colour volume SEI message (payload type 137).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MasteringDisplayColourVolume {
+    /// Chromaticity of the primaries, in the order used by the specification (G, B, R), in
+    /// increments of 0.00002.
+    pub display_primaries: [(u16, u16); 3],
+    /// Chromaticity of the white point, in increments of 0.00002.
+    pub white_point: (u16, u16),
+    /// In units of 0.0001 candelas per square metre.
+    pub max_display_mastering_luminance: u32,
+    /// In units of 0.0001 candelas per square metre.
+    pub min_display_mastering_luminance: u32,
+}
+
+/// Content light level information SEI message (payload type 144).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContentLightLevel {
+    pub max_content_light_level: u16,
+    pub max_pic_average_light_level: u16,
+}
+
+/// An SEI message that can be serialized with [`sei_nal_unit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeiMessage {
+    BufferingPeriod(BufferingPeriod),
+    PicTiming(PicTiming),
+    MasteringDisplayColourVolume(MasteringDisplayColourVolume),
+    ContentLightLevel(ContentLightLevel),
+    /// User data registered by Rec. ITU-T T.35 SEI message (payload type 4). `data` contains the
+    /// payload following `itu_t_t35_country_code`, starting with the provider code.
+    UserDataRegisteredItuT35 {
+        country_code: u8,
+        data: Vec<u8>,
+    },
+    /// User data unregistered SEI message (payload type 5).
+    UserDataUnregistered {
+        uuid: [u8; 16],
+        data: Vec<u8>,
+    },
+}
+
+/// `itu_t_t35_country_code` of the United States, used by ATSC closed captions.
+const T35_COUNTRY_CODE_US: u8 = 0xb5;
+
+/// Maximum number of caption triplets in an ATSC A/53 `cc_data()` structure.
+const MAX_CC_COUNT: usize = 31;
+
+/// Returns the ATSC A/53 `GA94` user data carrying the CEA-708 `cc_data` triplets, i.e. the T.35
+/// payload following the country code.
+fn cea708_t35_data(cc_data: &[[u8; 3]]) -> Result<Vec<u8>, SeiError> {
+    if cc_data.is_empty() || cc_data.len() > MAX_CC_COUNT {
+        return Err(SeiError::InvalidCaptionCount(cc_data.len()));
+    }
+
+    // itu_t_t35_provider_code (ATSC), user_identifier and user_data_type_code (cc_data).
+    let mut data = vec![0x00, 0x31, b'G', b'A', b'9', b'4', 0x03];
+    // reserved, process_cc_data_flag, additional_data_flag = 0 and cc_count, then em_data.
+    data.extend_from_slice(&[0xc0 | cc_data.len() as u8, 0xff]);
+    data.extend(cc_data.iter().flatten());
+    // marker_bits
+    data.push(0xff);
+
+    Ok(data)
+}
+
+impl SeiMessage {
+    /// Returns a user data registered SEI message carrying the CEA-708 closed caption `cc_data`
+    /// triplets, framed as ATSC A/53 `GA94` user data.
+    pub fn cea708_captions(cc_data: &[[u8; 3]]) -> Result<Self, SeiError> {
+        Ok(SeiMessage::UserDataRegisteredItuT35 {
+            country_code: T35_COUNTRY_CODE_US,
+            data: cea708_t35_data(cc_data)?,
+        })
+    }
+
+    /// Returns the `payloadType` of this message.
+    pub fn payload_type(&self) -> u32 {
+        match self {
+            SeiMessage::BufferingPeriod(_) => 0,
+            SeiMessage::PicTiming(_) => 1,
+            SeiMessage::UserDataRegisteredItuT35 { .. } => 4,
+            SeiMessage::UserDataUnregistered { .. } => 5,
+            SeiMessage::MasteringDisplayColourVolume(_) => 137,
+            SeiMessage::ContentLightLevel(_) => 144,
+        }
+    }
+
+    /// Returns the serialized payload of this message, for `codec`.
+    pub fn payload(&self, codec: SeiCodec) -> Result<Vec<u8>, SeiError> {
+        let mut w = BitWriter::default();
+
+        match self {
+            // The HEVC versions of these messages depend on many more VPS/SPS parameters.
+            SeiMessage::BufferingPeriod(_) | SeiMessage::PicTiming(_)
+                if codec == SeiCodec::Hevc =>
+            {
+                return Err(SeiError::UnsupportedMessage(self.payload_type(), codec));
+            }
+            SeiMessage::BufferingPeriod(bp) => {
+                w.write_ue(bp.seq_parameter_set_id);
+                for (delay, offset) in &bp.initial_cpb_removal_delays {
+                    w.write_bits(*delay, bp.initial_cpb_removal_delay_length)?;
+                    w.write_bits(*offset, bp.initial_cpb_removal_delay_length)?;
+                }
+            }
+            SeiMessage::PicTiming(pt) => {
+                if let Some((cpb_removal_delay, dpb_output_delay)) = pt.delays {
+                    w.write_bits(cpb_removal_delay, pt.cpb_removal_delay_length)?;
+                    w.write_bits(dpb_output_delay, pt.dpb_output_delay_length)?;
+                }
+                if let Some(pic_struct) = pt.pic_struct {
+                    w.write_bits(pic_struct as u32, 4)?;
+                    let num_clock_ts = match pic_struct {
+                        0..=2 => 1,
+                        3 | 4 | 7 => 2,
+                        _ => 3,
+                    };
+                    for _ in 0..num_clock_ts {
+                        // clock_timestamp_flag
+                        w.write_bits(0, 1)?;
+                    }
+                }
+            }
+            SeiMessage::UserDataRegisteredItuT35 { country_code, data } => {
+                w.write_bits(*country_code as u32, 8)?;
+                w.write_bytes(data);
+            }
+            SeiMessage::UserDataUnregistered { uuid, data } => {
+                w.write_bytes(uuid);
+                w.write_bytes(data);
+            }
+            SeiMessage::MasteringDisplayColourVolume(mdcv) => {
+                for (x, y) in mdcv.display_primaries {
+                    w.write_bits(x as u32, 16)?;
+                    w.write_bits(y as u32, 16)?;
+                }
+                w.write_bits(mdcv.white_point.0 as u32, 16)?;
+                w.write_bits(mdcv.white_point.1 as u32, 16)?;
+                w.write_bits(mdcv.max_display_mastering_luminance, 32)?;
+                w.write_bits(mdcv.min_display_mastering_luminance, 32)?;
+            }
+            SeiMessage::ContentLightLevel(cll) => {
+                w.write_bits(cll.max_content_light_level as u32, 16)?;
+                w.write_bits(cll.max_pic_average_light_level as u32, 16)?;
+            }
+        }
+
+        if !w.is_byte_aligned() {
+            // bit_equal_to_one followed by bit_equal_to_zero until aligned.
+            w.write_bits(1, 1)?;
+            w.align();
+        }
+
+        Ok(w.into_bytes())
+    }
+}
+
+/// Serializes `messages` into a single SEI NAL unit for `codec`, including its start code and
+/// emulation prevention bytes.
+pub fn sei_nal_unit(codec: SeiCodec, messages: &[SeiMessage]) -> Result<Vec<u8>, SeiError> {
+    if messages.is_empty() {
+        return Err(SeiError::NoMessage);
+    }
+
+    let mut rbsp = vec![];
+    for message in messages {
+        let payload = message.payload(codec)?;
+        write_sei_value(&mut rbsp, message.payload_type() as usize);
+        write_sei_value(&mut rbsp, payload.len());
+        rbsp.extend_from_slice(&payload);
+    }
+    // rbsp_trailing_bits
+    rbsp.push(0x80);
+
+    let mut nal = vec![0x00, 0x00, 0x00, 0x01];
+    nal.extend_from_slice(codec.nal_header());
+    let mut zeros = 0;
+    for byte in rbsp {
+        if zeros >= 2 && byte <= 0x03 {
+            nal.push(0x03);
+            zeros = 0;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        nal.push(byte);
+    }
+
+    Ok(nal)
+}
+
+/// Returns the packed header parameter and data buffers inserting `messages` into the bitstream
+/// of the picture they are submitted with.
+pub fn sei_packed_header(
+    codec: SeiCodec,
+    messages: &[SeiMessage],
+) -> Result<[BufferType; 2], SeiError> {
+    let data = sei_nal_unit(codec, messages)?;
+    let param = EncPackedHeaderParameterBuffer::new(
+        codec.packed_header_type(),
+        data.len() as u32 * 8,
+        true,
+    );
+
+    Ok([
+        BufferType::EncPackedHeaderParameter(param),
+        BufferType::EncPackedHeaderData(data),
+    ])
+}
+
+/// Serializes an AV1 `OBU_METADATA` OBU of type `METADATA_TYPE_ITUT_T35`, including its size
+/// field. `data` contains the payload following `itu_t_t35_country_code`.
+///
+/// AV1 has no SEI: the OBU is meant to be inserted into the temporal unit as raw packed data.
+pub fn av1_itut_t35_metadata_obu(country_code: u8, data: &[u8]) -> Vec<u8> {
+    const OBU_METADATA: u8 = 5;
+    const METADATA_TYPE_ITUT_T35: usize = 4;
+
+    let mut payload = vec![];
+    write_leb128(&mut payload, METADATA_TYPE_ITUT_T35);
+    payload.push(country_code);
+    payload.extend_from_slice(data);
+    // trailing_bits
+    payload.push(0x80);
+
+    // obu_type, obu_extension_flag = 0, obu_has_size_field = 1.
+    let mut obu = vec![(OBU_METADATA << 3) | 0x02];
+    write_leb128(&mut obu, payload.len());
+    obu.extend_from_slice(&payload);
+
+    obu
+}
+
+/// Serializes an AV1 metadata OBU carrying the CEA-708 closed caption `cc_data` triplets, the
+/// AV1 equivalent of [`SeiMessage::cea708_captions`].
+pub fn av1_cea708_captions_obu(cc_data: &[[u8; 3]]) -> Result<Vec<u8>, SeiError> {
+    Ok(av1_itut_t35_metadata_obu(
+        T35_COUNTRY_CODE_US,
+        &cea708_t35_data(cc_data)?,
+    ))
+}
+
+/// Writes `value` using the `leb128()` encoding of AV1.
+fn write_leb128(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Writes a `payloadType` or `payloadSize` value using the `0xff`-prefixed SEI encoding.
+fn write_sei_value(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0xff {
+        out.push(0xff);
+        value -= 0xff;
+    }
+    out.push(value as u8);
+}
+
+/// Minimal MSB-first bit writer.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    num_bits: usize,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.num_bits % 8 == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> (self.num_bits % 8);
+        }
+        self.num_bits += 1;
+    }
+
+    fn write_bits(&mut self, value: u32, num_bits: u8) -> Result<(), SeiError> {
+        if num_bits > 32 || (num_bits < 32 && value >> num_bits != 0) {
+            return Err(SeiError::ValueTooLarge(value, num_bits));
+        }
+
+        for i in (0..num_bits).rev() {
+            self.write_bit((value >> i) & 1 != 0);
+        }
+
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            for i in (0..8).rev() {
+                self.write_bit((byte >> i) & 1 != 0);
+            }
+        }
+    }
+
+    /// Writes `value` as an unsigned Exp-Golomb code.
+    fn write_ue(&mut self, value: u32) {
+        let value = value as u64 + 1;
+        let len = 64 - value.leading_zeros();
+        for _ in 1..len {
+            self.write_bit(false);
+        }
+        for i in (0..len).rev() {
+            self.write_bit((value >> i) & 1 != 0);
+        }
+    }
+
+    fn is_byte_aligned(&self) -> bool {
+        self.num_bits % 8 == 0
+    }
+
+    fn align(&mut self) {
+        while !self.is_byte_aligned() {
+            self.write_bit(false);
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_light_level_nal() {
+        let nal = sei_nal_unit(
+            SeiCodec::Hevc,
+            &[SeiMessage::ContentLightLevel(ContentLightLevel {
+                max_content_light_level: 1000,
+                max_pic_average_light_level: 400,
+            })],
+        )
+        .unwrap();
+
+        assert_eq!(
+            nal,
+            vec![0, 0, 0, 1, 0x4e, 0x01, 144, 4, 0x03, 0xe8, 0x01, 0x90, 0x80]
+        );
+    }
+
+    #[test]
+    fn emulation_prevention_and_alignment() {
+        let nal = sei_nal_unit(
+            SeiCodec::H264,
+            &[SeiMessage::UserDataUnregistered {
+                uuid: [0; 16],
+                data: vec![1],
+            }],
+        )
+        .unwrap();
+        assert_eq!(&nal[..12], &[0, 0, 0, 1, 0x06, 5, 17, 0, 0, 3, 0, 0]);
+
+        // ue(0) = 1, then 1-bit delay and offset, then alignment.
+        let bp = SeiMessage::BufferingPeriod(BufferingPeriod {
+            seq_parameter_set_id: 0,
+            initial_cpb_removal_delay_length: 1,
+            initial_cpb_removal_delays: vec![(1, 0)],
+        });
+        assert_eq!(bp.payload(SeiCodec::H264).unwrap(), vec![0b1101_0000]);
+        assert!(bp.payload(SeiCodec::Hevc).is_err());
+    }
+
+    #[test]
+    fn cea708_captions() {
+        let cc_data = [[0xfc, 0x94, 0x20], [0xfd, 0x80, 0x80]];
+        let message = SeiMessage::cea708_captions(&cc_data).unwrap();
+        assert_eq!(message.payload_type(), 4);
+        assert_eq!(
+            message.payload(SeiCodec::H264).unwrap(),
+            vec![
+                0xb5, 0x00, 0x31, b'G', b'A', b'9', b'4', 0x03, 0xc2, 0xff, 0xfc, 0x94, 0x20, 0xfd,
+                0x80, 0x80, 0xff
+            ]
+        );
+
+        let obu = av1_cea708_captions_obu(&cc_data).unwrap();
+        assert_eq!(&obu[..4], &[0x2a, 19, 4, 0xb5]);
+        assert_eq!(obu.last(), Some(&0x80));
+
+        assert!(matches!(
+            SeiMessage::cea708_captions(&[]),
+            Err(SeiError::InvalidCaptionCount(0))
+        ));
+        assert!(matches!(
+            av1_cea708_captions_obu(&[[0; 3]; 32]),
+            Err(SeiError::InvalidCaptionCount(32))
+        ));
+
+        let mut leb128 = vec![];
+        write_leb128(&mut leb128, 300);
+        assert_eq!(leb128, vec![0xac, 0x02]);
+    }
+}
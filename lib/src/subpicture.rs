@@ -0,0 +1,169 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use bitflags::bitflags;
+use log::error;
+
+use crate::bindings;
+use crate::display::Display;
+use crate::va_check;
+use crate::Image;
+use crate::Surface;
+use crate::SurfaceMemoryDescriptor;
+use crate::VaError;
+
+bitflags! {
+    /// Flags controlling how a [`Subpicture`] is blended, aka `VA_SUBPICTURE_*`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SubpictureFlags: u32 {
+        /// Pixels matching the chroma key set with [`Subpicture::set_chromakey`] are transparent.
+        const CHROMA_KEYING = bindings::VA_SUBPICTURE_CHROMA_KEYING;
+        /// The alpha value set with [`Subpicture::set_global_alpha`] is applied to the whole
+        /// subpicture.
+        const GLOBAL_ALPHA = bindings::VA_SUBPICTURE_GLOBAL_ALPHA;
+        /// The destination rectangle is expressed in screen coordinates instead of surface
+        /// coordinates.
+        const DESTINATION_IS_SCREEN_COORD = bindings::VA_SUBPICTURE_DESTINATION_IS_SCREEN_COORD;
+    }
+}
+
+/// Wrapper around a `VASubpictureID`.
+///
+/// A subpicture is an image (e.g. an OSD or a subtitle) that the driver blends on top of the
+/// surfaces it is associated with when they are presented. The content of the subpicture is
+/// provided by an [`Image`], which must outlive it.
+pub struct Subpicture<'a> {
+    display: Rc<Display>,
+    id: bindings::VASubpictureID,
+    /// The image providing the content of the subpicture must stay alive as long as we do.
+    phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> Subpicture<'a> {
+    /// Creates a subpicture displaying the content of `image` by wrapping around a
+    /// `vaCreateSubpicture` call.
+    ///
+    /// The format of `image` must be one of those returned by
+    /// [`Display::query_subpicture_formats`].
+    pub fn new(image: &'a Image<'_>) -> Result<Self, VaError> {
+        let display = Rc::clone(image.display());
+        let mut id = 0;
+
+        // Safe because `display` represents a valid `VADisplay` and `image` a valid `VAImage`.
+        va_check(unsafe {
+            bindings::vaCreateSubpicture(display.handle(), image.image().image_id, &mut id)
+        })?;
+
+        Ok(Self {
+            display,
+            id,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the ID of this subpicture.
+    pub fn id(&self) -> bindings::VASubpictureID {
+        self.id
+    }
+
+    /// Replaces the content of this subpicture with `image`. Wrapper over `vaSetSubpictureImage`.
+    pub fn set_image(&mut self, image: &'a Image<'_>) -> Result<(), VaError> {
+        // Safe because `self` represents a valid `VASubpicture` and `image` a valid `VAImage`.
+        va_check(unsafe {
+            bindings::vaSetSubpictureImage(self.display.handle(), self.id, image.image().image_id)
+        })
+    }
+
+    /// Sets the chroma key of this subpicture. Wrapper over `vaSetSubpictureChromakey`.
+    ///
+    /// Pixels whose value, once masked with `mask`, falls between `min` and `max` are considered
+    /// transparent when the subpicture is associated with [`SubpictureFlags::CHROMA_KEYING`].
+    pub fn set_chromakey(&self, min: u32, max: u32, mask: u32) -> Result<(), VaError> {
+        // Safe because `self` represents a valid `VASubpicture`.
+        va_check(unsafe {
+            bindings::vaSetSubpictureChromakey(self.display.handle(), self.id, min, max, mask)
+        })
+    }
+
+    /// Sets the global alpha of this subpicture, between `0.0` (transparent) and `1.0` (opaque).
+    /// Wrapper over `vaSetSubpictureGlobalAlpha`.
+    ///
+    /// This value is only used when the subpicture is associated with
+    /// [`SubpictureFlags::GLOBAL_ALPHA`].
+    pub fn set_global_alpha(&self, global_alpha: f32) -> Result<(), VaError> {
+        // Safe because `self` represents a valid `VASubpicture`.
+        va_check(unsafe {
+            bindings::vaSetSubpictureGlobalAlpha(self.display.handle(), self.id, global_alpha)
+        })
+    }
+
+    /// Associates this subpicture with `surfaces`. Wrapper over `vaAssociateSubpicture`.
+    ///
+    /// `src` is the region of the subpicture to blend, and `dest` the region of the surfaces it
+    /// will be blended into.
+    pub fn associate<D: SurfaceMemoryDescriptor>(
+        &self,
+        surfaces: &[&Surface<D>],
+        src: bindings::VARectangle,
+        dest: bindings::VARectangle,
+        flags: SubpictureFlags,
+    ) -> Result<(), VaError> {
+        let mut surface_ids: Vec<_> = surfaces.iter().map(|s| s.id()).collect();
+
+        // Safe because `self` represents a valid `VASubpicture` and the length of `surface_ids` is
+        // passed along with it.
+        va_check(unsafe {
+            bindings::vaAssociateSubpicture(
+                self.display.handle(),
+                self.id,
+                surface_ids.as_mut_ptr(),
+                surface_ids.len() as i32,
+                src.x,
+                src.y,
+                src.width,
+                src.height,
+                dest.x,
+                dest.y,
+                dest.width,
+                dest.height,
+                flags.bits(),
+            )
+        })
+    }
+
+    /// Removes the association between this subpicture and `surfaces`. Wrapper over
+    /// `vaDeassociateSubpicture`.
+    pub fn deassociate<D: SurfaceMemoryDescriptor>(
+        &self,
+        surfaces: &[&Surface<D>],
+    ) -> Result<(), VaError> {
+        let mut surface_ids: Vec<_> = surfaces.iter().map(|s| s.id()).collect();
+
+        // Safe because `self` represents a valid `VASubpicture` and the length of `surface_ids` is
+        // passed along with it.
+        va_check(unsafe {
+            bindings::vaDeassociateSubpicture(
+                self.display.handle(),
+                self.id,
+                surface_ids.as_mut_ptr(),
+                surface_ids.len() as i32,
+            )
+        })
+    }
+}
+
+impl<'a> Drop for Subpicture<'a> {
+    fn drop(&mut self) {
+        // Safe because `self` represents a valid `VASubpicture`.
+        let status =
+            va_check(unsafe { bindings::vaDestroySubpicture(self.display.handle(), self.id) });
+
+        if status.is_err() {
+            error!("vaDestroySubpicture failed: {}", status.unwrap_err());
+        }
+    }
+}
@@ -2,18 +2,35 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use std::alloc::Layout;
 use std::any::Any;
+use std::cell::Cell;
+use std::os::fd::AsRawFd;
 use std::os::fd::FromRawFd;
 use std::os::fd::OwnedFd;
 use std::os::raw::c_void;
+use std::ptr::NonNull;
 use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+use bitflags::bitflags;
+use thiserror::Error;
 
 use crate::bindings;
+use crate::copy;
+use crate::copy::CopyObject;
 use crate::display::Display;
+use crate::image::copy_image_planes;
 use crate::va_check;
+use crate::CopyMode;
+use crate::Image;
+use crate::PlaneLayout;
+use crate::ReadSurfaceError;
 use crate::UsageHint;
 use crate::VASurfaceID;
 use crate::VaError;
+use crate::VaObjectKind;
 
 /// Trait describing a memory backing for surfaces.
 ///
@@ -34,12 +51,32 @@ pub trait SurfaceMemoryDescriptor {
     fn add_attrs(&mut self, attrs: &mut Vec<bindings::VASurfaceAttrib>) -> Option<Box<dyn Any>>;
 }
 
+bitflags! {
+    /// Flags controlling how a surface is exported, aka `VA_EXPORT_SURFACE_*`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ExportSurfaceFlags: u32 {
+        /// The exported surface will be read from.
+        const READ_ONLY = bindings::VA_EXPORT_SURFACE_READ_ONLY;
+        /// The exported surface will be written to.
+        const WRITE_ONLY = bindings::VA_EXPORT_SURFACE_WRITE_ONLY;
+        /// The exported surface will be read from and written to.
+        const READ_WRITE = bindings::VA_EXPORT_SURFACE_READ_WRITE;
+        /// Export each plane as a separate layer, e.g. for import as separate R8/GR88 images.
+        const SEPARATE_LAYERS = bindings::VA_EXPORT_SURFACE_SEPARATE_LAYERS;
+        /// Export all the planes as a single layer, e.g. for import as a single NV12 image.
+        const COMPOSED_LAYERS = bindings::VA_EXPORT_SURFACE_COMPOSED_LAYERS;
+    }
+}
+
 /// VA memory types, aka `VA_SURFACE_ATTRIB_MEM_TYPE_*`.
 #[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MemoryType {
     Va = bindings::VA_SURFACE_ATTRIB_MEM_TYPE_VA,
     V4L2 = bindings::VA_SURFACE_ATTRIB_MEM_TYPE_V4L2,
     UserPtr = bindings::VA_SURFACE_ATTRIB_MEM_TYPE_USER_PTR,
+    /// Legacy DRM PRIME import, described by a `VASurfaceAttribExternalBuffers`.
+    DrmPrime = bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME,
     DrmPrime2 = bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME_2,
 }
 
@@ -95,6 +132,536 @@ where
     }
 }
 
+/// Memory type used to import a DMA-BUF into a surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmabufImportMemoryType {
+    /// `VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME_2`, described by a `VADRMPRIMESurfaceDescriptor`.
+    DrmPrime2,
+    /// `VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME`, described by a `VASurfaceAttribExternalBuffers`.
+    /// Used by older drivers that do not support `DrmPrime2`. Format modifiers are not supported
+    /// with this memory type.
+    DrmPrime,
+}
+
+impl DmabufImportMemoryType {
+    /// Selects the memory type to use given the `VA_SURFACE_ATTRIB_MEM_TYPE_*` bits supported by
+    /// the driver, preferring `DrmPrime2`.
+    pub fn select(supported_memory_types: u32) -> Option<Self> {
+        if supported_memory_types & bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME_2 != 0 {
+            Some(Self::DrmPrime2)
+        } else if supported_memory_types & bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME != 0 {
+            Some(Self::DrmPrime)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the memory type to set for `VASurfaceAttribMemoryType`.
+    pub fn memory_type(&self) -> MemoryType {
+        match self {
+            Self::DrmPrime2 => MemoryType::DrmPrime2,
+            Self::DrmPrime => MemoryType::DrmPrime,
+        }
+    }
+}
+
+/// Error returned by [`DmabufImportDescriptor::set_protected`].
+#[derive(Debug, Error)]
+pub enum ProtectedImportError {
+    #[error("protected DMA-BUFs require VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME support")]
+    DrmPrimeNotSupported,
+}
+
+/// Layout of a plane of a [`DmabufImportDescriptor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmabufPlane {
+    pub offset: u32,
+    pub pitch: u32,
+}
+
+/// Memory layout of a surface, as returned by [`Surface::layout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurfaceLayout {
+    /// Pixel format (`VA_FOURCC_*`) of the surface.
+    pub fourcc: u32,
+    /// Total size of the memory backing the surface, in bytes.
+    pub size: u32,
+    /// Offset and pitch of each plane.
+    pub planes: Vec<DmabufPlane>,
+}
+
+/// A single-object DMA-BUF to import as the memory backing of a surface, using whichever of the
+/// modern or legacy import paths the driver supports.
+///
+/// The same DMA-BUF can be imported several times with different format interpretations using
+/// [`DmabufImportDescriptor::view`] and [`DmabufImportDescriptor::plane_view`]. The views share
+/// the file descriptor, which is closed once the last of them is dropped.
+pub struct DmabufImportDescriptor {
+    memory_type: DmabufImportMemoryType,
+    fourcc: u32,
+    drm_format: u32,
+    width: u32,
+    height: u32,
+    fd: Rc<OwnedFd>,
+    size: u32,
+    drm_format_modifier: u64,
+    planes: Vec<DmabufPlane>,
+    protected: bool,
+}
+
+impl DmabufImportDescriptor {
+    /// Creates a descriptor importing the `size` bytes of `fd`, containing a `width`x`height`
+    /// frame of format `fourcc` (`VA_FOURCC_*`) and `drm_format` (`DRM_FORMAT_*`) with up to 4
+    /// `planes`.
+    ///
+    /// `memory_type` can be obtained with [`crate::Config::dmabuf_import_memory_type`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        memory_type: DmabufImportMemoryType,
+        fourcc: u32,
+        drm_format: u32,
+        width: u32,
+        height: u32,
+        fd: OwnedFd,
+        size: u32,
+        drm_format_modifier: u64,
+        planes: Vec<DmabufPlane>,
+    ) -> Self {
+        Self {
+            memory_type,
+            fourcc,
+            drm_format,
+            width,
+            height,
+            fd: Rc::new(fd),
+            size,
+            drm_format_modifier,
+            planes,
+            protected: false,
+        }
+    }
+
+    /// Returns a descriptor importing the same DMA-BUF as `self`, but interpreting it as a
+    /// `width`x`height` frame of format `fourcc` and `drm_format` with `planes`.
+    ///
+    /// This allows e.g. importing an NV12 buffer once as NV12 for video processing, and once per
+    /// plane as R8 and GR88 for shader-based processing.
+    pub fn view(
+        &self,
+        fourcc: u32,
+        drm_format: u32,
+        width: u32,
+        height: u32,
+        planes: Vec<DmabufPlane>,
+    ) -> Self {
+        Self {
+            memory_type: self.memory_type,
+            fourcc,
+            drm_format,
+            width,
+            height,
+            fd: Rc::clone(&self.fd),
+            size: self.size,
+            drm_format_modifier: self.drm_format_modifier,
+            planes,
+            protected: self.protected,
+        }
+    }
+
+    /// Returns a single-plane view of the `index`-th plane of `self`, interpreted as a
+    /// `width`x`height` frame of format `fourcc` and `drm_format`, or `None` if `self` has no such
+    /// plane.
+    pub fn plane_view(
+        &self,
+        index: usize,
+        fourcc: u32,
+        drm_format: u32,
+        width: u32,
+        height: u32,
+    ) -> Option<Self> {
+        let plane = *self.planes.get(index)?;
+
+        Some(self.view(fourcc, drm_format, width, height, vec![plane]))
+    }
+
+    /// Returns the memory type used to import this buffer.
+    pub fn memory_type(&self) -> DmabufImportMemoryType {
+        self.memory_type
+    }
+
+    /// Marks the DMA-BUF as protected (i.e. secure) memory, e.g. for the output buffers of
+    /// DRM-protected playback, using `VA_SURFACE_EXTBUF_DESC_PROTECTED`.
+    ///
+    /// Since only `VASurfaceAttribExternalBuffers` can carry that flag, protected buffers are
+    /// always imported using [`DmabufImportMemoryType::DrmPrime`], which must be among the
+    /// `supported_memory_types` of the driver (see [`crate::Config::supported_memory_types`]).
+    pub fn set_protected(
+        &mut self,
+        protected: bool,
+        supported_memory_types: u32,
+    ) -> Result<(), ProtectedImportError> {
+        if protected {
+            if supported_memory_types & bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME == 0 {
+                return Err(ProtectedImportError::DrmPrimeNotSupported);
+            }
+            self.memory_type = DmabufImportMemoryType::DrmPrime;
+        }
+        self.protected = protected;
+
+        Ok(())
+    }
+
+    /// Returns whether the DMA-BUF is imported as protected memory.
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    /// Returns the plane offsets and pitches, padded to the 4 entries expected by libva.
+    fn offsets_and_pitches(&self) -> ([u32; 4], [u32; 4]) {
+        let mut offsets = [0u32; 4];
+        let mut pitches = [0u32; 4];
+
+        for (i, plane) in self.planes.iter().take(4).enumerate() {
+            offsets[i] = plane.offset;
+            pitches[i] = plane.pitch;
+        }
+
+        (offsets, pitches)
+    }
+}
+
+impl SurfaceMemoryDescriptor for DmabufImportDescriptor {
+    fn add_attrs(&mut self, attrs: &mut Vec<bindings::VASurfaceAttrib>) -> Option<Box<dyn Any>> {
+        let (offset, pitch) = self.offsets_and_pitches();
+        let num_planes = self.planes.len().min(4) as u32;
+
+        attrs.push(bindings::VASurfaceAttrib::new_memory_type(
+            self.memory_type.memory_type(),
+        ));
+
+        match self.memory_type {
+            DmabufImportMemoryType::DrmPrime2 => {
+                let mut desc = Box::new(bindings::VADRMPRIMESurfaceDescriptor {
+                    fourcc: self.fourcc,
+                    width: self.width,
+                    height: self.height,
+                    num_objects: 1,
+                    num_layers: 1,
+                    ..Default::default()
+                });
+                desc.objects[0].fd = self.fd.as_raw_fd();
+                desc.objects[0].size = self.size;
+                desc.objects[0].drm_format_modifier = self.drm_format_modifier;
+                desc.layers[0].drm_format = self.drm_format;
+                desc.layers[0].num_planes = num_planes;
+                desc.layers[0].offset = offset;
+                desc.layers[0].pitch = pitch;
+
+                attrs.push(bindings::VASurfaceAttrib::new_buffer_descriptor(
+                    desc.as_mut(),
+                ));
+
+                Some(desc)
+            }
+            DmabufImportMemoryType::DrmPrime => {
+                // The descriptor points to the array of buffers, which must also be kept alive.
+                let mut buffers = Box::new([self.fd.as_raw_fd() as _]);
+                let mut desc = Box::new(bindings::VASurfaceAttribExternalBuffers {
+                    pixel_format: self.fourcc,
+                    width: self.width,
+                    height: self.height,
+                    data_size: self.size,
+                    num_planes,
+                    pitches: pitch,
+                    offsets: offset,
+                    buffers: buffers.as_mut_ptr(),
+                    num_buffers: 1,
+                    flags: if self.protected {
+                        bindings::VA_SURFACE_EXTBUF_DESC_PROTECTED
+                    } else {
+                        0
+                    },
+                    private_data: std::ptr::null_mut(),
+                });
+
+                attrs.push(bindings::VASurfaceAttrib::new_buffer_descriptor(
+                    desc.as_mut(),
+                ));
+
+                Some(Box::new((desc, buffers)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum UserPtrDescriptorError {
+    #[error("between 1 and 4 planes are required, got {0}")]
+    InvalidPlaneCount(usize),
+    #[error("the planes do not fit in memory")]
+    SizeOverflow,
+    #[error("the memory is not aligned to {0} bytes")]
+    Misaligned(usize),
+    #[error("the memory is {size} bytes but the planes require {required}")]
+    TooSmall { size: usize, required: usize },
+}
+
+/// Owner of the memory of a [`UserPtrDescriptor`].
+enum UserPtrMemory {
+    /// Memory allocated by the descriptor with this layout.
+    Allocated(Layout),
+    /// Memory provided by the caller, released when dropped.
+    Caller(Box<dyn AsMut<[u8]>>),
+}
+
+/// CPU memory to use as the backing of a surface, using `VA_SURFACE_ATTRIB_MEM_TYPE_USER_PTR`.
+///
+/// The memory is either allocated with the page alignment required by drivers, or provided by the
+/// caller with [`UserPtrDescriptor::from_memory`]. It can be filled by the CPU, e.g. with
+/// software-produced frames, before encoding them without an extra upload copy. The caller must
+/// make sure the surface is not being processed by the hardware while it accesses the memory.
+pub struct UserPtrDescriptor {
+    memory: NonNull<u8>,
+    size: usize,
+    owner: UserPtrMemory,
+    fourcc: u32,
+    width: u32,
+    height: u32,
+    planes: Vec<PlaneLayout>,
+}
+
+impl UserPtrDescriptor {
+    /// Alignment of the allocated memory.
+    pub const ALIGNMENT: usize = 4096;
+
+    /// Allocates zeroed memory for a `width`x`height` frame of format `fourcc` (`VA_FOURCC_*`)
+    /// made of `planes`, which can be computed with [`crate::SurfaceAlignment::plane_layouts`].
+    pub fn new(
+        fourcc: u32,
+        width: u32,
+        height: u32,
+        planes: Vec<PlaneLayout>,
+    ) -> Result<Self, UserPtrDescriptorError> {
+        let size = Self::required_size(&planes)?;
+
+        let layout = Layout::from_size_align(size.max(1), Self::ALIGNMENT)
+            .map_err(|_| UserPtrDescriptorError::SizeOverflow)?;
+        // Safe because `layout` has a non-zero size.
+        let memory = NonNull::new(unsafe { std::alloc::alloc_zeroed(layout) })
+            .unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+
+        Ok(Self {
+            memory,
+            size: layout.size(),
+            owner: UserPtrMemory::Allocated(layout),
+            fourcc,
+            width,
+            height,
+            planes,
+        })
+    }
+
+    /// Uses `memory`, provided by the caller, for a `width`x`height` frame of format `fourcc`
+    /// (`VA_FOURCC_*`) made of `planes`. This allows encoding frames from memory produced
+    /// elsewhere, e.g. shared memory mapped from another process, without copying them.
+    ///
+    /// `memory` must start on an [`UserPtrDescriptor::ALIGNMENT`] boundary and be large enough
+    /// for `planes`. It is owned by the descriptor, and thus by the surface it is used with,
+    /// and released when the surface is dropped.
+    pub fn from_memory(
+        mut memory: Box<dyn AsMut<[u8]>>,
+        fourcc: u32,
+        width: u32,
+        height: u32,
+        planes: Vec<PlaneLayout>,
+    ) -> Result<Self, UserPtrDescriptorError> {
+        let required = Self::required_size(&planes)?;
+
+        // The slice is only queried once: the boxed owner is never moved out of, so the memory
+        // it points to stays at the same address for the lifetime of the descriptor.
+        let slice = (*memory).as_mut();
+        if slice.is_empty() || slice.len() < required {
+            return Err(UserPtrDescriptorError::TooSmall {
+                size: slice.len(),
+                required: required.max(1),
+            });
+        }
+        if slice.as_ptr() as usize % Self::ALIGNMENT != 0 {
+            return Err(UserPtrDescriptorError::Misaligned(Self::ALIGNMENT));
+        }
+        let size = slice.len();
+        // Safe because `slice` is non-empty, hence its pointer is non-null.
+        let ptr = unsafe { NonNull::new_unchecked(slice.as_mut_ptr()) };
+
+        Ok(Self {
+            memory: ptr,
+            size,
+            owner: UserPtrMemory::Caller(memory),
+            fourcc,
+            width,
+            height,
+            planes,
+        })
+    }
+
+    /// Returns the number of bytes needed to hold `planes`.
+    fn required_size(planes: &[PlaneLayout]) -> Result<usize, UserPtrDescriptorError> {
+        if planes.is_empty() || planes.len() > 4 {
+            return Err(UserPtrDescriptorError::InvalidPlaneCount(planes.len()));
+        }
+
+        planes
+            .iter()
+            .map(|plane| {
+                (plane.pitch as usize)
+                    .checked_mul(plane.height as usize)
+                    .and_then(|size| size.checked_add(plane.offset as usize))
+            })
+            .try_fold(0usize, |max, end| end.map(|end| max.max(end)))
+            .ok_or(UserPtrDescriptorError::SizeOverflow)
+    }
+
+    /// Returns the layout of the planes within the memory.
+    pub fn planes(&self) -> &[PlaneLayout] {
+        &self.planes
+    }
+
+    /// Returns the memory backing the surface.
+    pub fn as_slice(&self) -> &[u8] {
+        // Safe because `memory` is a live allocation of `size` bytes.
+        unsafe { std::slice::from_raw_parts(self.memory.as_ptr(), self.size) }
+    }
+
+    /// Returns the memory backing the surface for modification.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // Safe because `memory` is a live allocation of `size` bytes, and we are borrowed
+        // mutably.
+        unsafe { std::slice::from_raw_parts_mut(self.memory.as_ptr(), self.size) }
+    }
+}
+
+impl Drop for UserPtrDescriptor {
+    fn drop(&mut self) {
+        // Memory provided by the caller is released when its owner is dropped.
+        if let UserPtrMemory::Allocated(layout) = self.owner {
+            // Safe because `memory` has been allocated with `layout`. The descriptor is owned by
+            // its surface, which has been destroyed by now.
+            unsafe { std::alloc::dealloc(self.memory.as_ptr(), layout) };
+        }
+    }
+}
+
+impl SurfaceMemoryDescriptor for UserPtrDescriptor {
+    fn add_attrs(&mut self, attrs: &mut Vec<bindings::VASurfaceAttrib>) -> Option<Box<dyn Any>> {
+        let mut offsets = [0u32; 4];
+        let mut pitches = [0u32; 4];
+        for (i, plane) in self.planes.iter().enumerate() {
+            offsets[i] = plane.offset;
+            pitches[i] = plane.pitch;
+        }
+
+        // The descriptor points to the array of buffers, which must also be kept alive.
+        let mut buffers = Box::new([self.memory.as_ptr() as _]);
+        let mut desc = Box::new(bindings::VASurfaceAttribExternalBuffers {
+            pixel_format: self.fourcc,
+            width: self.width,
+            height: self.height,
+            data_size: self.size as u32,
+            num_planes: self.planes.len() as u32,
+            pitches,
+            offsets,
+            buffers: buffers.as_mut_ptr(),
+            num_buffers: 1,
+            flags: 0,
+            private_data: std::ptr::null_mut(),
+        });
+
+        attrs.push(bindings::VASurfaceAttrib::new_memory_type(
+            MemoryType::UserPtr,
+        ));
+        attrs.push(bindings::VASurfaceAttrib::new_buffer_descriptor(
+            desc.as_mut(),
+        ));
+
+        Some(Box::new((desc, buffers)))
+    }
+}
+
+/// Driver-allocated surface memory using one of a list of acceptable DRM format modifiers, set
+/// with the `VASurfaceAttribDRMFormatModifiers` attribute.
+///
+/// This allows allocating surfaces with a tiling that a consumer (e.g. a Wayland compositor or a
+/// Vulkan device) can import. The modifier chosen by the driver can be read back from the
+/// descriptor returned by [`Surface::export_prime`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrmFormatModifiers(Vec<u64>);
+
+impl DrmFormatModifiers {
+    /// Creates a descriptor letting the driver choose among `modifiers` (`DRM_FORMAT_MOD_*`).
+    pub fn new(modifiers: Vec<u64>) -> Self {
+        Self(modifiers)
+    }
+
+    /// Returns the list of acceptable modifiers.
+    pub fn modifiers(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl SurfaceMemoryDescriptor for DrmFormatModifiers {
+    fn add_attrs(&mut self, attrs: &mut Vec<bindings::VASurfaceAttrib>) -> Option<Box<dyn Any>> {
+        // The list points to the array of modifiers, which must also be kept alive.
+        let mut modifiers = self.0.clone().into_boxed_slice();
+        let mut list = Box::new(bindings::VADRMFormatModifierList {
+            num_modifiers: modifiers.len() as u32,
+            modifiers: modifiers.as_mut_ptr(),
+        });
+
+        attrs.push(bindings::VASurfaceAttrib {
+            type_: bindings::VASurfaceAttribType::VASurfaceAttribDRMFormatModifiers,
+            flags: bindings::VA_SURFACE_ATTRIB_SETTABLE,
+            value: bindings::VAGenericValue::from(list.as_mut() as *mut _ as *mut c_void),
+        });
+
+        Some(Box::new((list, modifiers)))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SyncSurfaceError {
+    #[error("timeout expired before the surface became ready")]
+    Timeout,
+    #[error("error while syncing surface: {0}")]
+    VaError(#[from] VaError),
+}
+
+/// Status of a surface, as returned by `vaQuerySurfaceStatus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceStatus {
+    /// Rendering is still in progress.
+    Rendering,
+    /// The surface is being displayed.
+    Displaying,
+    /// No pending operations on the surface.
+    Ready,
+    /// The surface was not displayed because of a missed deadline.
+    Skipped,
+    /// A status value not known to this crate.
+    Other(bindings::VASurfaceStatus::Type),
+}
+
+impl From<bindings::VASurfaceStatus::Type> for SurfaceStatus {
+    fn from(status: bindings::VASurfaceStatus::Type) -> Self {
+        match status {
+            bindings::VASurfaceStatus::VASurfaceRendering => Self::Rendering,
+            bindings::VASurfaceStatus::VASurfaceDisplaying => Self::Displaying,
+            bindings::VASurfaceStatus::VASurfaceReady => Self::Ready,
+            bindings::VASurfaceStatus::VASurfaceSkipped => Self::Skipped,
+            other => Self::Other(other),
+        }
+    }
+}
+
 /// Decode error type aka `VADecodeErrorType`
 #[repr(u32)]
 #[derive(Debug)]
@@ -127,6 +694,9 @@ pub struct Surface<D: SurfaceMemoryDescriptor> {
     descriptor: D,
     width: u32,
     height: u32,
+    user_data: Option<Box<dyn Any>>,
+    generation: Cell<u64>,
+    last_used: Cell<Option<Instant>>,
 }
 
 impl From<i32> for bindings::VAGenericValue {
@@ -191,6 +761,48 @@ impl bindings::VASurfaceAttrib {
     }
 }
 
+/// Error returned when only some of the requested surfaces could be created, e.g. because the
+/// driver ran out of memory.
+pub struct CreateSurfacesError<D: SurfaceMemoryDescriptor> {
+    /// The surfaces that have been successfully created. Dropping them releases their memory.
+    pub surfaces: Vec<Surface<D>>,
+    /// The number of surfaces that were requested.
+    pub requested: usize,
+    /// The error returned by `vaCreateSurfaces` for the first surface that could not be created.
+    pub error: VaError,
+}
+
+impl<D: SurfaceMemoryDescriptor> CreateSurfacesError<D> {
+    /// Returns the number of surfaces that have been created.
+    pub fn created(&self) -> usize {
+        self.surfaces.len()
+    }
+}
+
+impl<D: SurfaceMemoryDescriptor> std::fmt::Debug for CreateSurfacesError<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreateSurfacesError")
+            .field("created", &self.created())
+            .field("requested", &self.requested)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<D: SurfaceMemoryDescriptor> std::fmt::Display for CreateSurfacesError<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "only {} out of {} surfaces could be created: {}",
+            self.created(),
+            self.requested,
+            self.error
+        )
+    }
+}
+
+impl<D: SurfaceMemoryDescriptor> std::error::Error for CreateSurfacesError<D> {}
+
 impl<D: SurfaceMemoryDescriptor> Surface<D> {
     /// Create `Surfaces` by wrapping around a `vaCreateSurfaces` call. This is just a helper for
     /// [`Display::create_surfaces`].
@@ -203,6 +815,31 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
         usage_hint: Option<UsageHint>,
         descriptors: Vec<D>,
     ) -> Result<Vec<Self>, VaError> {
+        Self::new_partial(
+            display,
+            rt_format,
+            va_fourcc,
+            width,
+            height,
+            usage_hint,
+            descriptors,
+        )
+        .map_err(|e| e.error)
+    }
+
+    /// Same as [`Surface::new`], but returns the surfaces that could be created along with the
+    /// error if not all of them could. This is just a helper for
+    /// [`Display::create_surfaces_partial`].
+    pub(crate) fn new_partial(
+        display: Rc<Display>,
+        rt_format: u32,
+        va_fourcc: Option<u32>,
+        width: u32,
+        height: u32,
+        usage_hint: Option<UsageHint>,
+        descriptors: Vec<D>,
+    ) -> Result<Vec<Self>, CreateSurfacesError<D>> {
+        let requested = descriptors.len();
         let mut surfaces = vec![];
 
         for mut descriptor in descriptors {
@@ -238,14 +875,26 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
                     attrs.len() as u32,
                 )
             }) {
-                Ok(()) => surfaces.push(Self {
-                    display: Rc::clone(&display),
-                    id: surface_id,
-                    descriptor,
-                    width,
-                    height,
-                }),
-                Err(e) => return Err(e),
+                Ok(()) => {
+                    display.register_object(VaObjectKind::Surface, surface_id);
+                    surfaces.push(Self {
+                        display: Rc::clone(&display),
+                        id: surface_id,
+                        descriptor,
+                        width,
+                        height,
+                        user_data: None,
+                        generation: Cell::new(0),
+                        last_used: Cell::new(None),
+                    })
+                }
+                Err(error) => {
+                    return Err(CreateSurfacesError {
+                        surfaces,
+                        requested,
+                        error,
+                    })
+                }
             }
         }
 
@@ -263,7 +912,41 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
     /// is safe to use the render target for a different picture.
     pub fn sync(&self) -> Result<(), VaError> {
         // Safe because `self` represents a valid VASurface.
-        va_check(unsafe { bindings::vaSyncSurface(self.display.handle(), self.id) })
+        self.display.check_device_lost(va_check(unsafe {
+            bindings::vaSyncSurface(self.display.handle(), self.id)
+        }))
+    }
+
+    /// Wrapper around `vaSyncSurface2` that blocks until all pending operations on the render
+    /// target have been completed, or until `timeout` has elapsed.
+    ///
+    /// Timeouts larger than `u64::MAX` nanoseconds are clamped. If the driver does not implement
+    /// `vaSyncSurface2`, or with VA-API versions older than 1.9 which lack it, this falls back to
+    /// the blocking [`Surface::sync`].
+    #[cfg(libva_1_9_or_higher)]
+    pub fn sync_with_timeout(&self, timeout: Duration) -> Result<(), SyncSurfaceError> {
+        let timeout_ns = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+
+        // Safe because `self` represents a valid VASurface.
+        match self.display.check_device_lost(va_check(unsafe {
+            bindings::vaSyncSurface2(self.display.handle(), self.id, timeout_ns)
+        })) {
+            Ok(()) => Ok(()),
+            Err(e) if e.va_status() == bindings::VA_STATUS_ERROR_TIMEDOUT as i32 => {
+                Err(SyncSurfaceError::Timeout)
+            }
+            Err(e) if e.va_status() == bindings::VA_STATUS_ERROR_UNIMPLEMENTED as i32 => {
+                Ok(self.sync()?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Fallback for [`Surface::sync_with_timeout`] without `vaSyncSurface2`, which blocks until
+    /// the surface is ready regardless of `timeout`.
+    #[cfg(not(libva_1_9_or_higher))]
+    pub fn sync_with_timeout(&self, _timeout: Duration) -> Result<(), SyncSurfaceError> {
+        Ok(self.sync()?)
     }
 
     /// Convenience function to return a VASurfaceID vector. Useful to interface with the C API
@@ -283,6 +966,12 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
         Ok(status)
     }
 
+    /// Returns the typed status of the surface without blocking. Useful to poll for completion
+    /// instead of calling [`Surface::sync`].
+    pub fn status(&self) -> Result<SurfaceStatus, VaError> {
+        self.query_status().map(SurfaceStatus::from)
+    }
+
     pub fn query_error(&self) -> Result<Vec<SurfaceDecodeMBError>, VaError> {
         let mut raw: *const bindings::VASurfaceDecodeMBErrors = std::ptr::null();
 
@@ -341,13 +1030,185 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
         self.id
     }
 
+    /// Returns the pitch and offset of each plane of this surface and the size of its memory,
+    /// without mapping it.
+    ///
+    /// The layout is obtained from the metadata of a derived image, or from an exported PRIME
+    /// descriptor if the surface cannot be derived.
+    pub fn layout(&self) -> Result<SurfaceLayout, VaError> {
+        // An all-zero byte-pattern is a valid initial value for `VAImage`.
+        let mut image: bindings::VAImage = Default::default();
+
+        // Safe because `self` represents a valid VASurface.
+        if va_check(unsafe { bindings::vaDeriveImage(self.display.handle(), self.id, &mut image) })
+            .is_ok()
+        {
+            // Safe because `image` is a valid `VAImage` that has not been mapped.
+            unsafe { bindings::vaDestroyImage(self.display.handle(), image.image_id) };
+
+            return Ok(SurfaceLayout {
+                fourcc: image.format.fourcc,
+                size: image.data_size,
+                planes: (0..(image.num_planes as usize).min(3))
+                    .map(|i| DmabufPlane {
+                        offset: image.offsets[i],
+                        pitch: image.pitches[i],
+                    })
+                    .collect(),
+            });
+        }
+
+        let desc = self.export_prime()?;
+        let planes = desc
+            .layers
+            .first()
+            .map(|layer| {
+                (0..layer.num_planes.min(4) as usize)
+                    .map(|i| DmabufPlane {
+                        offset: layer.offset[i],
+                        pitch: layer.pitch[i],
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(SurfaceLayout {
+            fourcc: desc.fourcc,
+            size: desc.objects.iter().map(|object| object.size).sum(),
+            planes,
+        })
+    }
+
+    /// Reads the contents of this surface into `dst`, converted to `format` if needed, with the
+    /// planes laid out as described by `dst_planes`.
+    ///
+    /// The surface is derived if its format is already `format`, and copied into an image of
+    /// `format` otherwise. Each line is copied up to the smallest of the image and destination
+    /// pitches.
+    pub fn read_to(
+        &self,
+        format: bindings::VAImageFormat,
+        dst: &mut [u8],
+        dst_planes: &[PlaneLayout],
+    ) -> Result<(), ReadSurfaceError> {
+        let size = self.size();
+        let derived = Image::derive_from(self, size)
+            .ok()
+            .filter(|image| image.image().format.fourcc == format.fourcc);
+        let image = match derived {
+            Some(image) => image,
+            None => Image::create_from(self, format, size, size)?,
+        };
+
+        copy_image_planes(image.image(), image.as_ref(), dst, dst_planes)
+    }
+
+    /// Copies the contents of this surface into `dst` on the GPU using `vaCopy`, waiting for the
+    /// copy to complete before returning. This can be used e.g. to duplicate a reference frame.
+    /// Fails with `VA_STATUS_ERROR_UNIMPLEMENTED` before VA-API 1.10.
+    pub fn copy_to<E: SurfaceMemoryDescriptor>(
+        &self,
+        dst: &Surface<E>,
+        mode: CopyMode,
+    ) -> Result<(), VaError> {
+        copy::copy(
+            &self.display,
+            CopyObject::Surface(dst.id),
+            CopyObject::Surface(self.id),
+            mode,
+        )
+    }
+
     /// Returns the dimensions of this surface.
     pub fn size(&self) -> (u32, u32) {
         (self.width, self.height)
     }
 
-    /// Returns a PRIME descriptor for this surface.
+    /// Returns the generation of this surface, which starts at 0 and is increased every time the
+    /// surface is marked as used or reallocated. Renderers can compare it with the generation
+    /// they last saw to find out whether the contents changed.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// Returns when the surface has last been used, or `None` if it never was.
+    ///
+    /// The last use time is updated when a [`crate::Picture`] targeting the surface is begun or
+    /// synced, and when the surface is marked as used.
+    pub fn last_used(&self) -> Option<Instant> {
+        self.last_used.get()
+    }
+
+    /// Marks the surface as used, e.g. after it has been rendered to, increasing its generation
+    /// and updating its last use time. This is done by [`crate::Picture::sync`] for the target
+    /// surface of the picture.
+    pub fn mark_used(&self) {
+        self.generation.set(self.generation.get() + 1);
+        self.touch();
+    }
+
+    /// Updates the last use time of the surface without changing its generation, e.g. when work
+    /// targeting it is submitted.
+    pub(crate) fn touch(&self) {
+        self.last_used.set(Some(Instant::now()));
+    }
+
+    /// Attaches `data` to this surface, replacing any previously attached data. This allows
+    /// frame-tracking state (e.g. timestamps or crop rectangles) to follow the surface.
+    pub fn set_user_data<T: 'static>(&mut self, data: T) {
+        self.user_data = Some(Box::new(data));
+    }
+
+    /// Returns the data attached to this surface, or `None` if there is none or it is not of type
+    /// `T`.
+    pub fn user_data<T: 'static>(&self) -> Option<&T> {
+        self.user_data.as_ref()?.downcast_ref()
+    }
+
+    /// Returns a mutable reference to the data attached to this surface, or `None` if there is
+    /// none or it is not of type `T`.
+    pub fn user_data_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.user_data.as_mut()?.downcast_mut()
+    }
+
+    /// Detaches and returns the data attached to this surface if it is of type `T`. Data of
+    /// another type is left attached.
+    pub fn take_user_data<T: 'static>(&mut self) -> Option<T> {
+        match self.user_data.take()?.downcast() {
+            Ok(data) => Some(*data),
+            Err(data) => {
+                self.user_data = Some(data);
+                None
+            }
+        }
+    }
+
+    /// Returns a PRIME descriptor for this surface, exported for reading with all the planes in a
+    /// single layer.
     pub fn export_prime(&self) -> Result<DrmPrimeSurfaceDescriptor, VaError> {
+        self.export_prime_with_flags(
+            ExportSurfaceFlags::READ_ONLY | ExportSurfaceFlags::COMPOSED_LAYERS,
+        )
+    }
+
+    /// Returns a token that can be sent to another thread in order to access this surface from
+    /// there. See [`SurfaceHandle`].
+    pub fn to_handle(&self) -> Result<SurfaceHandle, VaError> {
+        self.export_prime_with_flags(
+            ExportSurfaceFlags::READ_WRITE | ExportSurfaceFlags::COMPOSED_LAYERS,
+        )
+        .map(SurfaceHandle)
+    }
+
+    /// Returns a PRIME descriptor for this surface, exported according to `flags`. Wrapper over
+    /// `vaExportSurfaceHandle`.
+    ///
+    /// `flags` must contain one of [`ExportSurfaceFlags::SEPARATE_LAYERS`] or
+    /// [`ExportSurfaceFlags::COMPOSED_LAYERS`].
+    pub fn export_prime_with_flags(
+        &self,
+        flags: ExportSurfaceFlags,
+    ) -> Result<DrmPrimeSurfaceDescriptor, VaError> {
         let mut desc: bindings::VADRMPRIMESurfaceDescriptor = Default::default();
 
         va_check(unsafe {
@@ -355,7 +1216,7 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
                 self.display.handle(),
                 self.id(),
                 bindings::VA_SURFACE_ATTRIB_MEM_TYPE_DRM_PRIME_2,
-                bindings::VA_EXPORT_SURFACE_READ_ONLY | bindings::VA_EXPORT_SURFACE_COMPOSED_LAYERS,
+                flags.bits(),
                 &mut desc as *mut _ as *mut c_void,
             )
         })?;
@@ -407,6 +1268,49 @@ impl<D: SurfaceMemoryDescriptor> Surface<D> {
     }
 }
 
+impl Surface<()> {
+    /// Replaces the driver-allocated memory of the `surfaces` with new surfaces of `rt_format`,
+    /// `va_fourcc` and `width`x`height`, e.g. upon a mid-stream resolution change.
+    ///
+    /// The `Surface` objects are kept, so references held by the caller (e.g. in a pool) remain
+    /// valid along with their user data, but their [`Surface::id`] changes and their generation
+    /// is increased. The new surfaces are all created before the old
+    /// ones are destroyed, so `surfaces` is left untouched if an error occurs. All the surfaces
+    /// are re-created on the display of the first one.
+    pub fn reallocate(
+        surfaces: &mut [Self],
+        rt_format: u32,
+        va_fourcc: Option<u32>,
+        width: u32,
+        height: u32,
+        usage_hint: Option<UsageHint>,
+    ) -> Result<(), VaError> {
+        let Some(display) = surfaces.first().map(|surface| Rc::clone(&surface.display)) else {
+            return Ok(());
+        };
+
+        let new_surfaces = Self::new(
+            display,
+            rt_format,
+            va_fourcc,
+            width,
+            height,
+            usage_hint,
+            vec![(); surfaces.len()],
+        )?;
+
+        for (surface, mut new_surface) in surfaces.iter_mut().zip(new_surfaces) {
+            // The previous surface is destroyed when `new_surface` goes out of scope.
+            new_surface.user_data = surface.user_data.take();
+            new_surface.generation.set(surface.generation.get() + 1);
+            new_surface.last_used.set(surface.last_used.get());
+            std::mem::swap(surface, &mut new_surface);
+        }
+
+        Ok(())
+    }
+}
+
 impl<D: SurfaceMemoryDescriptor> AsRef<D> for Surface<D> {
     fn as_ref(&self) -> &D {
         &self.descriptor
@@ -423,6 +1327,8 @@ impl<D: SurfaceMemoryDescriptor> Drop for Surface<D> {
     fn drop(&mut self) {
         // Safe because `self` represents a valid VASurface.
         unsafe { bindings::vaDestroySurfaces(self.display.handle(), &mut self.id, 1) };
+        self.display
+            .unregister_object(VaObjectKind::Surface, self.id);
     }
 }
 
@@ -443,6 +1349,10 @@ pub struct DrmPrimeSurfaceDescriptorLayer {
 }
 
 /// Safe wrapper around `VADRMPRIMESurfaceDescriptor`.
+///
+/// Besides being returned by [`Surface::export_prime`], this can be used as the memory
+/// descriptor of a surface to import DMA-BUFs made of up to 4 objects, e.g. frames from a camera
+/// with one buffer per plane. The file descriptors are closed when the surface is dropped.
 pub struct DrmPrimeSurfaceDescriptor {
     pub fourcc: u32,
     pub width: u32,
@@ -450,3 +1360,66 @@ pub struct DrmPrimeSurfaceDescriptor {
     pub objects: Vec<DrmPrimeSurfaceDescriptorObject>,
     pub layers: Vec<DrmPrimeSurfaceDescriptorLayer>,
 }
+
+/// A token allowing a surface to be used from another thread, despite `Surface` and `Display`
+/// not being `Send`.
+///
+/// The token holds the DMA-BUFs of the surface, and can be sent to another thread to be attached
+/// to a `Display` living there with [`Display::attach_surface`]. Both surfaces then share the
+/// same memory, which allows e.g. decoding on one thread and reading back on another.
+pub struct SurfaceHandle(DrmPrimeSurfaceDescriptor);
+
+impl SurfaceHandle {
+    /// Returns the PRIME descriptor of the surface.
+    pub fn prime_descriptor(&self) -> &DrmPrimeSurfaceDescriptor {
+        &self.0
+    }
+
+    /// Returns the PRIME descriptor of the surface, to be used as the memory descriptor of a new
+    /// surface.
+    pub(crate) fn into_prime_descriptor(self) -> DrmPrimeSurfaceDescriptor {
+        self.0
+    }
+}
+
+impl DrmPrimeSurfaceDescriptor {
+    /// Returns the DRM format modifier of the first object, i.e. the modifier chosen by the driver
+    /// for an exported surface. Returns `None` if there is no object.
+    pub fn drm_format_modifier(&self) -> Option<u64> {
+        self.objects
+            .first()
+            .map(|object| object.drm_format_modifier)
+    }
+}
+
+impl ExternalBufferDescriptor for DrmPrimeSurfaceDescriptor {
+    const MEMORY_TYPE: MemoryType = MemoryType::DrmPrime2;
+    type DescriptorAttribute = bindings::VADRMPRIMESurfaceDescriptor;
+
+    fn va_surface_attribute(&mut self) -> Self::DescriptorAttribute {
+        let mut desc = bindings::VADRMPRIMESurfaceDescriptor {
+            fourcc: self.fourcc,
+            width: self.width,
+            height: self.height,
+            num_objects: self.objects.len().min(4) as u32,
+            num_layers: self.layers.len().min(4) as u32,
+            ..Default::default()
+        };
+
+        for (i, object) in self.objects.iter().take(4).enumerate() {
+            desc.objects[i].fd = object.fd.as_raw_fd();
+            desc.objects[i].size = object.size;
+            desc.objects[i].drm_format_modifier = object.drm_format_modifier;
+        }
+
+        for (i, layer) in self.layers.iter().take(4).enumerate() {
+            desc.layers[i].drm_format = layer.drm_format;
+            desc.layers[i].num_planes = layer.num_planes;
+            desc.layers[i].object_index = layer.object_index.map(u32::from);
+            desc.layers[i].offset = layer.offset;
+            desc.layers[i].pitch = layer.pitch;
+        }
+
+        desc
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to pick consistent fourcc and render target formats when creating surfaces.
+
+use thiserror::Error;
+
+use crate::bindings;
+
+/// Returns the `VA_RT_FORMAT_*` of the surfaces able to hold frames of format `fourcc`, or `None`
+/// if `fourcc` is not known to this helper.
+pub fn rt_format_for_fourcc(fourcc: u32) -> Option<u32> {
+    let rt_format = match fourcc {
+        bindings::VA_FOURCC_Y800 => bindings::VA_RT_FORMAT_YUV400,
+        bindings::VA_FOURCC_NV12 | bindings::VA_FOURCC_I420 | bindings::VA_FOURCC_YV12 => {
+            bindings::VA_RT_FORMAT_YUV420
+        }
+        bindings::VA_FOURCC_YUY2 | bindings::VA_FOURCC_UYVY | bindings::VA_FOURCC_422H => {
+            bindings::VA_RT_FORMAT_YUV422
+        }
+        bindings::VA_FOURCC_444P => bindings::VA_RT_FORMAT_YUV444,
+        bindings::VA_FOURCC_P010 => bindings::VA_RT_FORMAT_YUV420_10,
+        // There is no 16-bit 4:2:0 render target format, drivers use 12-bit surfaces for P016.
+        bindings::VA_FOURCC_P012 | bindings::VA_FOURCC_P016 => bindings::VA_RT_FORMAT_YUV420_12,
+        bindings::VA_FOURCC_Y210 => bindings::VA_RT_FORMAT_YUV422_10,
+        bindings::VA_FOURCC_Y410 => bindings::VA_RT_FORMAT_YUV444_10,
+        bindings::VA_FOURCC_ARGB
+        | bindings::VA_FOURCC_ABGR
+        | bindings::VA_FOURCC_XRGB
+        | bindings::VA_FOURCC_XBGR
+        | bindings::VA_FOURCC_RGBA
+        | bindings::VA_FOURCC_BGRA
+        | bindings::VA_FOURCC_RGBX
+        | bindings::VA_FOURCC_BGRX => bindings::VA_RT_FORMAT_RGB32,
+        _ => return None,
+    };
+
+    Some(rt_format)
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SurfaceFormatError {
+    #[error("unknown fourcc {0:#010x}")]
+    UnknownFourcc(u32),
+    #[error("fourcc {fourcc:#010x} requires render target format {expected:#x}, got {actual:#x}")]
+    RtFormatMismatch {
+        fourcc: u32,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+/// Checks that surfaces of render target format `rt_format` can hold frames of format `fourcc`.
+pub fn check_fourcc_rt_format(fourcc: u32, rt_format: u32) -> Result<(), SurfaceFormatError> {
+    let expected = rt_format_for_fourcc(fourcc).ok_or(SurfaceFormatError::UnknownFourcc(fourcc))?;
+
+    if expected != rt_format {
+        return Err(SurfaceFormatError::RtFormatMismatch {
+            fourcc,
+            expected,
+            actual: rt_format,
+        });
+    }
+
+    Ok(())
+}
+
+/// High bit depth 4:2:0 surface formats, e.g. for HEVC Main10 or AV1 10-bit decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighBitDepthFormat {
+    /// 10 bits per component, stored in the most significant bits of 16-bit words.
+    P010,
+    /// 12 bits per component, stored in the most significant bits of 16-bit words.
+    P012,
+    /// 16 bits per component.
+    P016,
+}
+
+impl HighBitDepthFormat {
+    /// Returns the format able to hold components of `bit_depth` bits, if any.
+    pub fn for_bit_depth(bit_depth: u32) -> Option<Self> {
+        match bit_depth {
+            9..=10 => Some(Self::P010),
+            11..=12 => Some(Self::P012),
+            13..=16 => Some(Self::P016),
+            _ => None,
+        }
+    }
+
+    /// Returns the `VA_FOURCC_*` of this format.
+    pub fn fourcc(&self) -> u32 {
+        match self {
+            Self::P010 => bindings::VA_FOURCC_P010,
+            Self::P012 => bindings::VA_FOURCC_P012,
+            Self::P016 => bindings::VA_FOURCC_P016,
+        }
+    }
+
+    /// Returns the `VA_RT_FORMAT_*` of the surfaces of this format.
+    pub fn rt_format(&self) -> u32 {
+        match self {
+            Self::P010 => bindings::VA_RT_FORMAT_YUV420_10,
+            Self::P012 | Self::P016 => bindings::VA_RT_FORMAT_YUV420_12,
+        }
+    }
+
+    /// Returns the number of significant bits per component.
+    pub fn bit_depth(&self) -> u32 {
+        match self {
+            Self::P010 => 10,
+            Self::P012 => 12,
+            Self::P016 => 16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fourcc_rt_format_pairing() {
+        for format in [
+            HighBitDepthFormat::P010,
+            HighBitDepthFormat::P012,
+            HighBitDepthFormat::P016,
+        ] {
+            assert_eq!(
+                check_fourcc_rt_format(format.fourcc(), format.rt_format()),
+                Ok(())
+            );
+            assert_eq!(
+                HighBitDepthFormat::for_bit_depth(format.bit_depth()),
+                Some(format)
+            );
+        }
+
+        assert_eq!(
+            check_fourcc_rt_format(bindings::VA_FOURCC_P010, bindings::VA_RT_FORMAT_YUV420),
+            Err(SurfaceFormatError::RtFormatMismatch {
+                fourcc: bindings::VA_FOURCC_P010,
+                expected: bindings::VA_RT_FORMAT_YUV420_10,
+                actual: bindings::VA_RT_FORMAT_YUV420,
+            })
+        );
+        assert_eq!(
+            check_fourcc_rt_format(0, bindings::VA_RT_FORMAT_YUV420),
+            Err(SurfaceFormatError::UnknownFourcc(0))
+        );
+        assert_eq!(HighBitDepthFormat::for_bit_depth(8), None);
+    }
+}
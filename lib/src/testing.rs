@@ -0,0 +1,244 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Small decode test vectors with their expected output, and a runner to check them against the
+//! hardware. This allows validating a driver and crate combination quickly, e.g. in CI labs.
+//!
+//! Only an MPEG-2 vector is bundled for now. Vectors for other codecs must come with CRCs
+//! obtained from a reference decoder, and can be added to [`decode_test_vectors`] as they become
+//! available.
+
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use crate::bindings;
+use crate::BufferType;
+use crate::Display;
+use crate::IQMatrix;
+use crate::IQMatrixBufferMPEG2;
+use crate::Image;
+use crate::MPEG2PictureCodingExtension;
+use crate::Picture;
+use crate::PictureParameter;
+use crate::PictureParameterBufferMPEG2;
+use crate::SliceParameter;
+use crate::SliceParameterBufferMPEG2;
+use crate::UsageHint;
+use crate::VaError;
+
+/// Returns a 32-bit CRC for the visible part of `image`, which must be in NV12 format.
+pub fn crc_nv12_image(image: &Image) -> u32 {
+    let data = image.as_ref();
+    let va_image = image.image();
+    let offsets = &va_image.offsets;
+    let pitches = &va_image.pitches;
+    let width = va_image.width as usize;
+    let height = va_image.height as usize;
+
+    assert_eq!(va_image.format.fourcc, bindings::VA_FOURCC_NV12);
+    assert_eq!(va_image.num_planes, 2);
+
+    let mut hasher = crc32fast::Hasher::new();
+
+    let offset = offsets[0] as usize;
+    let pitch = pitches[0] as usize;
+    let y_plane = data[offset..(offset + pitch * height)]
+        .chunks(pitch)
+        .map(|line| &line[0..width]);
+
+    let offset = offsets[1] as usize;
+    let pitch = pitches[1] as usize;
+    let uv_plane = data[offset..(offset + pitch * height.div_ceil(2))]
+        .chunks(pitch)
+        .map(|line| &line[0..width]);
+
+    for line in y_plane.chain(uv_plane) {
+        hasher.update(line);
+    }
+
+    hasher.finalize()
+}
+
+/// A single frame of a [`DecodeTestVector`].
+pub struct DecodeTestFrame {
+    /// Returns the buffers to submit to decode the frame.
+    pub buffers: fn() -> Vec<BufferType>,
+    /// Expected CRC of the decoded frame, as computed by [`crc_nv12_image`].
+    pub expected_crc: u32,
+}
+
+/// A small bitstream, already split into the buffers to submit for each of its frames.
+pub struct DecodeTestVector {
+    pub name: &'static str,
+    pub profile: bindings::VAProfile::Type,
+    pub rt_format: u32,
+    pub width: u32,
+    pub height: u32,
+    pub frames: Vec<DecodeTestFrame>,
+}
+
+/// Result of a successful run of a [`DecodeTestVector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestVectorOutcome {
+    /// All frames have been decoded with the expected CRC.
+    Passed,
+    /// The profile of the vector is not supported for decoding by the driver.
+    Unsupported,
+}
+
+#[derive(Debug, Error)]
+pub enum TestVectorError {
+    #[error("error while decoding: {0}")]
+    VaError(#[from] VaError),
+    #[error("NV12 images are not supported by the driver")]
+    Nv12NotSupported,
+    #[error("frame {frame}: expected CRC {expected:#010x}, got {actual:#010x}")]
+    CrcMismatch {
+        frame: usize,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl DecodeTestVector {
+    /// Decodes this vector on `display` and checks the CRC of each frame.
+    pub fn run(&self, display: &Rc<Display>) -> Result<TestVectorOutcome, TestVectorError> {
+        let entrypoint = bindings::VAEntrypoint::VAEntrypointVLD;
+
+        if !display.query_config_profiles()?.contains(&self.profile)
+            || !display
+                .query_config_entrypoints(self.profile)?
+                .contains(&entrypoint)
+        {
+            return Ok(TestVectorOutcome::Unsupported);
+        }
+
+        let image_fmt = display
+            .query_image_formats()?
+            .into_iter()
+            .find(|f| f.fourcc == bindings::VA_FOURCC_NV12)
+            .ok_or(TestVectorError::Nv12NotSupported)?;
+
+        let attrs = vec![bindings::VAConfigAttrib {
+            type_: bindings::VAConfigAttribType::VAConfigAttribRTFormat,
+            value: self.rt_format,
+        }];
+        let config = display.create_config(attrs, self.profile, entrypoint)?;
+
+        let mut surfaces = display.create_surfaces(
+            self.rt_format,
+            None,
+            self.width,
+            self.height,
+            Some(UsageHint::USAGE_HINT_DECODER),
+            self.frames.iter().map(|_| ()).collect(),
+        )?;
+        let context =
+            display.create_context(&config, self.width, self.height, Some(&surfaces), true)?;
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let mut picture = Picture::new(i as u64, Rc::clone(&context), surfaces.remove(0));
+            for buffer in (frame.buffers)() {
+                picture.add_buffer(context.create_buffer(buffer)?);
+            }
+
+            let picture = picture
+                .begin()?
+                .render()?
+                .end()?
+                .sync()
+                .map_err(|(e, _)| e)?;
+
+            let resolution = (self.width, self.height);
+            let image = picture.create_image(image_fmt, resolution, resolution)?;
+            let crc = crc_nv12_image(&image);
+            if crc != frame.expected_crc {
+                return Err(TestVectorError::CrcMismatch {
+                    frame: i,
+                    expected: frame.expected_crc,
+                    actual: crc,
+                });
+            }
+        }
+
+        Ok(TestVectorOutcome::Passed)
+    }
+}
+
+/// Returns all the bundled test vectors.
+pub fn decode_test_vectors() -> Vec<DecodeTestVector> {
+    vec![mpeg2_16x16_i_frame()]
+}
+
+/// Single 16x16 MPEG-2 I frame, adapted from libva-utils at decode/mpeg2vldemo.cpp.
+pub fn mpeg2_16x16_i_frame() -> DecodeTestVector {
+    DecodeTestVector {
+        name: "mpeg2_16x16_i_frame",
+        profile: bindings::VAProfile::VAProfileMPEG2Main,
+        rt_format: bindings::VA_RT_FORMAT_YUV420,
+        width: 16,
+        height: 16,
+        frames: vec![DecodeTestFrame {
+            buffers: mpeg2_16x16_i_frame_buffers,
+            expected_crc: 0xa5713e52,
+        }],
+    }
+}
+
+fn mpeg2_16x16_i_frame_buffers() -> Vec<BufferType> {
+    // Slice data of the clip, starting after the sequence and picture headers.
+    let slice_data = vec![
+        0x00, 0x00, 0x01, 0x01, 0x13, 0xe1, 0x00, 0x15, 0x81, 0x54, 0xe0, 0x2a, 0x05, 0x43, 0x00,
+        0x2d, 0x60, 0x18, 0x01, 0x4e, 0x82, 0xb9, 0x58, 0xb1, 0x83, 0x49, 0xa4, 0xa0, 0x2e, 0x05,
+        0x80, 0x4b, 0x7a, 0x00, 0x01, 0x38, 0x20, 0x80, 0xe8, 0x05, 0xff, 0x60, 0x18, 0xe0, 0x1d,
+        0x80, 0x98, 0x01, 0xf8, 0x06, 0x00, 0x54, 0x02, 0xc0, 0x18, 0x14, 0x03, 0xb2, 0x92, 0x80,
+        0xc0, 0x18, 0x94, 0x42, 0x2c, 0xb2, 0x11, 0x64, 0xa0, 0x12, 0x5e, 0x78, 0x03, 0x3c, 0x01,
+        0x80, 0x0e, 0x80, 0x18, 0x80, 0x6b, 0xca, 0x4e, 0x01, 0x0f, 0xe4, 0x32, 0xc9, 0xbf, 0x01,
+        0x42, 0x69, 0x43, 0x50, 0x4b, 0x01, 0xc9, 0x45, 0x80, 0x50, 0x01, 0x38, 0x65, 0xe8, 0x01,
+        0x03, 0xf3, 0xc0, 0x76, 0x00, 0xe0, 0x03, 0x20, 0x28, 0x18, 0x01, 0xa9, 0x34, 0x04, 0xc5,
+        0xe0, 0x0b, 0x0b, 0x04, 0x20, 0x06, 0xc0, 0x89, 0xff, 0x60, 0x12, 0x12, 0x8a, 0x2c, 0x34,
+        0x11, 0xff, 0xf6, 0xe2, 0x40, 0xc0, 0x30, 0x1b, 0x7a, 0x01, 0xa9, 0x0d, 0x00, 0xac, 0x64,
+    ];
+
+    let picture_coding_extension =
+        MPEG2PictureCodingExtension::new(0, 3, 0, 1, 0, 0, 0, 0, 0, 1, 1);
+    let pic_param = PictureParameterBufferMPEG2::new(
+        16,
+        16,
+        0xffffffff,
+        0xffffffff,
+        1,
+        0xffff,
+        &picture_coding_extension,
+    );
+
+    let iq_matrix = IQMatrixBufferMPEG2::new(
+        1,
+        1,
+        0,
+        0,
+        [
+            8, 16, 16, 19, 16, 19, 22, 22, 22, 22, 22, 22, 26, 24, 26, 27, 27, 27, 26, 26, 26, 26,
+            27, 27, 27, 29, 29, 29, 34, 34, 34, 29, 29, 29, 27, 27, 29, 29, 32, 32, 34, 34, 37, 38,
+            37, 35, 35, 34, 35, 38, 38, 40, 40, 40, 48, 48, 46, 46, 56, 56, 58, 69, 69, 83,
+        ],
+        [
+            16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ],
+        [0; 64],
+        [0; 64],
+    );
+
+    let slice_param = SliceParameterBufferMPEG2::new(150, 0, 0, 38, 0, 0, 2, 0);
+
+    vec![
+        BufferType::PictureParameter(PictureParameter::MPEG2(pic_param)),
+        BufferType::SliceParameter(SliceParameter::MPEG2(slice_param)),
+        BufferType::IQMatrix(IQMatrix::MPEG2(iq_matrix)),
+        BufferType::SliceData(slice_data),
+    ]
+}
@@ -0,0 +1,161 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to track presentation and decoding timestamps across frame reordering.
+//!
+//! [`crate::Picture`] only carries a single timestamp, which is not enough to mux streams with
+//! B-frames, where the decoding order differs from the presentation order.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::VecDeque;
+
+/// Timestamps of a frame, in the time base of the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTimestamps {
+    /// Presentation timestamp.
+    pub pts: u64,
+    /// Decoding timestamp. It can be negative for the first frames of a stream with reordering,
+    /// so that it never exceeds the presentation timestamp.
+    pub dts: i64,
+}
+
+/// Assigns decoding timestamps to encoded frames.
+///
+/// Frames are submitted to the encoder in presentation order with [`Self::push_input`], and come
+/// out in coding order. For each output frame, [`Self::pop_output`] returns its timestamps, the
+/// decoding timestamps being the presentation timestamps of the input frames delayed by the
+/// reorder depth of the stream.
+#[derive(Debug)]
+pub struct EncodeTimestampTracker {
+    reorder_depth: usize,
+    frame_duration: u64,
+    input_pts: VecDeque<u64>,
+    first_pts: Option<u64>,
+    num_output: usize,
+}
+
+impl EncodeTimestampTracker {
+    /// Creates a tracker for a stream where frames are coded at most `reorder_depth` frames ahead
+    /// of their presentation, e.g. the number of consecutive B-frames. `frame_duration` is used
+    /// to extrapolate the decoding timestamps of the first frames.
+    pub fn new(reorder_depth: usize, frame_duration: u64) -> Self {
+        Self {
+            reorder_depth,
+            frame_duration,
+            input_pts: Default::default(),
+            first_pts: None,
+            num_output: 0,
+        }
+    }
+
+    /// Records the presentation timestamp of a frame submitted to the encoder.
+    pub fn push_input(&mut self, pts: u64) {
+        self.first_pts.get_or_insert(pts);
+        self.input_pts.push_back(pts);
+    }
+
+    /// Returns the timestamps of the next encoded frame, whose presentation timestamp is `pts`.
+    pub fn pop_output(&mut self, pts: u64) -> FrameTimestamps {
+        let dts = if self.num_output < self.reorder_depth {
+            let first_pts = self.first_pts.unwrap_or(pts) as i64;
+            let frames_ahead = (self.reorder_depth - self.num_output) as i64;
+
+            first_pts - frames_ahead * self.frame_duration as i64
+        } else {
+            self.input_pts.pop_front().unwrap_or(pts) as i64
+        };
+        self.num_output += 1;
+
+        FrameTimestamps { pts, dts }
+    }
+
+    /// Resets the tracker, e.g. when the encoder is flushed.
+    pub fn reset(&mut self) {
+        self.input_pts.clear();
+        self.first_pts = None;
+        self.num_output = 0;
+    }
+}
+
+/// Assigns presentation timestamps to decoded frames.
+///
+/// Packets are submitted to the decoder in decoding order with [`Self::push_input`], and frames
+/// come out in presentation order. Since presentation timestamps are monotonic in presentation
+/// order, each output frame gets the smallest presentation timestamp among the pending ones.
+#[derive(Debug, Default)]
+pub struct DecodeTimestampTracker {
+    pending: BinaryHeap<Reverse<(u64, i64)>>,
+}
+
+impl DecodeTimestampTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records the timestamps of a packet submitted to the decoder.
+    pub fn push_input(&mut self, timestamps: FrameTimestamps) {
+        self.pending.push(Reverse((timestamps.pts, timestamps.dts)));
+    }
+
+    /// Returns the timestamps of the next frame output by the decoder, or `None` if no packet is
+    /// pending.
+    pub fn pop_output(&mut self) -> Option<FrameTimestamps> {
+        self.pending
+            .pop()
+            .map(|Reverse((pts, dts))| FrameTimestamps { pts, dts })
+    }
+
+    /// Returns the number of packets whose frame has not been output yet.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether all the frames of the submitted packets have been output.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Resets the tracker, e.g. when seeking.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_timestamps() {
+        // I0 B1 B2 P3 in presentation order, coded as I0 P3 B1 B2.
+        let mut tracker = EncodeTimestampTracker::new(1, 10);
+        for pts in [0, 10, 20, 30] {
+            tracker.push_input(pts);
+        }
+
+        let output: Vec<_> = [0, 30, 10, 20]
+            .into_iter()
+            .map(|pts| tracker.pop_output(pts))
+            .collect();
+        let dts: Vec<_> = output.iter().map(|t| t.dts).collect();
+        assert_eq!(dts, vec![-10, 0, 10, 20]);
+        assert!(output.iter().all(|t| t.dts <= t.pts as i64));
+    }
+
+    #[test]
+    fn decode_timestamps() {
+        let mut tracker = DecodeTimestampTracker::new();
+        for (pts, dts) in [(0, -10), (30, 0), (10, 10), (20, 20)] {
+            tracker.push_input(FrameTimestamps { pts, dts });
+        }
+
+        let pts: Vec<_> = std::iter::from_fn(|| tracker.pop_output())
+            .map(|t| t.pts)
+            .collect();
+        assert_eq!(pts, vec![0, 10, 20, 30]);
+        assert!(tracker.is_empty());
+    }
+}
@@ -8,6 +8,9 @@ use crate::bindings;
 
 bitflags! {
     /// Gives the driver a hint of intended usage to optimize allocation (e.g. tiling).
+    ///
+    /// libva has no hint for protected surfaces: surfaces are made secure by attaching a
+    /// `ProtectedSession` to the context processing them.
     #[derive(Debug, Clone, Copy)]
     pub struct UsageHint: u32 {
         /// Surface used by video decoder.
@@ -0,0 +1,128 @@
+// Copyright 2025 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers to import exported surfaces into Vulkan.
+//!
+//! The types in this module map an exported [`DrmPrimeSurfaceDescriptor`] to the parameters of
+//! the `VK_EXT_image_drm_format_modifier` and `VK_KHR_external_memory_fd` extensions, without
+//! depending on any particular Vulkan binding.
+
+use std::os::fd::OwnedFd;
+
+use thiserror::Error;
+
+use crate::DrmPrimeSurfaceDescriptor;
+use crate::Surface;
+use crate::SurfaceMemoryDescriptor;
+use crate::VaError;
+
+#[derive(Debug, Error)]
+pub enum VulkanImportError {
+    #[error("error while exporting surface: {0}")]
+    VaError(#[from] VaError),
+    #[error("the descriptor has no layer")]
+    NoLayer,
+    #[error("the descriptor has {0} layers, but a single composed layer is required")]
+    MultipleLayers(usize),
+    #[error("plane {plane} refers to object {index} which does not exist")]
+    InvalidObjectIndex { plane: usize, index: u8 },
+}
+
+/// A memory object to import, i.e. the parameters of `VkImportMemoryFdInfoKHR` with the
+/// `VK_EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT` handle type.
+pub struct VulkanMemoryImport {
+    /// DMA-BUF to import. Vulkan takes ownership of it upon successful import, so it should be
+    /// passed using `IntoRawFd`.
+    pub fd: OwnedFd,
+    /// Size of the memory, to use as `VkMemoryAllocateInfo::allocationSize`.
+    pub size: u64,
+}
+
+/// Layout of a plane, i.e. the members of `VkSubresourceLayout` used with
+/// `VkImageDrmFormatModifierExplicitCreateInfoEXT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VulkanPlaneLayout {
+    /// Index of the memory object in [`VulkanImageImport::memories`] containing the plane.
+    pub memory_index: usize,
+    /// Offset of the plane within its memory object.
+    pub offset: u64,
+    /// Number of bytes between two consecutive lines of the plane.
+    pub row_pitch: u64,
+}
+
+/// Everything needed to create a Vulkan image from an exported surface.
+pub struct VulkanImageImport {
+    /// DRM format (`DRM_FORMAT_*`) of the image, to be converted to a `VkFormat`.
+    pub drm_format: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Modifier to pass to `VkImageDrmFormatModifierExplicitCreateInfoEXT`.
+    pub drm_format_modifier: u64,
+    /// Memory objects to import and bind to the image.
+    pub memories: Vec<VulkanMemoryImport>,
+    /// Layout of each plane of the image.
+    pub planes: Vec<VulkanPlaneLayout>,
+}
+
+impl VulkanImageImport {
+    /// Converts `desc`, which must have been exported with a single composed layer, into Vulkan
+    /// import parameters.
+    pub fn from_prime(desc: DrmPrimeSurfaceDescriptor) -> Result<Self, VulkanImportError> {
+        let layer = match desc.layers.as_slice() {
+            [] => return Err(VulkanImportError::NoLayer),
+            [layer] => layer,
+            layers => return Err(VulkanImportError::MultipleLayers(layers.len())),
+        };
+
+        let planes = (0..layer.num_planes.min(4) as usize)
+            .map(|plane| {
+                let index = layer.object_index[plane];
+                if index as usize >= desc.objects.len() {
+                    return Err(VulkanImportError::InvalidObjectIndex { plane, index });
+                }
+
+                Ok(VulkanPlaneLayout {
+                    memory_index: index as usize,
+                    offset: layer.offset[plane].into(),
+                    row_pitch: layer.pitch[plane].into(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let drm_format = layer.drm_format;
+        let drm_format_modifier = desc.drm_format_modifier().unwrap_or_default();
+
+        Ok(Self {
+            drm_format,
+            width: desc.width,
+            height: desc.height,
+            drm_format_modifier,
+            memories: desc
+                .objects
+                .into_iter()
+                .map(|object| VulkanMemoryImport {
+                    fd: object.fd,
+                    size: object.size.into(),
+                })
+                .collect(),
+            planes,
+        })
+    }
+
+    /// Returns whether the planes are spread over several memory objects, in which case the image
+    /// must be created with `VK_IMAGE_CREATE_DISJOINT_BIT`.
+    pub fn is_disjoint(&self) -> bool {
+        self.planes
+            .iter()
+            .any(|plane| plane.memory_index != self.planes[0].memory_index)
+    }
+}
+
+impl<D: SurfaceMemoryDescriptor> Surface<D> {
+    /// Exports this surface for reading and returns the parameters needed to import it as a
+    /// Vulkan image.
+    pub fn export_vulkan(&self) -> Result<VulkanImageImport, VulkanImportError> {
+        VulkanImageImport::from_prime(self.export_prime()?)
+    }
+}